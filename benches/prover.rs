@@ -0,0 +1,109 @@
+//! Witness generation, key generation, proving and verifying across
+//! sequence lengths and `k`, so layout changes can be measured. Run with
+//! `cargo bench`; the IPA parameters for each `k` are generated once, up
+//! front, and not timed.
+
+// Build against halo2_proofs 0.4 under the usual crate name; see `compat`.
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_proofs_0_4 as halo2_proofs;
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::poly::commitment::Params;
+
+use fibonacci_variant::sequence::witnesses;
+use fibonacci_variant::{min_k_for, prover, witness, FiboCircuit, FiboSpec};
+
+/// Sequence lengths for the proving benches, each at its smallest `k` and
+/// two above it.
+const NUMS: [usize; 3] = [10, 100, 1000];
+const EXTRA_K: [u32; 2] = [0, 2];
+
+fn circuit(num: usize) -> FiboCircuit<Fp> {
+    FiboCircuit {
+        spec: FiboSpec { num, ..FiboSpec::default() },
+        a: Value::known(Fp::from(1)),
+        b: Value::known(Fp::from(2)),
+        c: Value::known(Fp::from(3)),
+        witness: None,
+    }
+}
+
+fn result(num: usize) -> Fp {
+    witness(Fp::from(1), Fp::from(2), Fp::from(3), num)[num - 1]
+}
+
+/// Every `(num, k)` the proving benches run.
+fn cases() -> Vec<(usize, u32)> {
+    NUMS.iter()
+        .flat_map(|&num| EXTRA_K.iter().map(move |&extra| (num, min_k_for(num) + extra)))
+        .collect()
+}
+
+fn bench_witness(c: &mut Criterion) {
+    let mut group = c.benchmark_group("witness");
+    for num in [100, 1_000, 10_000, 100_000] {
+        group.bench_with_input(BenchmarkId::from_parameter(num), &num, |bench, &num| {
+            bench.iter(|| witness(Fp::from(1), Fp::from(2), Fp::from(3), black_box(num)))
+        });
+    }
+    group.finish();
+
+    // 10,000 terms for each of several sequences, in parallel with
+    // `multicore`; compare one sequence for the time of a single thread.
+    let mut group = c.benchmark_group("witnesses");
+    for count in [1, 4, 16] {
+        let sequences: Vec<_> = (0..count).map(|a| ([Fp::from(a), Fp::from(2), Fp::from(3)], 10_000)).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(count), &sequences, |bench, sequences| {
+            bench.iter(|| witnesses(black_box(sequences)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_proofs(c: &mut Criterion) {
+    let cases = cases();
+    let params: BTreeMap<u32, Params<EqAffine>> = cases.iter().map(|&(_, k)| (k, Params::new(k))).collect();
+
+    let mut group = c.benchmark_group("keygen");
+    group.sample_size(10);
+    for &(num, k) in &cases {
+        let circuit = circuit(num);
+        group.bench_function(format!("num={}/k={}", num, k), |bench| {
+            bench.iter(|| prover::keygen(&params[&k], &circuit).unwrap())
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("prove");
+    group.sample_size(10);
+    for &(num, k) in &cases {
+        let circuit = circuit(num);
+        let pk = prover::keygen(&params[&k], &circuit).unwrap();
+        let instance = [result(num)];
+        group.bench_function(format!("num={}/k={}", num, k), |bench| {
+            bench.iter(|| prover::prove(&params[&k], &pk, &circuit, &instance).unwrap())
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("verify");
+    group.sample_size(20);
+    for &(num, k) in &cases {
+        let circuit = circuit(num);
+        let pk = prover::keygen(&params[&k], &circuit).unwrap();
+        let instance = [result(num)];
+        let proof = prover::prove(&params[&k], &pk, &circuit, &instance).unwrap();
+        group.bench_function(format!("num={}/k={}", num, k), |bench| {
+            bench.iter(|| prover::verify(&params[&k], pk.get_vk(), &proof, &instance).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_witness, bench_proofs);
+criterion_main!(benches);
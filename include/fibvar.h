@@ -0,0 +1,35 @@
+/* The C interface of fibonacci_variant, built with `--features ffi`; see
+ * src/ffi.rs for the ownership rules. */
+#ifndef FIBVAR_H
+#define FIBVAR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define FIBVAR_OK 0
+#define FIBVAR_INVALID_ARGUMENT 1
+#define FIBVAR_IO_ERROR 2
+#define FIBVAR_PROOF_ERROR 3
+#define FIBVAR_PANIC 4
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Proves the `num` terms from `a`, `b`, `c` (decimal or 0x hex) and stores a
+ * proof the caller owns in `*proof`, `*proof_len`. `params_dir` may be NULL
+ * for "params". */
+int fibvar_prove(const char *a, const char *b, const char *c, size_t num,
+                 const char *params_dir, uint8_t **proof, size_t *proof_len);
+
+/* Checks a proof against the public inputs it carries. */
+int fibvar_verify(const uint8_t *proof, size_t proof_len, const char *params_dir);
+
+/* Frees a proof from fibvar_prove. NULL is ignored. */
+void fibvar_free_proof(uint8_t *proof, size_t proof_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
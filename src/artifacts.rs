@@ -0,0 +1,155 @@
+//! What proving or verifying one shape costs to set up, kept for the next
+//! proof of that shape. `CircuitArtifacts` holds the parameters for each
+//! `k` it has needed, on top of the on-disk `ParamsCache`, and for each
+//! `k` and spec the configured constraint system, the verifying key and its
+//! fingerprint, and, once something has been proven, a `ProverContext`.
+//! The server keeps one for as long as it runs and `verify-batch` one per
+//! run; entries stay until `invalidate`, `invalidate_k` or `clear` drops
+//! them, e.g. after the parameters for a `k` are replaced on disk.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, VerifyingKey};
+use halo2_proofs::poly::commitment::Params;
+
+use crate::circuit::{FiboCircuit, FiboSpec};
+use crate::context::{ProverContext, Setup};
+use crate::dispatch::{with_circuit, WithCircuit};
+use crate::error::FibError;
+use crate::fingerprint::VkHash;
+use crate::params::ParamsCache;
+use crate::proof::verifying_key;
+
+/// One key's value, once made.
+type Slot<V> = Arc<Mutex<Option<Arc<V>>>>;
+
+type Slots<K, V> = Mutex<HashMap<K, Slot<V>>>;
+
+/// Looks `key` up in `map`, making and keeping the value on a miss. The map
+/// is locked only to find the key's slot, and `make` runs under the slot's
+/// own lock: concurrent misses on one key make it once, and lookups of
+/// other keys are not held up behind it.
+fn cached<K: Eq + Hash, V, E>(
+    map: &Slots<K, V>,
+    key: K,
+    make: impl FnOnce() -> Result<V, E>,
+) -> Result<Arc<V>, E> {
+    // Every value is whole once inserted, so a panic elsewhere leaves the
+    // map and the slots usable.
+    let slot = map.lock().unwrap_or_else(PoisonError::into_inner).entry(key).or_default().clone();
+    let mut slot = slot.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(value) = &*slot {
+        return Ok(value.clone());
+    }
+    let value = Arc::new(make()?);
+    *slot = Some(value.clone());
+    Ok(value)
+}
+
+/// The value in `slot`, unless it is still being made.
+fn filled<V>(slot: &Slot<V>) -> Option<Arc<V>> {
+    slot.try_lock().ok().and_then(|value| value.clone())
+}
+
+/// What verifying a shape needs besides the parameters.
+pub struct Artifacts {
+    pub k: u32,
+    pub spec: FiboSpec,
+    /// The constraint system `configure` builds for the spec's circuit.
+    pub cs: ConstraintSystem<Fp>,
+    pub vk: VerifyingKey<EqAffine>,
+    pub vk_hash: VkHash,
+}
+
+/// Parameters, constraint systems and keys per shape, made on first use and
+/// shared by every caller, across threads, until invalidated.
+pub struct CircuitArtifacts {
+    cache: ParamsCache,
+    params: Slots<u32, Params<EqAffine>>,
+    verifying: Slots<(u32, FiboSpec), Artifacts>,
+    provers: Slots<(u32, FiboSpec), ProverContext>,
+}
+
+impl CircuitArtifacts {
+    pub fn new(cache: ParamsCache) -> Self {
+        Self { cache, params: Mutex::default(), verifying: Mutex::default(), provers: Mutex::default() }
+    }
+
+    pub fn params_cache(&self) -> &ParamsCache {
+        &self.cache
+    }
+
+    /// The parameters for `k`, read or generated by the `ParamsCache` once.
+    pub fn params(&self, k: u32) -> Result<Arc<Params<EqAffine>>, FibError> {
+        cached(&self.params, k, || Ok(self.cache.get(k)?))
+    }
+
+    /// The constraint system and verifying key for `spec` at `k`, without a
+    /// proving key. The spec is checked against `k` before any are made.
+    pub fn artifacts(&self, k: u32, spec: FiboSpec) -> Result<Arc<Artifacts>, FibError> {
+        spec.check_num()?;
+        spec.check_k(k)?;
+        cached(&self.verifying, (k, spec), || {
+            let params = self.params(k)?;
+            let vk = match self.cached_prover(k, spec) {
+                Some(prover) => prover.keys().verifying_key().clone(),
+                None => verifying_key(&params, spec)?,
+            };
+            let cs = with_circuit(FiboCircuit { spec, ..FiboCircuit::default() }, Configure)?;
+            Ok(Artifacts { k, spec, cs, vk_hash: VkHash::of(&vk), vk })
+        })
+    }
+
+    /// A prover for `spec` at `k`, its keys made once.
+    pub fn prover(&self, k: u32, spec: FiboSpec) -> Result<Arc<ProverContext>, FibError> {
+        spec.check_num()?;
+        spec.check_k(k)?;
+        cached(&self.provers, (k, spec), || Setup::new(self.params(k)?).keys(spec).map(|keys| keys.prover()))
+    }
+
+    fn cached_prover(&self, k: u32, spec: FiboSpec) -> Option<Arc<ProverContext>> {
+        let slot = self.provers.lock().unwrap_or_else(PoisonError::into_inner).get(&(k, spec)).cloned()?;
+        filled(&slot)
+    }
+
+    /// The artifacts already made whose verifying key has `vk_hash`.
+    pub fn find(&self, vk_hash: VkHash) -> Option<Arc<Artifacts>> {
+        let slots: Vec<_> = self.verifying.lock().unwrap_or_else(PoisonError::into_inner).values().cloned().collect();
+        slots.iter().filter_map(filled).find(|artifacts| artifacts.vk_hash == vk_hash)
+    }
+
+    /// Drops everything kept for `spec` at `k`; the next use makes it anew.
+    pub fn invalidate(&self, k: u32, spec: FiboSpec) {
+        self.verifying.lock().unwrap_or_else(PoisonError::into_inner).remove(&(k, spec));
+        self.provers.lock().unwrap_or_else(PoisonError::into_inner).remove(&(k, spec));
+    }
+
+    /// Drops the parameters for `k` and everything made under them.
+    pub fn invalidate_k(&self, k: u32) {
+        self.params.lock().unwrap_or_else(PoisonError::into_inner).remove(&k);
+        self.verifying.lock().unwrap_or_else(PoisonError::into_inner).retain(|&(at, _), _| at != k);
+        self.provers.lock().unwrap_or_else(PoisonError::into_inner).retain(|&(at, _), _| at != k);
+    }
+
+    pub fn clear(&self) {
+        self.params.lock().unwrap_or_else(PoisonError::into_inner).clear();
+        self.verifying.lock().unwrap_or_else(PoisonError::into_inner).clear();
+        self.provers.lock().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+}
+
+/// `configure` for whichever circuit the spec calls for.
+struct Configure;
+
+impl WithCircuit for Configure {
+    type Output = ConstraintSystem<Fp>;
+
+    fn with<C: Circuit<Fp>>(self, _: C) -> Self::Output {
+        let mut cs = ConstraintSystem::default();
+        C::configure(&mut cs);
+        cs
+    }
+}
@@ -0,0 +1,161 @@
+//! Choosing the commitment scheme at run time. IPA over the Pasta curves,
+//! the `prover` and `proof` modules, is always built; KZG over bn256, the
+//! `kzg` module, with the `kzg` feature. The two name their proofs apart
+//! by the scheme byte of the header, so one `verify` reads either kind and
+//! checks it under its own parameters from the `ParamsCache`.
+
+use std::{fmt, io, str::FromStr};
+
+use crate::circuit::FiboSpec;
+use crate::error::FibError;
+#[cfg(feature = "kzg")]
+use crate::kzg::KzgProof;
+use crate::params::ParamsCache;
+#[cfg(not(feature = "kzg"))]
+use crate::proof::invalid;
+use crate::proof::{from_hex, Proof, KZG_SCHEME, SCHEME_OFFSET};
+
+/// Which commitment scheme proofs are made with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// IPA over the Pasta curves: `prover` and `Proof`.
+    #[default]
+    Ipa,
+    /// KZG over bn256: `kzg` and `KzgProof`, with the `kzg` feature.
+    Kzg,
+}
+
+impl Backend {
+    /// The name `FromStr` accepts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Ipa => "ipa",
+            Backend::Kzg => "kzg",
+        }
+    }
+
+    /// Whether this build can prove and verify with it.
+    pub fn is_built(&self) -> bool {
+        match self {
+            Backend::Ipa => true,
+            Backend::Kzg => cfg!(feature = "kzg"),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Ipa => write!(f, "IPA over the Pasta curves"),
+            Backend::Kzg => write!(f, "KZG over bn256"),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ipa" => Ok(Backend::Ipa),
+            "kzg" => Ok(Backend::Kzg),
+            _ => Err(format!("unknown backend {:?}, expected \"ipa\" or \"kzg\"", s)),
+        }
+    }
+}
+
+/// A proof of either backend, as its header says.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AnyProof {
+    Ipa(Proof),
+    #[cfg(feature = "kzg")]
+    Kzg(KzgProof),
+}
+
+impl AnyProof {
+    /// Reads a proof that must span all of `bytes`, by its scheme byte. A
+    /// KZG proof is an error if the `kzg` feature is off.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        match bytes.get(SCHEME_OFFSET) {
+            #[cfg(feature = "kzg")]
+            Some(&KZG_SCHEME) => KzgProof::from_bytes(bytes).map(AnyProof::Kzg),
+            #[cfg(not(feature = "kzg"))]
+            Some(&KZG_SCHEME) => Err(invalid("a KZG proof, and this build has no kzg feature")),
+            _ => Proof::from_bytes(bytes).map(AnyProof::Ipa),
+        }
+    }
+
+    /// Parses the hex encoding; surrounding whitespace is ignored.
+    pub fn from_hex(hex: &str) -> io::Result<Self> {
+        Self::from_bytes(&from_hex(hex)?)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            AnyProof::Ipa(proof) => proof.to_bytes(),
+            #[cfg(feature = "kzg")]
+            AnyProof::Kzg(proof) => proof.to_bytes(),
+        }
+    }
+
+    pub fn to_hex(&self) -> String {
+        match self {
+            AnyProof::Ipa(proof) => proof.to_hex(),
+            #[cfg(feature = "kzg")]
+            AnyProof::Kzg(proof) => proof.to_hex(),
+        }
+    }
+
+    /// The proof bytes alone, without the header and instance.
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            AnyProof::Ipa(proof) => &proof.bytes,
+            #[cfg(feature = "kzg")]
+            AnyProof::Kzg(proof) => &proof.bytes,
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self {
+            AnyProof::Ipa(_) => Backend::Ipa,
+            #[cfg(feature = "kzg")]
+            AnyProof::Kzg(_) => Backend::Kzg,
+        }
+    }
+
+    pub fn k(&self) -> u32 {
+        match self {
+            AnyProof::Ipa(proof) => proof.k,
+            #[cfg(feature = "kzg")]
+            AnyProof::Kzg(proof) => proof.k,
+        }
+    }
+
+    pub fn spec(&self) -> FiboSpec {
+        match self {
+            AnyProof::Ipa(proof) => proof.spec,
+            #[cfg(feature = "kzg")]
+            AnyProof::Kzg(proof) => proof.spec,
+        }
+    }
+
+    /// `Proof::check_shape` or `KzgProof::check_shape`.
+    pub fn check_shape(&self) -> Result<(), FibError> {
+        match self {
+            AnyProof::Ipa(proof) => proof.check_shape(),
+            #[cfg(feature = "kzg")]
+            AnyProof::Kzg(proof) => proof.check_shape(),
+        }
+    }
+
+    /// Checks the shape, then verifies under the backend's parameters for
+    /// the proof's `k` from `cache`.
+    pub fn verify(&self, cache: &ParamsCache) -> Result<(), FibError> {
+        self.check_shape()?;
+        match self {
+            AnyProof::Ipa(proof) => proof.verify(&cache.get(proof.k)?),
+            #[cfg(feature = "kzg")]
+            AnyProof::Kzg(proof) => proof.verify(&cache.get_kzg(proof.k)?),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Many independent sequences in one proof. Each member is a `FiboCircuit`
+//! of its own length and output, laid out in its own rows below the one
+//! before it, on one shared step chip; its public values follow those of
+//! the member before it on the instance column. The members' witnesses are
+//! independent too, so they are computed in parallel with `multicore`.
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+use crate::chip::{FiboChip, FiboConfig};
+use crate::circuit::{synthesize_sequence, FiboCircuit, Layout};
+use crate::compat::par_map;
+use crate::recurrence::Recurrence;
+
+/// `circuits` side by side in one circuit, each in the four-column layout
+/// and for the variant. Only the members' specs shape the circuit.
+#[derive(Clone, Default)]
+pub struct BatchFiboCircuit<F> {
+    pub circuits: Vec<FiboCircuit<F>>,
+}
+
+impl<F: Field> BatchFiboCircuit<F> {
+    /// The first instance row of each member's public values.
+    pub fn instance_offsets(&self) -> Vec<usize> {
+        self.circuits
+            .iter()
+            .scan(0, |offset, circuit| {
+                let start = *offset;
+                *offset += circuit.spec.output.instance_rows();
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// Computes every member's witness up front, in parallel, so proving
+    /// only assigns cells; see `FiboCircuit::from_witness`.
+    pub fn with_witnesses(mut self) -> Self {
+        let witnesses = par_map(&self.circuits, FiboCircuit::compute_witness);
+        for (circuit, witness) in self.circuits.iter_mut().zip(witnesses) {
+            circuit.witness = Some(witness);
+        }
+        self
+    }
+
+    /// Rows the members occupy together.
+    pub fn rows(&self) -> usize {
+        self.circuits.iter().map(|circuit| circuit.spec.rows()).sum()
+    }
+
+    /// Instance rows the members' public values take together.
+    pub fn instance_rows(&self) -> usize {
+        self.circuits.iter().map(|circuit| circuit.spec.output.instance_rows()).sum()
+    }
+
+    /// The smallest `k` whose `2^k` rows fit every member, or their public
+    /// values if those take more rows, and the rows halo2 reserves for
+    /// blinding; `FiboSpec::min_k` for the batch.
+    pub fn min_k(&self) -> u32 {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        FiboChip::configure(&mut cs);
+        let used = self.rows().max(self.instance_rows());
+        let required = (used + cs.blinding_factors() + 1).max(cs.minimum_rows());
+        required.next_power_of_two().trailing_zeros()
+    }
+}
+
+impl<F: Field> Circuit<F> for BatchFiboCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            circuits: self.circuits.iter().map(|circuit| circuit.without_witnesses()).collect(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let witnesses = par_map(&self.circuits, FiboCircuit::full_witness);
+        let members = self.circuits.iter().zip(witnesses).zip(self.instance_offsets());
+        for (i, ((circuit, witness), offset)) in members.enumerate() {
+            let spec = &circuit.spec;
+            if spec.layout != Layout::Rows || spec.recurrence != Recurrence::Variant {
+                return Err(Error::Synthesis);
+            }
+            let chip = FiboChip::construct_with_offset(config, offset);
+            synthesize_sequence(&chip, spec, &witness, layouter.namespace(|| format!("member {}", i)))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,419 @@
+//! The step chip: one row per term, with a gate checking each new term
+//! against the three before it. Rows are either their own regions, tied
+//! together by copy constraints (`load_first_row`, `load_row`), or one
+//! region tied together by the chain gate (`load_sequence`).
+
+use std::{fmt, marker::PhantomData};
+use halo2_proofs::circuit::{Value, Layouter, AssignedCell, Region};
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::{plonk::*};
+use halo2_proofs::arithmetic::Field;
+
+use crate::recurrence::{RecurrenceGate, Variant};
+
+pub const GATE_NAME: &str = "mul add gate";
+pub const CHAIN_GATE_NAME: &str = "chain gate";
+
+/// A term of the sequence, assigned in some region.
+#[derive(Clone)]
+pub struct Number<F: Field>(pub AssignedCell<F, F>);
+
+/// Cells `load_sequence` hands back for exposing.
+pub struct SequenceCells<F: Field> {
+    /// `a, b, c` of the first row: the initial values.
+    pub initial: [Number<F>; 3],
+    /// `b, c, d` of the last row: the last three terms.
+    pub last: [Number<F>; 3],
+    /// Every term, in order; empty from `load_sequence_streaming`, which
+    /// keeps none of them.
+    pub terms: Vec<Number<F>>,
+}
+
+impl<F: Field> SequenceCells<F> {
+    /// What a sequence of `num` terms ends on: the last term laid out, or,
+    /// for fewer than four, one of the initial values.
+    pub fn result(&self, num: usize) -> Result<&Number<F>, Error> {
+        match num {
+            0 => Err(Error::Synthesis),
+            1..=3 => Ok(&self.initial[num - 1]),
+            _ => Ok(&self.last[2]),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Copy)]
+pub struct FiboConfig {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub d: Column<Advice>,
+    pub i: Column<Instance>,
+    pub s: Selector,
+    /// Rows of a `load_sequence` region after the first: `a, b, c` equal
+    /// `b, c, d` of the row above.
+    pub s_chain: Selector,
+}
+
+impl fmt::Display for FiboConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "advice a, b, c (previous terms) and d (next term), instance i (result), \
+             gate \"{}\": (a + c) * b = d, \"{}\": a, b, c = b, c, d of the row above",
+            GATE_NAME,
+            CHAIN_GATE_NAME,
+        )
+    }
+}
+
+pub struct FiboChip<F: Field> {
+    config: FiboConfig,
+    /// First row of the instance column owned by this chip.
+    instance_offset: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> FiboChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self::construct_with_offset(config, 0)
+    }
+
+    /// Constructs a chip whose public outputs start at `instance_offset`, so
+    /// several chips can expose values on the same instance column.
+    pub fn construct_with_offset(config: FiboConfig, instance_offset: usize) -> Self {
+        Self {
+            config,
+            instance_offset,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+    ) -> FiboConfig {
+        Self::configure_recurrence::<Variant>(meta)
+    }
+
+    /// `configure`, with the step gate checking `G` instead of the variant.
+    pub fn configure_recurrence<G: RecurrenceGate<F>>(
+        meta: &mut ConstraintSystem<F>,
+    ) -> FiboConfig {
+        let advice = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let instance = meta.instance_column();
+        // Backs `load_constant`, e.g. the term count of a continuation.
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+        Self::configure_recurrence_with::<G>(meta, advice, instance)
+    }
+
+    /// Configures the chip on columns the caller already allocated, so a host
+    /// circuit can share them with its other gadgets. Equality is enabled on
+    /// all of them; only the selectors are allocated here.
+    pub fn configure_with(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        instance: Column<Instance>,
+    ) -> FiboConfig {
+        Self::configure_recurrence_with::<Variant>(meta, advice, instance)
+    }
+
+    /// `configure_with`, with the step gate checking `G`.
+    pub fn configure_recurrence_with<G: RecurrenceGate<F>>(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        instance: Column<Instance>,
+    ) -> FiboConfig {
+        let _span = tracing::info_span!("configure", gate = G::NAME).entered();
+        Self::configure_step_with(meta, advice, instance, G::NAME, |_, [a, b, c]| {
+            G::expression(a, b, c)
+        })
+    }
+
+    /// `configure_with`, with the step gate `name` checking `d` against
+    /// `step` of the queried `a, b, c`. `step` may query further columns of
+    /// the row, such as fixed coefficients.
+    pub fn configure_step_with(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 4],
+        instance: Column<Instance>,
+        name: &'static str,
+        step: impl FnOnce(&mut VirtualCells<'_, F>, [Expression<F>; 3]) -> Expression<F>,
+    ) -> FiboConfig {
+        let [a, b, c, d] = advice;
+        let i = instance;
+        let s = meta.selector();
+        let s_chain = meta.selector();
+
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(c);
+        meta.enable_equality(d);
+        meta.enable_equality(i);
+
+        meta.create_gate(name, |meta| {
+            let s = meta.query_selector(s);
+            let a_tmp = meta.query_advice(a, Rotation::cur());
+            let b_tmp = meta.query_advice(b, Rotation::cur());
+            let c_tmp = meta.query_advice(c, Rotation::cur());
+            let d_tmp = meta.query_advice(d, Rotation::cur());
+            vec![s * (step(meta, [a_tmp, b_tmp, c_tmp]) - d_tmp)]
+        });
+
+        meta.create_gate(CHAIN_GATE_NAME, |meta| {
+            let s = meta.query_selector(s_chain);
+            let b_prev = meta.query_advice(b, Rotation::prev());
+            let c_prev = meta.query_advice(c, Rotation::prev());
+            let d_prev = meta.query_advice(d, Rotation::prev());
+            let a_tmp = meta.query_advice(a, Rotation::cur());
+            let b_tmp = meta.query_advice(b, Rotation::cur());
+            let c_tmp = meta.query_advice(c, Rotation::cur());
+            vec![
+                s.clone() * (a_tmp - b_prev),
+                s.clone() * (b_tmp - c_prev),
+                s * (c_tmp - d_prev),
+            ]
+        });
+
+        FiboConfig {
+            a, b, c, d, i, s, s_chain,
+        }
+    }
+
+    /// `SequenceChip::load_sequence`, calling `assign_row` on every row for
+    /// the cells of the host circuit's own columns, e.g. the fixed columns a
+    /// `configure_step_with` gate reads.
+    pub fn load_sequence_with(
+        &self,
+        mut layouter: impl Layouter<F>,
+        witness: &[Value<F>],
+        assign_row: impl Fn(&mut Region<'_, F>, usize) -> Result<(), Error>,
+    ) -> Result<SequenceCells<F>, Error> {
+        layouter.assign_region(
+            || "sequence",
+            |mut region| {
+                let mut first = None;
+                let mut last = None;
+                let mut cells = vec![];
+                for (row, terms) in witness.windows(4).enumerate() {
+                    self.config.s.enable(&mut region, row)?;
+                    if row > 0 {
+                        self.config.s_chain.enable(&mut region, row)?;
+                    }
+                    assign_row(&mut region, row)?;
+                    let a = region.assign_advice(|| "a", self.config.a, row, || terms[0]).map(Number)?;
+                    let b = region.assign_advice(|| "b", self.config.b, row, || terms[1]).map(Number)?;
+                    let c = region.assign_advice(|| "c", self.config.c, row, || terms[2]).map(Number)?;
+                    let d = region.assign_advice(|| "d", self.config.d, row, || terms[3]).map(Number)?;
+                    if row == 0 {
+                        cells.extend([a.clone(), b.clone(), c.clone()]);
+                        first = Some([a, b.clone(), c.clone()]);
+                    }
+                    cells.push(d.clone());
+                    last = Some([b, c, d]);
+                }
+                match (first, last) {
+                    (Some(initial), Some(last)) => Ok(SequenceCells { initial, last, terms: cells }),
+                    _ => Err(Error::Synthesis),
+                }
+            },
+        )
+    }
+
+    /// `SequenceChip::load_sequence` for the `rows` rows from `initial`,
+    /// each `d` computed by `step` from the row's `a, b, c` as it is
+    /// assigned. Only the last row's values and the cells handed back are
+    /// held, however many rows, so `terms` is left empty.
+    pub fn load_sequence_streaming(
+        &self,
+        mut layouter: impl Layouter<F>,
+        initial: [Value<F>; 3],
+        rows: usize,
+        step: impl Fn([Value<F>; 3]) -> Value<F>,
+    ) -> Result<SequenceCells<F>, Error> {
+        layouter.assign_region(
+            || "sequence",
+            |mut region| {
+                let mut first = None;
+                let mut last = None;
+                let mut window = initial;
+                for row in 0..rows {
+                    self.config.s.enable(&mut region, row)?;
+                    if row > 0 {
+                        self.config.s_chain.enable(&mut region, row)?;
+                    }
+                    let [a, b, c] = window;
+                    let d = step(window);
+                    let a = region.assign_advice(|| "a", self.config.a, row, || a).map(Number)?;
+                    let b = region.assign_advice(|| "b", self.config.b, row, || b).map(Number)?;
+                    let c = region.assign_advice(|| "c", self.config.c, row, || c).map(Number)?;
+                    let d = region.assign_advice(|| "d", self.config.d, row, || d).map(Number)?;
+                    if row == 0 {
+                        first = Some([a, b.clone(), c.clone()]);
+                    }
+                    window = [b.0.value().copied(), c.0.value().copied(), d.0.value().copied()];
+                    last = Some([b, c, d]);
+                }
+                match (first, last) {
+                    (Some(initial), Some(last)) => Ok(SequenceCells { initial, last, terms: vec![] }),
+                    _ => Err(Error::Synthesis),
+                }
+            },
+        )
+    }
+
+    pub fn load_first_row(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Value<F>,
+        b: Value<F>,
+        c: Value<F>,
+        d: Value<F>,
+    ) -> Result<[Number<F>; 4], Error> {
+        layouter.assign_region(
+            || "first row",
+            |mut region| {
+                self.config.s.enable(&mut region, 0)?;
+
+                let a_num = region.assign_advice(
+                    || "a",
+                    self.config.a,
+                    0,
+                    || a,
+                ).map(Number)?;
+
+                let b_num = region.assign_advice(
+                    || "b",
+                    self.config.b,
+                    0,
+                    || b,
+                ).map(Number)?;
+
+                let c_num = region.assign_advice(
+                    || "b",
+                    self.config.c,
+                    0,
+                    || c,
+                ).map(Number)?;
+
+                let d_num = region.assign_advice(
+                    || "c",
+                    self.config.d,
+                    0,
+                    || d,
+                ).map(Number)?;
+
+                Ok([a_num, b_num, c_num, d_num])
+            },
+        )
+    }
+
+    pub fn load_row(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &Number<F>,
+        b: &Number<F>,
+        c: &Number<F>,
+        d: Value<F>,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "row-load",
+            |mut region| {
+                self.config.s.enable(&mut region, 0)?;
+
+                a.0.copy_advice(|| "a", &mut region, self.config.a, 0)?;
+                b.0.copy_advice(|| "b", &mut region, self.config.b, 0)?;
+                c.0.copy_advice(|| "c", &mut region, self.config.c, 0)?;
+
+                region.assign_advice(
+                    || "d",
+                    self.config.d,
+                    0,
+                    || d,
+                ).map(Number)
+            },
+        )
+    }
+}
+
+/// What the sequence circuits need from a chip, whatever its layout.
+pub trait SequenceChip<F: Field> {
+    /// Lays out all of `witness`, at least four terms.
+    fn load_sequence(
+        &self,
+        layouter: impl Layouter<F>,
+        witness: &[Value<F>],
+    ) -> Result<SequenceCells<F>, Error>;
+
+    /// Assigns `value` fixed by the verifying key rather than by the prover.
+    fn load_constant(
+        &self,
+        layouter: impl Layouter<F>,
+        value: F,
+    ) -> Result<Number<F>, Error>;
+
+    /// Constrains `nums` to consecutive instance rows starting at `row`.
+    fn expose_public<'a>(
+        &self,
+        layouter: impl Layouter<F>,
+        nums: impl IntoIterator<Item = &'a Number<F>>,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        F: 'a;
+}
+
+impl<F: Field> SequenceChip<F> for FiboChip<F> {
+    /// Lays out the whole of `witness`, at least four terms, in a single
+    /// region of `witness.len() - 3` rows, the chain gate standing in for the
+    /// copy constraints between rows.
+    fn load_sequence(
+        &self,
+        layouter: impl Layouter<F>,
+        witness: &[Value<F>],
+    ) -> Result<SequenceCells<F>, Error> {
+        self.load_sequence_with(layouter, witness, |_, _| Ok(()))
+    }
+
+    /// Assigns `value` fixed by the verifying key rather than by the prover.
+    /// Needs a constants column, which `configure` enables but
+    /// `configure_with` leaves to the host circuit.
+    fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: F,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "constant",
+            |mut region| {
+                region.assign_advice_from_constant(
+                    || "constant",
+                    self.config.a,
+                    0,
+                    value,
+                ).map(Number)
+            },
+        )
+    }
+
+    /// Constrains `nums` to consecutive instance rows starting at `row`,
+    /// counted from the chip's offset.
+    fn expose_public<'a>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        nums: impl IntoIterator<Item = &'a Number<F>>,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        F: 'a,
+    {
+        for (i, num) in nums.into_iter().enumerate() {
+            layouter.constrain_instance(num.0.cell(), self.config.i, self.instance_offset + row + i)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Proving a sequence too long for one circuit as a chain of proofs. Every
+//! chunk is a `FiboCircuit` of the same `chunk` terms in `OutputMode::Chunk`,
+//! exposing its first three terms and its last three, so all chunks share
+//! one verifying key. Chunk `i + 1` starts from the last three terms of
+//! chunk `i`, so each advances the sequence by `chunk - 3` terms, and
+//! `verify_chunks` checks both the proofs and those links.
+
+use std::{error, fmt};
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::poly::commitment::Params;
+
+use crate::circuit::{FiboCircuit, FiboSpec, OutputMode};
+use crate::error::FibError;
+use crate::fingerprint::VkHash;
+use crate::proof::{verify_batch, Proof};
+use crate::prover;
+use crate::sequence::witness;
+
+/// What a verified chain vouches for: the `num` terms from `initial` end in
+/// `last`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkedStatement<F> {
+    pub initial: [F; 3],
+    pub last: [F; 3],
+    pub num: usize,
+}
+
+#[derive(Debug)]
+pub enum ChunkError {
+    /// `num` terms are not three plus whole steps of `chunk - 3`, or
+    /// `chunk` is below four.
+    Length { num: usize, chunk: usize },
+    /// There are no proofs to check.
+    Empty,
+    /// Proof `index` is not a chunk of the same shape and `k` as the first.
+    Shape { index: usize },
+    /// Proof `index` does not start where the one before it stopped.
+    Link { index: usize },
+    /// Proving failed, or some proof does not verify.
+    Proof(FibError),
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkError::Length { num, chunk } => {
+                write!(f, "{} terms do not split into chunks of {} overlapping by three", num, chunk)
+            }
+            ChunkError::Empty => write!(f, "no chunk proofs"),
+            ChunkError::Shape { index } => write!(f, "proof {} is not a chunk like the first", index),
+            ChunkError::Link { index } => {
+                write!(f, "proof {} does not start where proof {} stopped", index, index - 1)
+            }
+            ChunkError::Proof(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for ChunkError {}
+
+/// One chunk's circuit and the instance column it is proven against.
+#[derive(Clone)]
+pub struct Chunk<F> {
+    pub circuit: FiboCircuit<F>,
+    /// The chunk's first three terms, then its last three.
+    pub instance: Vec<F>,
+}
+
+/// How many chunks of `chunk` terms cover `num`, if they do exactly.
+pub fn chunk_count(num: usize, chunk: usize) -> Option<usize> {
+    if chunk < 4 || num < chunk || !(num - 3).is_multiple_of(chunk - 3) {
+        return None;
+    }
+    Some((num - 3) / (chunk - 3))
+}
+
+/// The chunks of the `num` terms from `a, b, c`, in order. Each circuit
+/// carries the terms it lays out, so synthesizing only assigns them; the
+/// last three start the next chunk.
+pub fn chunk_circuits<F: Field>(
+    a: F,
+    b: F,
+    c: F,
+    num: usize,
+    chunk: usize,
+) -> Result<Vec<Chunk<F>>, ChunkError> {
+    let count = chunk_count(num, chunk).ok_or(ChunkError::Length { num, chunk })?;
+    let spec = FiboSpec { num: chunk, output: OutputMode::Chunk, ..FiboSpec::default() };
+    let mut start = [a, b, c];
+    let mut chunks = Vec::with_capacity(count);
+    for _ in 0..count {
+        let terms = witness(start[0], start[1], start[2], chunk);
+        let last = [terms[chunk - 3], terms[chunk - 2], terms[chunk - 1]];
+        let circuit = FiboCircuit {
+            spec,
+            ..FiboCircuit::from_witness(terms.into_iter().map(Value::known).collect())
+        };
+        chunks.push(Chunk {
+            circuit,
+            instance: start.into_iter().chain(last).collect(),
+        });
+        start = last;
+    }
+    Ok(chunks)
+}
+
+/// Proves the `num` terms from `a, b, c` as chunks of `chunk` terms, with
+/// one proving key for all of them. `params` must be for `k`, at least the
+/// chunk spec's `min_k`.
+pub fn prove_chunks(
+    params: &Params<EqAffine>,
+    k: u32,
+    [a, b, c]: [Fp; 3],
+    num: usize,
+    chunk: usize,
+) -> Result<Vec<Proof>, ChunkError> {
+    let chunks = chunk_circuits(a, b, c, num, chunk)?;
+    let pk = prover::keygen(params, &chunks[0].circuit).map_err(ChunkError::Proof)?;
+    let vk_hash = VkHash::of(pk.get_vk());
+    chunks
+        .into_iter()
+        .map(|Chunk { circuit, instance }| {
+            let bytes = prover::prove(params, &pk, &circuit, &instance).map_err(ChunkError::Proof)?;
+            Ok(Proof::new(k, circuit.spec, instance, bytes, vk_hash))
+        })
+        .collect()
+}
+
+/// Checks a chain of chunk proofs, in order, and what they prove together.
+/// `params` must be for the proofs' `k`.
+pub fn verify_chunks(proofs: &[Proof], params: &Params<EqAffine>) -> Result<ChunkedStatement<Fp>, ChunkError> {
+    let first = proofs.first().ok_or(ChunkError::Empty)?;
+    for (index, proof) in proofs.iter().enumerate() {
+        if proof.spec.output != OutputMode::Chunk
+            || proof.spec != first.spec
+            || proof.k != first.k
+            || proof.instance.len() != OutputMode::Chunk.instance_rows()
+        {
+            return Err(ChunkError::Shape { index });
+        }
+        if index > 0 && proof.instance[..3] != proofs[index - 1].instance[3..] {
+            return Err(ChunkError::Link { index });
+        }
+    }
+    let vk = first.verifying_key(params).map_err(ChunkError::Proof)?;
+    verify_batch(proofs, &vk, params).map_err(ChunkError::Proof)?;
+    let last = &proofs[proofs.len() - 1].instance;
+    Ok(ChunkedStatement {
+        initial: [first.instance[0], first.instance[1], first.instance[2]],
+        last: [last[3], last[4], last[5]],
+        num: 3 + proofs.len() * (first.spec.num - 3),
+    })
+}
@@ -0,0 +1,439 @@
+//! The sequence circuit: its shape parameters, what it exposes, and the
+//! `Circuit` implementations over the step chip.
+
+use std::{borrow::Cow, error, fmt, str::FromStr};
+use halo2_proofs::circuit::{Value, Layouter, SimpleFloorPlanner};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::{plonk::*};
+use halo2_proofs::arithmetic::Field;
+
+use crate::chip::{FiboChip, FiboConfig, SequenceChip};
+use crate::column::ColumnChip;
+use crate::error::FibError;
+use crate::hashed::{HashedConfig, COMMIT_ROWS, HASH_ROWS};
+use crate::merkle::tree_rows;
+use crate::recurrence::Recurrence;
+use crate::sequence::{field_from_usize, witness};
+
+/// What the proof exposes as public inputs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OutputMode {
+    /// The last term, at instance row 0.
+    #[default]
+    Result,
+    /// A `Continuation`: the last three terms at rows 0..3 and the term
+    /// count at row 3, enough for a verifier to carry the sequence on.
+    Continuation,
+    /// `a`, `b` and `c` at rows 0..3 and the last term at row 3, so the
+    /// starting values are part of the statement rather than the prover's
+    /// choice.
+    InitialAndResult,
+    /// The Poseidon hash of the last term, at row 0, so the result itself
+    /// stays private: `FiboCircuitHashed`.
+    Hashed,
+    /// A Poseidon commitment to `a`, `b` and `c` at row 0 and the last term
+    /// at row 1, so the starting values stay private: `FiboCircuitCommitted`.
+    Committed,
+    /// The root of a Poseidon Merkle tree over every term at row 0:
+    /// `merkle::FiboCircuitMerkle`.
+    MerkleRoot,
+    /// `a`, `b` and `c` at rows 0..3 and the last three terms at rows 3..6:
+    /// one link of a chain of proofs, see `chunked`.
+    Chunk,
+}
+
+impl OutputMode {
+    /// A short name, for logs and reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputMode::Result => "result",
+            OutputMode::Continuation => "continuation",
+            OutputMode::InitialAndResult => "initial-and-result",
+            OutputMode::Hashed => "hashed",
+            OutputMode::Committed => "committed",
+            OutputMode::MerkleRoot => "merkle-root",
+            OutputMode::Chunk => "chunk",
+        }
+    }
+
+    /// Instance rows the output takes.
+    pub fn instance_rows(&self) -> usize {
+        match self {
+            OutputMode::Result | OutputMode::Hashed | OutputMode::MerkleRoot => 1,
+            OutputMode::Committed => 2,
+            OutputMode::Continuation | OutputMode::InitialAndResult => 4,
+            OutputMode::Chunk => 6,
+        }
+    }
+
+    /// The fewest terms the output can be proven for. A sequence shorter
+    /// than four exposes the initial value it ends on; the last three terms
+    /// and a tree over all of them need the full four.
+    pub fn min_num(&self) -> usize {
+        match self {
+            OutputMode::Result | OutputMode::InitialAndResult | OutputMode::Hashed | OutputMode::Committed => 1,
+            OutputMode::Continuation | OutputMode::Chunk | OutputMode::MerkleRoot => 4,
+        }
+    }
+}
+
+impl fmt::Display for OutputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputMode::Result => write!(f, "exposing the last term"),
+            OutputMode::Continuation => write!(f, "exposing the last three terms and the term count"),
+            OutputMode::InitialAndResult => write!(f, "exposing the initial values and the last term"),
+            OutputMode::Hashed => write!(f, "exposing the hash of the last term"),
+            OutputMode::Committed => write!(f, "exposing a commitment to the initial values and the last term"),
+            OutputMode::MerkleRoot => write!(f, "exposing the Merkle root of the terms"),
+            OutputMode::Chunk => write!(f, "exposing the first three terms and the last three"),
+        }
+    }
+}
+
+/// How the terms are arranged in the advice columns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Four columns, one row per step: `FiboCircuit`.
+    #[default]
+    Rows,
+    /// One column, one row per term, read back by rotation:
+    /// `FiboColumnCircuit`.
+    Column,
+}
+
+impl Layout {
+    /// The name `FromStr` accepts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layout::Rows => "rows",
+            Layout::Column => "column",
+        }
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layout::Rows => write!(f, "in four columns"),
+            Layout::Column => write!(f, "in a single column"),
+        }
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rows" => Ok(Layout::Rows),
+            "column" => Ok(Layout::Column),
+            _ => Err(format!("unknown layout {:?}, expected \"rows\" or \"column\"", s)),
+        }
+    }
+}
+
+/// Shape parameters of the circuit. Everything that changes the layout, and
+/// therefore the keys, lives here rather than next to the witness values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FiboSpec {
+    pub num: usize,
+    pub output: OutputMode,
+    pub layout: Layout,
+    pub recurrence: Recurrence,
+}
+
+impl FiboSpec {
+    /// Rows the layout occupies: in rows, the first holds four terms and
+    /// every later one adds one; in a column, each term takes a row. A
+    /// continuation takes one more for the term count, a hash or commitment
+    /// the rows of the Poseidon regions.
+    pub fn rows(&self) -> usize {
+        let count = match self.output {
+            OutputMode::Result | OutputMode::InitialAndResult | OutputMode::Chunk => 0,
+            OutputMode::Continuation => 1,
+            OutputMode::Hashed => HASH_ROWS,
+            OutputMode::Committed => COMMIT_ROWS,
+            OutputMode::MerkleRoot => tree_rows(self.num),
+        };
+        // Saturating, so a `num` no circuit fits reads as too many rows
+        // rather than wrapping around to a few.
+        match self.layout {
+            Layout::Rows => (self.layout_terms() - 3).saturating_add(count),
+            Layout::Column => self.layout_terms().saturating_add(count),
+        }
+    }
+
+    /// Terms laid out: `num`, or the first four for a shorter sequence,
+    /// whose result is then one of the initial values.
+    pub fn layout_terms(&self) -> usize {
+        self.num.max(4)
+    }
+
+    /// Rows halo2 needs in total: the layout, or the instance column if a
+    /// short sequence exposes more than it lays out, plus the rows halo2
+    /// reserves at the end of every column for blinding.
+    pub fn required_rows(&self) -> usize {
+        // The reserved rows depend on the gates' queries, not the field.
+        let mut cs = ConstraintSystem::<Fp>::default();
+        match self.layout {
+            Layout::Rows if matches!(self.output, OutputMode::Hashed | OutputMode::Committed | OutputMode::MerkleRoot) => {
+                HashedConfig::configure(&mut cs);
+            }
+            Layout::Rows => {
+                FiboChip::configure(&mut cs);
+            }
+            Layout::Column => {
+                ColumnChip::configure(&mut cs);
+            }
+        }
+        let used = self.rows().max(self.output.instance_rows());
+        used.saturating_add(cs.blinding_factors() + 1).max(cs.minimum_rows())
+    }
+
+    /// The smallest `k` whose `2^k` rows fit `required_rows`, or
+    /// `usize::BITS` if no `usize` row count does.
+    pub fn min_k(&self) -> u32 {
+        self.required_rows()
+            .checked_next_power_of_two()
+            .map_or(usize::BITS, |rows| rows.trailing_zeros())
+    }
+
+    /// Checks there are enough terms for the output; see
+    /// `OutputMode::min_num`.
+    pub fn check_num(&self) -> Result<(), FibError> {
+        let min = self.output.min_num();
+        if self.num < min {
+            return Err(FibError::InvalidLength { num: self.num, min });
+        }
+        Ok(())
+    }
+
+    /// Checks up front that `2^k` rows are enough, rather than leaving it to
+    /// halo2's `NotEnoughRowsAvailable` partway through synthesis, and that
+    /// `k` is at most `MAX_K`, rather than leaving it to the assertion in
+    /// `Params::new`.
+    pub fn check_k(&self, k: u32) -> Result<(), FibError> {
+        if k > MAX_K {
+            return Err(FibError::KTooLarge { k, max: MAX_K });
+        }
+        let required = self.required_rows();
+        if (1usize << k) < required {
+            return Err(FibError::KTooSmall(KTooSmall {
+                k,
+                required,
+                layout: self.rows(),
+                min_k: self.min_k(),
+            }));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FiboSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} terms of {} over {} rows {}, {}",
+            self.num,
+            self.recurrence,
+            self.rows(),
+            self.layout,
+            self.output,
+        )
+    }
+}
+
+/// The largest `k` IPA parameters can be made for: `Params::new` asserts
+/// `k < 32`.
+pub const MAX_K: u32 = 31;
+
+/// The smallest `k` for `num` terms, exposing only the result.
+pub fn min_k_for(num: usize) -> u32 {
+    FiboSpec { num, ..FiboSpec::default() }.min_k()
+}
+
+/// `k` leaves too few rows for the layout; see `FiboSpec::check_k`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KTooSmall {
+    pub k: u32,
+    /// Rows needed in total.
+    pub required: usize,
+    /// Rows of those taken by the layout itself.
+    pub layout: usize,
+    pub min_k: u32,
+}
+
+impl fmt::Display for KTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "k = {} gives {} rows, but {} are needed ({} for the sequence, {} reserved by halo2); use k = {} or more",
+            self.k,
+            1usize << self.k,
+            self.required,
+            self.layout,
+            self.required - self.layout,
+            self.min_k,
+        )
+    }
+}
+
+impl error::Error for KTooSmall {}
+
+#[derive(Clone, Default)]
+pub struct FiboCircuit<F> {
+    pub spec: FiboSpec,
+    pub a: Value<F>,
+    pub b: Value<F>,
+    pub c: Value<F>,
+    /// The whole sequence, when computed ahead of time by the caller.
+    pub witness: Option<Vec<Value<F>>>,
+}
+
+impl<F: Field> FiboCircuit<F> {
+    /// Builds the circuit from an already computed sequence, so `synthesize`
+    /// only assigns cells and never redoes the arithmetic. A sequence of one
+    /// to three terms is still laid out as four: the initial values it leaves
+    /// out are zero, which only `OutputMode::InitialAndResult` would expose,
+    /// and the fourth term follows from them by the variant. An empty one
+    /// fails to synthesize rather than panic here.
+    pub fn from_witness(mut witness: Vec<Value<F>>) -> Self {
+        let num = witness.len();
+        if num < 4 {
+            witness.resize(3, Value::known(F::ZERO));
+            witness = crate::sequence::witness(witness[0], witness[1], witness[2], 4);
+        }
+        Self {
+            spec: FiboSpec { num, ..FiboSpec::default() },
+            a: witness[0],
+            b: witness[1],
+            c: witness[2],
+            witness: Some(witness),
+        }
+    }
+
+    /// The terms laid out, at least four; see `FiboSpec::layout_terms`.
+    pub fn compute_witness(&self) -> Vec<Value<F>> {
+        witness(self.a, self.b, self.c, self.spec.layout_terms())
+    }
+
+    /// The precomputed sequence if there is one, else a freshly computed one.
+    /// Called once per `synthesize` rather than inside the region closures,
+    /// which the floor planner runs more than once.
+    pub(crate) fn full_witness(&self) -> Cow<'_, [Value<F>]> {
+        match &self.witness {
+            Some(witness) => Cow::Borrowed(witness),
+            None => Cow::Owned(self.compute_witness()),
+        }
+    }
+}
+
+/// Lays `witness` out with `chip` and exposes what `spec.output` asks for;
+/// the part of `synthesize` every layout shares.
+pub(crate) fn synthesize_sequence<F: Field>(
+    chip: &impl SequenceChip<F>,
+    spec: &FiboSpec,
+    witness: &[Value<F>],
+    mut layouter: impl Layouter<F>,
+) -> Result<(), Error> {
+    let _span = tracing::info_span!("synthesize", num = spec.num, output = %spec.output).entered();
+    spec.check_num().map_err(|_| Error::Synthesis)?;
+    let cells = chip.load_sequence(layouter.namespace(|| "sequence"), witness)?;
+    let result = cells.result(spec.num)?;
+    let [a, b, c] = &cells.initial;
+    if let OutputMode::InitialAndResult | OutputMode::Chunk = spec.output {
+        chip.expose_public(layouter.namespace(|| "expose initial"), [a, b, c], 0)?;
+    }
+    let [b, c, d] = &cells.last;
+    match spec.output {
+        OutputMode::Result => {
+            chip.expose_public(layouter.namespace(|| "expose public"), [result], 0)?;
+        }
+        OutputMode::Continuation => {
+            let count = chip.load_constant(
+                layouter.namespace(|| "term count"),
+                field_from_usize(spec.num),
+            )?;
+            chip.expose_public(layouter.namespace(|| "expose public"), [b, c, d, &count], 0)?;
+        }
+        OutputMode::InitialAndResult => {
+            chip.expose_public(layouter.namespace(|| "expose public"), [result], 3)?;
+        }
+        OutputMode::Chunk => {
+            chip.expose_public(layouter.namespace(|| "expose public"), [b, c, d], 3)?;
+        }
+        // Need the Poseidon chip as well: `FiboCircuitHashed`,
+        // `FiboCircuitCommitted` and `FiboCircuitMerkle`.
+        OutputMode::Hashed | OutputMode::Committed | OutputMode::MerkleRoot => return Err(Error::Synthesis),
+    }
+    Ok(())
+}
+
+impl<F: Field> Circuit<F> for FiboCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            spec: self.spec,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        if self.spec.layout != Layout::Rows || self.spec.recurrence != Recurrence::Variant {
+            return Err(Error::Synthesis);
+        }
+        let chip = FiboChip::construct(config);
+        synthesize_sequence(&chip, &self.spec, &self.full_witness(), layouter)
+    }
+}
+
+/// `FiboCircuit` with the sequence length fixed by the type, so code holding
+/// keys for exactly `NUM` terms can say so in its signatures.
+pub struct FiboCircuitFixed<F, const NUM: usize>(pub FiboCircuit<F>);
+
+impl<F: Field, const NUM: usize> FiboCircuitFixed<F, NUM> {
+    const VALID_NUM: () = assert!(NUM >= 1, "the circuit needs at least one term");
+
+    pub fn new(a: Value<F>, b: Value<F>, c: Value<F>) -> Self {
+        let () = Self::VALID_NUM;
+        Self(FiboCircuit {
+            spec: FiboSpec { num: NUM, ..FiboSpec::default() },
+            a,
+            b,
+            c,
+            witness: None,
+        })
+    }
+}
+
+impl<F: Field, const NUM: usize> Circuit<F> for FiboCircuitFixed<F, NUM> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboCircuit::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
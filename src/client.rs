@@ -0,0 +1,152 @@
+//! Calling a proving service from Rust: a `Client` with one async method
+//! per endpoint of `server`, sending and receiving the same request and
+//! response types, so a caller of a hosted prover builds no JSON by hand.
+//! The requests go out over reqwest, and need a tokio runtime to run on.
+//!
+//! `prove` holds the connection open until the proof is made. For long
+//! proofs, `submit` queues the request and `wait` polls for its result;
+//! `ProveResponse::to_proof` turns either answer into a `Proof`.
+
+use std::time::Duration;
+use std::{error, fmt};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::proof::Proof;
+use crate::server::{JobResponse, JobStatus, ProveRequest, ProveResponse, VerifyRequest, VerifyResponse};
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The service could not be reached, or its answer not read.
+    Http(reqwest::Error),
+    /// The service answered with an error status, and this message.
+    Service { status: u16, message: String },
+    /// The job failed on the service, with this error.
+    JobFailed { id: u64, error: String },
+    /// The job was cancelled before it finished.
+    JobCancelled(u64),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Http(err) => write!(f, "{}", err),
+            ClientError::Service { status, message } => write!(f, "the service answered {}: {}", status, message),
+            ClientError::JobFailed { id, error } => write!(f, "job {} failed: {}", id, error),
+            ClientError::JobCancelled(id) => write!(f, "job {} was cancelled", id),
+        }
+    }
+}
+
+impl error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ClientError::Http(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        ClientError::Http(err)
+    }
+}
+
+/// The body of an error answer.
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// A proving service at one base URL, e.g. `http://127.0.0.1:8080`.
+#[derive(Clone, Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    base: String,
+}
+
+impl Client {
+    pub fn new(base: impl Into<String>) -> Self {
+        Self::with_http(reqwest::Client::new(), base)
+    }
+
+    /// `new` over `http`, e.g. one built with timeouts or a proxy.
+    pub fn with_http(http: reqwest::Client, base: impl Into<String>) -> Self {
+        let base = base.into().trim_end_matches('/').to_string();
+        Self { http, base }
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// `POST /prove`, answered once the proof is made.
+    pub async fn prove(&self, request: &ProveRequest) -> Result<ProveResponse, ClientError> {
+        self.send(self.http.post(self.url("/prove")).json(request)).await
+    }
+
+    /// `POST /verify`.
+    pub async fn verify(&self, request: &VerifyRequest) -> Result<VerifyResponse, ClientError> {
+        self.send(self.http.post(self.url("/verify")).json(request)).await
+    }
+
+    /// `verify` for `proof` against its own public inputs.
+    pub async fn verify_proof(&self, proof: &Proof) -> Result<bool, ClientError> {
+        let request = VerifyRequest { proof: proof.to_hex(), public_inputs: vec![] };
+        Ok(self.verify(&request).await?.valid)
+    }
+
+    /// `POST /jobs`: queues the proof, answered at once with the job id.
+    pub async fn submit(&self, request: &ProveRequest) -> Result<JobResponse, ClientError> {
+        self.send(self.http.post(self.url("/jobs")).json(request)).await
+    }
+
+    /// `GET /jobs/{id}`. The service forgets a job once it has reported it
+    /// done, failed or cancelled, so that answer comes only once.
+    pub async fn job(&self, id: u64) -> Result<JobResponse, ClientError> {
+        self.send(self.http.get(self.url(&format!("/jobs/{}", id)))).await
+    }
+
+    /// `DELETE /jobs/{id}`.
+    pub async fn cancel(&self, id: u64) -> Result<JobResponse, ClientError> {
+        self.send(self.http.delete(self.url(&format!("/jobs/{}", id)))).await
+    }
+
+    /// Polls job `id` every `interval` until it is done, and returns its
+    /// proof; a failed or cancelled job is an error.
+    pub async fn wait(&self, id: u64, interval: Duration) -> Result<ProveResponse, ClientError> {
+        loop {
+            match self.job(id).await?.status {
+                JobStatus::Queued | JobStatus::Running => tokio::time::sleep(interval).await,
+                JobStatus::Done { result } => return Ok(result),
+                JobStatus::Failed { error } => return Err(ClientError::JobFailed { id, error }),
+                JobStatus::Cancelled => return Err(ClientError::JobCancelled(id)),
+            }
+        }
+    }
+
+    /// `submit`, then `wait`.
+    pub async fn prove_queued(&self, request: &ProveRequest, interval: Duration) -> Result<ProveResponse, ClientError> {
+        let job = self.submit(request).await?;
+        self.wait(job.id, interval).await
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base, path)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T, ClientError> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.json().await?);
+        }
+        // Replies the service did not write itself, e.g. a proxy's, may not
+        // be its JSON; the status is reported either way.
+        let text = response.text().await?;
+        let message = serde_json::from_str::<ErrorBody>(&text).map_or(text, |body| body.error);
+        Err(ClientError::Service { status: status.as_u16(), message })
+    }
+}
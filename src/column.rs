@@ -0,0 +1,170 @@
+//! The single-column layout: term `i` sits in row `i` of one advice column
+//! and the gate reads the three terms before it by rotation. No cell is
+//! copied between rows, so the permutation argument spans only the advice
+//! column, the instance column and the constants, instead of the four
+//! advice columns `FiboChip` ties together with copy constraints.
+
+use std::{fmt, marker::PhantomData};
+use halo2_proofs::circuit::{Value, Layouter, SimpleFloorPlanner};
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::{plonk::*};
+use halo2_proofs::arithmetic::Field;
+
+use crate::chip::{Number, SequenceCells, SequenceChip};
+use crate::circuit::{synthesize_sequence, FiboCircuit, Layout};
+use crate::recurrence::Recurrence;
+
+pub const COLUMN_GATE_NAME: &str = "column gate";
+
+#[derive(Clone, Debug, Copy)]
+pub struct ColumnConfig {
+    /// The terms, one per row.
+    pub x: Column<Advice>,
+    pub i: Column<Instance>,
+    /// Every row from the fourth term on.
+    pub s: Selector,
+}
+
+impl fmt::Display for ColumnConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "advice x (one term per row), instance i (result), \
+             gate \"{}\": (x[-1] + x[-3]) * x[-2] = x",
+            COLUMN_GATE_NAME,
+        )
+    }
+}
+
+pub struct ColumnChip<F: Field> {
+    config: ColumnConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ColumnChip<F> {
+    pub fn construct(config: ColumnConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> ColumnConfig {
+        let _span = tracing::info_span!("configure", layout = "column").entered();
+        let x = meta.advice_column();
+        let i = meta.instance_column();
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+        meta.enable_equality(x);
+        meta.enable_equality(i);
+        let s = meta.selector();
+
+        meta.create_gate(COLUMN_GATE_NAME, |meta| {
+            let s = meta.query_selector(s);
+            let x3 = meta.query_advice(x, Rotation(-3));
+            let x2 = meta.query_advice(x, Rotation(-2));
+            let x1 = meta.query_advice(x, Rotation::prev());
+            let x0 = meta.query_advice(x, Rotation::cur());
+            vec![s * ((x1 + x3) * x2 - x0)]
+        });
+
+        ColumnConfig { x, i, s }
+    }
+}
+
+impl<F: Field> SequenceChip<F> for ColumnChip<F> {
+    /// Lays out all of `witness`, at least four terms, one per row of a
+    /// single region.
+    fn load_sequence(
+        &self,
+        mut layouter: impl Layouter<F>,
+        witness: &[Value<F>],
+    ) -> Result<SequenceCells<F>, Error> {
+        if witness.len() < 4 {
+            return Err(Error::Synthesis);
+        }
+        let config = self.config;
+        layouter.assign_region(
+            || "column",
+            |mut region| {
+                let mut terms = vec![];
+                for (row, &term) in witness.iter().enumerate() {
+                    if row >= 3 {
+                        config.s.enable(&mut region, row)?;
+                    }
+                    let cell = region.assign_advice(|| format!("term {}", row), config.x, row, || term)?;
+                    terms.push(Number(cell));
+                }
+                let n = terms.len();
+                Ok(SequenceCells {
+                    initial: [0, 1, 2].map(|i| terms[i].clone()),
+                    last: [n - 3, n - 2, n - 1].map(|i| terms[i].clone()),
+                    terms,
+                })
+            },
+        )
+    }
+
+    /// Assigns `value` fixed by the verifying key rather than by the prover,
+    /// in a row of its own below the sequence.
+    fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: F,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "constant",
+            |mut region| {
+                region
+                    .assign_advice_from_constant(|| "constant", self.config.x, 0, value)
+                    .map(Number)
+            },
+        )
+    }
+
+    fn expose_public<'a>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        nums: impl IntoIterator<Item = &'a Number<F>>,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        F: 'a,
+    {
+        for (i, num) in nums.into_iter().enumerate() {
+            layouter.constrain_instance(num.0.cell(), self.config.i, row + i)?;
+        }
+        Ok(())
+    }
+}
+
+/// `FiboCircuit` in the single-column layout. The spec must say
+/// `Layout::Column`, so that its row count and `k` match this circuit, and
+/// the variant, the only recurrence the column gate checks.
+#[derive(Clone, Default)]
+pub struct FiboColumnCircuit<F>(pub FiboCircuit<F>);
+
+impl<F: Field> Circuit<F> for FiboColumnCircuit<F> {
+    type Config = ColumnConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        ColumnChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        if self.0.spec.layout != Layout::Column || self.0.spec.recurrence != Recurrence::Variant {
+            return Err(Error::Synthesis);
+        }
+        let chip = ColumnChip::construct(config);
+        synthesize_sequence(&chip, &self.0.spec, &self.0.full_witness(), layouter)
+    }
+}
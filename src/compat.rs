@@ -0,0 +1,88 @@
+//! Compatibility shim over the supported halo2_proofs releases.
+//!
+//! The crate is written against `halo2_proofs::...` paths; with `halo2-0-4`
+//! the 0.4 release is aliased to that name in the crate root, so circuit code is
+//! identical on both. The API differences that leak into this crate are
+//! the RNG interfaces: `Field::random` is handled in `small_field`, and
+//! `create_proof` gets its RNG from `os_rng` or `seeded_rng` below.
+//! `par_map` stands in for rayon where `multicore` leaves threads out.
+
+#[cfg(all(feature = "halo2-0-3", feature = "halo2-0-4"))]
+compile_error!("features `halo2-0-3` and `halo2-0-4` are mutually exclusive");
+
+#[cfg(not(any(feature = "halo2-0-3", feature = "halo2-0-4")))]
+compile_error!("enable one of the features `halo2-0-3` or `halo2-0-4`");
+
+#[cfg(all(feature = "secp256k1", feature = "halo2-0-4"))]
+compile_error!("halo2curves 0.6 is built on ff 0.13, so `secp256k1` needs `halo2-0-3`");
+
+#[cfg(all(feature = "kzg", feature = "halo2-0-4"))]
+compile_error!("halo2-axiom is built on ff 0.13, so `kzg` needs `halo2-0-3`");
+
+/// Fresh OS randomness for proof blinding, as whichever RNG trait the
+/// backend's `create_proof` takes.
+#[cfg(feature = "halo2-0-3")]
+pub(crate) fn os_rng() -> impl rand_core::RngCore {
+    rand_core::OsRng
+}
+
+#[cfg(feature = "halo2-0-4")]
+pub(crate) fn os_rng() -> impl rand_core_0_10::Rng {
+    rand_core_0_10::UnwrapErr(getrandom_0_4::SysRng)
+}
+
+/// A ChaCha20 stream from `seed`, for proofs that come out byte for byte the
+/// same, as whichever RNG trait the backend's `create_proof` takes.
+#[cfg(feature = "halo2-0-3")]
+pub(crate) fn seeded_rng(seed: [u8; 32]) -> impl rand_core::RngCore {
+    use rand_chacha::rand_core::SeedableRng;
+
+    rand_chacha::ChaCha20Rng::from_seed(seed)
+}
+
+#[cfg(feature = "halo2-0-4")]
+pub(crate) fn seeded_rng(seed: [u8; 32]) -> impl rand_core_0_10::Rng {
+    use rand_chacha::rand_core::SeedableRng;
+
+    ChaCha20(rand_chacha::ChaCha20Rng::from_seed(seed))
+}
+
+/// rand_chacha's generator, built on rand_core 0.6, under 0.10's traits:
+/// there is no ChaCha for 0.10 in the dependency tree.
+#[cfg(feature = "halo2-0-4")]
+struct ChaCha20(rand_chacha::ChaCha20Rng);
+
+#[cfg(feature = "halo2-0-4")]
+impl rand_core_0_10::TryRng for ChaCha20 {
+    type Error = std::convert::Infallible;
+
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+        Ok(rand_chacha::rand_core::RngCore::next_u32(&mut self.0))
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        Ok(rand_chacha::rand_core::RngCore::next_u64(&mut self.0))
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+        rand_chacha::rand_core::RngCore::fill_bytes(&mut self.0, dst);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "halo2-0-4")]
+impl rand_core_0_10::TryCryptoRng for ChaCha20 {}
+
+/// `f` over `items`, on rayon's thread pool.
+#[cfg(feature = "multicore")]
+pub(crate) fn par_map<'a, T: Sync, U: Send>(items: &'a [T], f: impl Fn(&'a T) -> U + Sync + Send) -> Vec<U> {
+    use rayon::prelude::*;
+
+    items.par_iter().map(f).collect()
+}
+
+/// `f` over `items`, one after another: no `multicore`, no threads.
+#[cfg(not(feature = "multicore"))]
+pub(crate) fn par_map<'a, T: Sync, U: Send>(items: &'a [T], f: impl Fn(&'a T) -> U + Sync + Send) -> Vec<U> {
+    items.iter().map(f).collect()
+}
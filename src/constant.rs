@@ -0,0 +1,145 @@
+//! The sequence from initial values fixed as circuit constants, for
+//! deployments where `a, b, c` are part of the protocol. The first row's
+//! cells are constrained to the constants column, so the values are in the
+//! verifying key: a prover starting anywhere else fails the copy
+//! constraints, and a key for one start does not verify another's proofs.
+
+use halo2_proofs::circuit::{Value, Layouter, SimpleFloorPlanner};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::{plonk::*};
+use halo2_proofs::arithmetic::Field;
+
+use crate::chip::{FiboChip, FiboConfig, Number, SequenceCells, SequenceChip};
+use crate::circuit::{synthesize_sequence, FiboCircuit, FiboSpec, Layout, OutputMode};
+use crate::recurrence::Recurrence;
+
+/// `FiboChip` with the first row's `a, b, c` tied to constants.
+pub struct ConstantChip<F: Field> {
+    fibo: FiboChip<F>,
+    initial: [F; 3],
+}
+
+impl<F: Field> ConstantChip<F> {
+    pub fn construct(config: FiboConfig, initial: [F; 3]) -> Self {
+        Self {
+            fibo: FiboChip::construct(config),
+            initial,
+        }
+    }
+}
+
+impl<F: Field> SequenceChip<F> for ConstantChip<F> {
+    fn load_sequence(
+        &self,
+        mut layouter: impl Layouter<F>,
+        witness: &[Value<F>],
+    ) -> Result<SequenceCells<F>, Error> {
+        let cells = self.fibo.load_sequence(layouter.namespace(|| "sequence"), witness)?;
+        layouter.assign_region(
+            || "constant initial",
+            |mut region| {
+                for (cell, &value) in cells.initial.iter().zip(&self.initial) {
+                    region.constrain_constant(cell.0.cell(), value)?;
+                }
+                Ok(())
+            },
+        )?;
+        Ok(cells)
+    }
+
+    fn load_constant(
+        &self,
+        layouter: impl Layouter<F>,
+        value: F,
+    ) -> Result<Number<F>, Error> {
+        self.fibo.load_constant(layouter, value)
+    }
+
+    fn expose_public<'a>(
+        &self,
+        layouter: impl Layouter<F>,
+        nums: impl IntoIterator<Item = &'a Number<F>>,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        F: 'a,
+    {
+        self.fibo.expose_public(layouter, nums, row)
+    }
+}
+
+/// `FiboCircuit` in the four-column layout, for the variant, starting from
+/// `initial`. The initial values are shape, not witness: `without_witnesses`
+/// keeps them, and keys are per choice. `circuit`'s own `a, b, c` are the
+/// prover's claim, which must match; `new` sets them so.
+#[derive(Clone)]
+pub struct FiboCircuitConstant<F> {
+    pub initial: [F; 3],
+    pub circuit: FiboCircuit<F>,
+}
+
+impl<F: Field> FiboCircuitConstant<F> {
+    /// The `spec.num` terms from the constants `a, b, c`.
+    pub fn new(spec: FiboSpec, [a, b, c]: [F; 3]) -> Self {
+        Self {
+            initial: [a, b, c],
+            circuit: FiboCircuit {
+                spec,
+                a: Value::known(a),
+                b: Value::known(b),
+                c: Value::known(c),
+                witness: None,
+            },
+        }
+    }
+
+    /// Rows the layout occupies: the spec's, or the constants column's if
+    /// that is longer, holding the initial values and a continuation's term
+    /// count.
+    pub fn rows(&self) -> usize {
+        let count = match self.circuit.spec.output {
+            OutputMode::Continuation => 1,
+            _ => 0,
+        };
+        self.circuit.spec.rows().max(3 + count)
+    }
+
+    /// The smallest `k` whose `2^k` rows fit the layout, the instance column
+    /// and the rows halo2 reserves for blinding.
+    pub fn min_k(&self) -> u32 {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        FiboChip::configure(&mut cs);
+        let used = self.rows().max(self.circuit.spec.output.instance_rows());
+        let required = (used + cs.blinding_factors() + 1).max(cs.minimum_rows());
+        required.next_power_of_two().trailing_zeros()
+    }
+}
+
+impl<F: Field> Circuit<F> for FiboCircuitConstant<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            initial: self.initial,
+            circuit: self.circuit.without_witnesses(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        let spec = &self.circuit.spec;
+        if spec.layout != Layout::Rows || spec.recurrence != Recurrence::Variant {
+            return Err(Error::Synthesis);
+        }
+        let chip = ConstantChip::construct(config, self.initial);
+        synthesize_sequence(&chip, spec, &self.circuit.full_witness(), layouter)
+    }
+}
@@ -0,0 +1,204 @@
+//! Keygen once, prove many: a `ProverContext` holds the parameters and the
+//! proving key for one spec, and proves any initial values of that shape
+//! without redoing keygen, which costs about as much as a proof. This is the
+//! usual case for a service, where the shape is fixed and only the inputs
+//! change.
+//!
+//! The types follow the order the work must happen in. A `Setup` holds the
+//! parameters for one `k`, `Setup::keys` is the only way to make `Keys` for a
+//! spec under them, and `Keys::prover` the only way to a `ProverContext`. A
+//! prover thus always proves under a key for its own shape and parameters,
+//! and the proofs it makes cannot fail verification over a mismatched key.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{Circuit, ProvingKey, VerifyingKey};
+use halo2_proofs::poly::commitment::Params;
+
+use crate::circuit::{FiboCircuit, FiboSpec};
+use crate::dispatch::{with_circuit, WithCircuit};
+use crate::error::FibError;
+use crate::fingerprint::VkHash;
+use crate::params::ParamsCache;
+use crate::proof::Proof;
+use crate::prover::{self, instance, ProveOutput, Timings};
+use crate::transcript::TranscriptHash;
+
+/// The parameters for one `k`, from which keys for any spec fitting it are
+/// made.
+#[derive(Clone)]
+pub struct Setup {
+    params: Arc<Params<EqAffine>>,
+}
+
+impl Setup {
+    pub fn new(params: impl Into<Arc<Params<EqAffine>>>) -> Self {
+        Self { params: params.into() }
+    }
+
+    /// The parameters for `k` from `cache`, generated on a miss.
+    pub fn load(cache: &ParamsCache, k: u32) -> Result<Self, FibError> {
+        Ok(Self::new(cache.get(k)?))
+    }
+
+    pub fn k(&self) -> u32 {
+        self.params.k()
+    }
+
+    pub fn params(&self) -> &Params<EqAffine> {
+        &self.params
+    }
+
+    /// Makes the proving key for `spec`, which must fit `k`.
+    pub fn keys(&self, spec: FiboSpec) -> Result<Keys, FibError> {
+        spec.check_num()?;
+        spec.check_k(self.k())?;
+        let start = Instant::now();
+        let circuit = FiboCircuit { spec, ..FiboCircuit::default() };
+        let pk = with_circuit(circuit, Keygen(&self.params))??;
+        let keygen = start.elapsed();
+        let vk_hash = VkHash::of(pk.get_vk());
+        Ok(Keys { params: self.params.clone(), spec, pk, vk_hash, keygen })
+    }
+}
+
+/// A proving key, with the spec it is for and the parameters it was made
+/// under; only `Setup::keys` makes one.
+pub struct Keys {
+    params: Arc<Params<EqAffine>>,
+    spec: FiboSpec,
+    pk: ProvingKey<EqAffine>,
+    vk_hash: VkHash,
+    keygen: Duration,
+}
+
+impl Keys {
+    pub fn spec(&self) -> FiboSpec {
+        self.spec
+    }
+
+    pub fn params(&self) -> &Params<EqAffine> {
+        &self.params
+    }
+
+    pub fn verifying_key(&self) -> &VerifyingKey<EqAffine> {
+        self.pk.get_vk()
+    }
+
+    pub fn vk_hash(&self) -> VkHash {
+        self.vk_hash
+    }
+
+    /// How long `Setup::keys` spent on keygen.
+    pub fn keygen_time(&self) -> Duration {
+        self.keygen
+    }
+
+    /// Checks `proof` under these keys, without regenerating any: a proof
+    /// of another shape is `VkMismatch`, one at another `k` `ParamsMismatch`.
+    pub fn verify(&self, proof: &Proof) -> Result<(), FibError> {
+        proof.check_shape()?;
+        if proof.k != self.params.k() {
+            return Err(FibError::ParamsMismatch { params: self.params.k(), proof: proof.k });
+        }
+        proof.verify_with_key(&self.params, self.pk.get_vk())
+    }
+
+    /// A prover under these keys, with the Blake2b transcript.
+    pub fn prover(self) -> ProverContext {
+        ProverContext { keys: self, transcript: TranscriptHash::Blake2b }
+    }
+}
+
+/// `Keys` ready to prove with.
+///
+/// Everything in it is read-only once made, so a `ProverContext` is `Send`
+/// and `Sync`: share one behind an `Arc` and call `prove` from as many
+/// threads as you like. Each proof is parallel already with `multicore`, so
+/// more threads than cores gain little.
+pub struct ProverContext {
+    keys: Keys,
+    transcript: TranscriptHash,
+}
+
+impl ProverContext {
+    /// `Setup::new(params).keys(spec)`, then `prover`, in one call.
+    pub fn new(params: impl Into<Arc<Params<EqAffine>>>, spec: FiboSpec) -> Result<Self, FibError> {
+        Ok(Setup::new(params).keys(spec)?.prover())
+    }
+
+    pub fn with_transcript(self, transcript: TranscriptHash) -> Self {
+        Self { transcript, ..self }
+    }
+
+    pub fn keys(&self) -> &Keys {
+        &self.keys
+    }
+
+    pub fn into_keys(self) -> Keys {
+        self.keys
+    }
+
+    /// Proves the sequence from `[a, b, c]` against the instance values they
+    /// give, blinded from the operating system. The output's keygen time is
+    /// zero: the key was made beforehand.
+    pub fn prove(&self, inputs: [Fp; 3]) -> Result<ProveOutput, FibError> {
+        self.prove_inner(inputs, None)
+    }
+
+    /// `prove` blinded from `seed`, as `prover::prove_seeded`: for test
+    /// vectors only.
+    pub fn prove_seeded(&self, inputs: [Fp; 3], seed: [u8; 32]) -> Result<ProveOutput, FibError> {
+        self.prove_inner(inputs, Some(seed))
+    }
+
+    fn prove_inner(&self, [a, b, c]: [Fp; 3], seed: Option<[u8; 32]>) -> Result<ProveOutput, FibError> {
+        let Keys { params, spec, vk_hash, .. } = &self.keys;
+        let instance = instance(spec, [a, b, c])?;
+        let terms = spec.recurrence.terms(a, b, c, spec.layout_terms());
+        let witness = terms.into_iter().map(Value::known).collect();
+        let circuit = FiboCircuit { spec: *spec, ..FiboCircuit::from_witness(witness) };
+        let start = Instant::now();
+        let bytes = with_circuit(circuit, ProveWithKey { context: self, instance: &instance, seed })??;
+        Ok(ProveOutput {
+            k: params.k(),
+            spec: *spec,
+            bytes,
+            instance,
+            vk_hash: *vk_hash,
+            transcript: self.transcript,
+            timings: Timings { keygen: Duration::ZERO, prove: start.elapsed() },
+        })
+    }
+}
+
+struct Keygen<'a>(&'a Params<EqAffine>);
+
+impl WithCircuit for Keygen<'_> {
+    type Output = Result<ProvingKey<EqAffine>, FibError>;
+
+    fn with<C: Circuit<Fp>>(self, circuit: C) -> Self::Output {
+        prover::keygen(self.0, &circuit)
+    }
+}
+
+struct ProveWithKey<'a> {
+    context: &'a ProverContext,
+    instance: &'a [Fp],
+    seed: Option<[u8; 32]>,
+}
+
+impl WithCircuit for ProveWithKey<'_> {
+    type Output = Result<Vec<u8>, FibError>;
+
+    fn with<C: Circuit<Fp>>(self, circuit: C) -> Self::Output {
+        let (Keys { params, pk, .. }, hash) = (&self.context.keys, self.context.transcript);
+        match self.seed {
+            Some(seed) => prover::prove_seeded(params, pk, &circuit, self.instance, hash, seed),
+            None => prover::prove_with_transcript(params, pk, &circuit, self.instance, hash),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Picking the `Circuit` type a `FiboSpec` calls for. `configure` is static,
+//! so each layout, recurrence and output needing other columns or gates has
+//! a type of its own; `with_circuit` wraps a `FiboCircuit` in the right one
+//! and hands it to a `WithCircuit`, which cannot be a closure since it is
+//! generic over the circuit type.
+
+use std::{error, fmt};
+use halo2_proofs::circuit::Value;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{self, Circuit};
+
+use crate::circuit::{FiboCircuit, FiboSpec, Layout, OutputMode};
+use crate::column::FiboColumnCircuit;
+use crate::hashed::{FiboCircuitCommitted, FiboCircuitHashed};
+use crate::merkle::FiboCircuitMerkle;
+use crate::recurrence::{Fibonacci, Recurrence, RecurrenceCircuit};
+
+/// Something to do with whichever circuit the spec calls for.
+pub trait WithCircuit {
+    type Output;
+
+    fn with<C: Circuit<Fp>>(self, circuit: C) -> Self::Output;
+}
+
+/// `MockProver::run` at `k` against the instance column, for whichever
+/// circuit the spec calls for.
+pub struct Mock(pub u32, pub Vec<Fp>);
+
+impl WithCircuit for Mock {
+    type Output = Result<MockProver<Fp>, plonk::Error>;
+
+    fn with<C: Circuit<Fp>>(self, circuit: C) -> Self::Output {
+        MockProver::run(self.0, &circuit, vec![self.1])
+    }
+}
+
+/// No circuit implements this combination of shape parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Unsupported {
+    pub spec: FiboSpec,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl error::Error for Unsupported {}
+
+/// Runs `f` on `circuit` wrapped in the type its spec calls for. A
+/// committed circuit gets no blinding, since `FiboCircuit` has none to give:
+/// enough for keys, not for a proof.
+pub fn with_circuit<W: WithCircuit>(circuit: FiboCircuit<Fp>, f: W) -> Result<W::Output, Unsupported> {
+    let spec = circuit.spec;
+    let unsupported = |reason| Err(Unsupported { spec, reason });
+    match (spec.layout, spec.recurrence, spec.output) {
+        (Layout::Rows, Recurrence::Variant, OutputMode::Hashed) => Ok(f.with(FiboCircuitHashed(circuit))),
+        (Layout::Rows, Recurrence::Variant, OutputMode::Committed) => {
+            Ok(f.with(FiboCircuitCommitted { circuit, blinding: Value::unknown() }))
+        }
+        (Layout::Rows, Recurrence::Variant, OutputMode::MerkleRoot) => Ok(f.with(FiboCircuitMerkle(circuit))),
+        (_, _, OutputMode::Hashed | OutputMode::Committed | OutputMode::MerkleRoot) => {
+            unsupported("hashed outputs need the rows layout and the variant recurrence")
+        }
+        (Layout::Rows, Recurrence::Variant, _) => Ok(f.with(circuit)),
+        (Layout::Rows, Recurrence::Fibonacci, _) => Ok(f.with(RecurrenceCircuit::<_, Fibonacci>::new(circuit))),
+        (Layout::Column, Recurrence::Variant, _) => Ok(f.with(FiboColumnCircuit(circuit))),
+        (Layout::Column, _, _) => unsupported("the column layout only supports the variant recurrence"),
+    }
+}
@@ -0,0 +1,113 @@
+//! `FibError`, what the prover and verifier layers return, so a library
+//! caller can tell a bad request from a bad proof without matching on
+//! halo2's own error.
+
+use std::{error, fmt, io};
+
+use halo2_proofs::plonk;
+
+use crate::circuit::KTooSmall;
+use crate::dispatch::Unsupported;
+use crate::fingerprint::VkHash;
+
+#[derive(Debug)]
+pub enum FibError {
+    /// Too few terms for the output: `min` is one, or four for the outputs
+    /// built on the last three terms or on every term.
+    InvalidLength { num: usize, min: usize },
+    /// `2^k` rows do not fit the layout.
+    KTooSmall(KTooSmall),
+    /// `k` is past `circuit::MAX_K`, the most the parameters allow.
+    KTooLarge { k: u32, max: u32 },
+    /// No circuit implements the spec.
+    Unsupported(Unsupported),
+    /// The proof does not verify against the key and public inputs, or is
+    /// not a proof at all.
+    ProofVerificationFailed,
+    /// The proof records a verifying key other than the one it is checked
+    /// against: made for another circuit, or before the constraints changed.
+    VkMismatch { recorded: VkHash, expected: VkHash },
+    /// The parameters are for another `k` than the proof.
+    ParamsMismatch { params: u32, proof: u32 },
+    /// Reading or writing parameters or proofs failed.
+    Io(io::Error),
+    /// Key generation or proving failed: the witness does not satisfy the
+    /// circuit, or the circuit does not fit.
+    Synthesis(plonk::Error),
+    /// `Synthesis`, from the KZG backend.
+    #[cfg(feature = "kzg")]
+    KzgSynthesis(halo2_axiom::plonk::Error),
+}
+
+impl fmt::Display for FibError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FibError::InvalidLength { num, min } => {
+                write!(f, "{} terms are too few for the output, which needs at least {}", num, min)
+            }
+            FibError::KTooSmall(err) => write!(f, "{}", err),
+            FibError::KTooLarge { k, max } => write!(f, "k = {} is too large; parameters go up to k = {}", k, max),
+            FibError::Unsupported(err) => write!(f, "{}", err),
+            FibError::ProofVerificationFailed => write!(f, "the proof does not verify"),
+            FibError::VkMismatch { recorded, expected } => {
+                write!(f, "the proof was made under verifying key {}, not {}", recorded, expected)
+            }
+            FibError::ParamsMismatch { params, proof } => {
+                write!(f, "the parameters are for k = {}, but the proof is for k = {}", params, proof)
+            }
+            FibError::Io(err) => write!(f, "{}", err),
+            FibError::Synthesis(err) => write!(f, "{}", err),
+            #[cfg(feature = "kzg")]
+            FibError::KzgSynthesis(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for FibError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FibError::KTooSmall(err) => Some(err),
+            FibError::Unsupported(err) => Some(err),
+            FibError::Io(err) => Some(err),
+            FibError::Synthesis(err) => Some(err),
+            #[cfg(feature = "kzg")]
+            FibError::KzgSynthesis(err) => Some(err),
+            FibError::InvalidLength { .. }
+            | FibError::KTooLarge { .. }
+            | FibError::ProofVerificationFailed
+            | FibError::VkMismatch { .. }
+            | FibError::ParamsMismatch { .. } => None,
+        }
+    }
+}
+
+impl From<KTooSmall> for FibError {
+    fn from(err: KTooSmall) -> Self {
+        FibError::KTooSmall(err)
+    }
+}
+
+impl From<Unsupported> for FibError {
+    fn from(err: Unsupported) -> Self {
+        FibError::Unsupported(err)
+    }
+}
+
+impl From<io::Error> for FibError {
+    fn from(err: io::Error) -> Self {
+        FibError::Io(err)
+    }
+}
+
+impl From<plonk::Error> for FibError {
+    fn from(err: plonk::Error) -> Self {
+        FibError::Synthesis(err)
+    }
+}
+
+#[cfg(feature = "kzg")]
+impl From<halo2_axiom::plonk::Error> for FibError {
+    fn from(err: halo2_axiom::plonk::Error) -> Self {
+        FibError::KzgSynthesis(err)
+    }
+}
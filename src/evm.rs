@@ -0,0 +1,104 @@
+//! Checking `kzg` proofs on Ethereum. `solidity_verifier` turns a verifying
+//! key into a Solidity contract, generated by snark-verifier, whose fallback
+//! takes `calldata`, the public inputs and then the proof bytes, and reverts
+//! unless the proof verifies. The contract replays the transcript with
+//! Keccak, which the EVM has an opcode for, so it checks proofs from `prove`
+//! here and not `kzg::prove`'s Blake2b ones; `verify` runs the contract's
+//! own checks natively, through the same code the contract is built from.
+//!
+//! The contract trusts the parameters it is generated from: for anything
+//! deployed, read those of a ceremony rather than using `kzg::setup`.
+
+use std::rc::Rc;
+
+use halo2_axiom::plonk::{ProvingKey, VerifyingKey};
+use halo2_axiom::poly::commitment::{Params, ParamsProver};
+use halo2_axiom::poly::kzg::commitment::ParamsKZG;
+use snark_verifier::loader::evm::{encode_calldata, EvmLoader};
+use snark_verifier::loader::native::NativeLoader;
+use snark_verifier::pcs::kzg::{Bdfg21, KzgAs, KzgDecidingKey};
+use snark_verifier::system::halo2::transcript::evm::{ChallengeEvm, EvmTranscript};
+use snark_verifier::system::halo2::{compile, Config};
+use snark_verifier::verifier::plonk::{PlonkProtocol, PlonkVerifier};
+use snark_verifier::verifier::SnarkVerifier;
+
+use crate::circuit::FiboCircuit;
+use crate::error::FibError;
+use crate::kzg::{self, Bn256, Fr, G1Affine};
+
+/// snark-verifier's PLONK verifier for `kzg`'s SHPLONK openings, which it
+/// calls BDFG21 after the paper.
+type Verifier = PlonkVerifier<KzgAs<Bn256, Bdfg21>>;
+
+/// The Keccak transcript `prove` writes and the contract reads.
+type Transcript<S> = EvmTranscript<G1Affine, NativeLoader, S, Vec<u8>>;
+
+/// The Solidity source of a contract verifying proofs under `vk` with
+/// `instances` public inputs, `OutputMode::instance_rows` of its spec.
+///
+/// # Panics
+///
+/// If `vk` is for another `k` than `params`.
+pub fn solidity_verifier(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, instances: usize) -> String {
+    let _span = tracing::info_span!("evm_verifier", k = params.k()).entered();
+    let protocol = protocol(params, vk, instances);
+    let loader = EvmLoader::new::<halo2_axiom::halo2curves::bn256::Fq, Fr>();
+    let protocol = protocol.loaded(&loader);
+    let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader);
+    let instances = transcript.load_instances(vec![instances]);
+    let dk = deciding_key(params);
+    // The EVM loader only records the operations, so reading and checking
+    // the symbolic proof cannot fail.
+    let proof = Verifier::read_proof(&dk, &protocol, &instances, &mut transcript).expect("reading a symbolic proof");
+    Verifier::verify(&dk, &protocol, &instances, &proof).expect("checking a symbolic proof");
+    loader.solidity_code()
+}
+
+/// `kzg::prove` with the Keccak transcript, for `solidity_verifier`'s
+/// contract.
+pub fn prove(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: &FiboCircuit<Fr>,
+    instance: &[Fr],
+) -> Result<Vec<u8>, FibError> {
+    let _span = tracing::info_span!("evm_prove", k = params.k()).entered();
+    kzg::prove_with::<Transcript<Vec<u8>>, ChallengeEvm<G1Affine>>(params, pk, circuit, instance)
+}
+
+/// Checks a proof from `prove` as the contract would, pairing included.
+/// Whatever fails is `FibError::ProofVerificationFailed`.
+///
+/// # Panics
+///
+/// If `vk` is for another `k` than `params`.
+pub fn verify(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
+    instance: &[Fr],
+) -> Result<(), FibError> {
+    let _span = tracing::info_span!("evm_verify", k = params.k()).entered();
+    let protocol = protocol(params, vk, instance.len());
+    let instances = [instance.to_vec()];
+    let dk = deciding_key(params);
+    let mut transcript = Transcript::new(proof);
+    let proof = Verifier::read_proof(&dk, &protocol, &instances, &mut transcript)
+        .map_err(|_| FibError::ProofVerificationFailed)?;
+    Verifier::verify(&dk, &protocol, &instances, &proof).map_err(|_| FibError::ProofVerificationFailed)
+}
+
+/// The calldata of a call to the contract: each public input as a 32-byte
+/// big-endian word, then the proof bytes as they are.
+pub fn calldata(instance: &[Fr], proof: &[u8]) -> Vec<u8> {
+    encode_calldata(&[instance.to_vec()], proof)
+}
+
+fn protocol(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, instances: usize) -> PlonkProtocol<G1Affine> {
+    compile(params, vk, Config::kzg().with_num_instance(vec![instances]))
+}
+
+/// The generator and the two G2 points the final pairing check takes.
+fn deciding_key(params: &ParamsKZG<Bn256>) -> KzgDecidingKey<Bn256> {
+    (params.get_g()[0], params.g2(), params.s_g2()).into()
+}
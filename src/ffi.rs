@@ -0,0 +1,155 @@
+//! A C interface to the prover, behind the `ffi` feature and exported from
+//! the `cdylib`; `include/fibvar.h` declares it. Proofs cross as byte
+//! buffers in `Proof`'s binary encoding, and the IPA parameters come from a
+//! `ParamsCache` directory, made on first use. A buffer `fibvar_prove`
+//! returns belongs to the caller until it goes back to `fibvar_free_proof`;
+//! buffers and strings passed in stay the caller's and are only read during
+//! the call. Every function returns one of the `FIBVAR_*` codes and never
+//! unwinds into the caller.
+
+use std::ffi::{c_char, c_int, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::{ptr, slice};
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::Fp;
+
+use crate::circuit::{FiboCircuit, FiboSpec};
+use crate::error::FibError;
+use crate::fingerprint::VkHash;
+use crate::params::ParamsCache;
+use crate::parse::parse_field;
+use crate::proof::Proof;
+use crate::prover;
+use crate::sequence::witness;
+
+pub const FIBVAR_OK: c_int = 0;
+/// A null pointer, a string that is not UTF-8 or not a field element, or
+/// no terms.
+pub const FIBVAR_INVALID_ARGUMENT: c_int = 1;
+/// The parameter cache could not be read or written.
+pub const FIBVAR_IO_ERROR: c_int = 2;
+/// Proving failed, or the proof is malformed or does not verify.
+pub const FIBVAR_PROOF_ERROR: c_int = 3;
+/// A bug: the call panicked.
+pub const FIBVAR_PANIC: c_int = 4;
+
+fn code(err: FibError) -> c_int {
+    match err {
+        FibError::InvalidLength { .. }
+        | FibError::KTooSmall(_)
+        | FibError::KTooLarge { .. }
+        | FibError::Unsupported(_)
+        | FibError::ParamsMismatch { .. } => FIBVAR_INVALID_ARGUMENT,
+        FibError::Io(_) => FIBVAR_IO_ERROR,
+        FibError::ProofVerificationFailed | FibError::VkMismatch { .. } | FibError::Synthesis(_) => {
+            FIBVAR_PROOF_ERROR
+        }
+    }
+}
+
+/// Runs `f`, turning a panic into `FIBVAR_PANIC`.
+fn guard(f: impl FnOnce() -> Result<(), c_int>) -> c_int {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => FIBVAR_OK,
+        Ok(Err(code)) => code,
+        Err(_) => FIBVAR_PANIC,
+    }
+}
+
+/// Reads a C string, or `None` if `s` is null.
+unsafe fn string<'a>(s: *const c_char) -> Result<Option<&'a str>, c_int> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(s).to_str().map(Some).map_err(|_| FIBVAR_INVALID_ARGUMENT)
+}
+
+unsafe fn field(s: *const c_char) -> Result<Fp, c_int> {
+    let s = string(s)?.ok_or(FIBVAR_INVALID_ARGUMENT)?;
+    parse_field(s).map_err(|_| FIBVAR_INVALID_ARGUMENT)
+}
+
+/// The cache in `dir`, or in `params` if `dir` is null.
+unsafe fn cache(dir: *const c_char) -> Result<ParamsCache, c_int> {
+    Ok(ParamsCache::new(PathBuf::from(string(dir)?.unwrap_or("params"))))
+}
+
+/// Proves the `num` terms of the variant from `a`, `b` and `c`, decimal or
+/// `0x` hex, at the smallest `k`, exposing the last. On success stores the
+/// proof and its length in `*proof` and `*proof_len`; free it with
+/// `fibvar_free_proof`. `params_dir` may be null for `params`.
+///
+/// # Safety
+///
+/// `a`, `b` and `c` must be valid C strings, `params_dir` one or null, and
+/// `proof` and `proof_len` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fibvar_prove(
+    a: *const c_char,
+    b: *const c_char,
+    c: *const c_char,
+    num: usize,
+    params_dir: *const c_char,
+    proof: *mut *mut u8,
+    proof_len: *mut usize,
+) -> c_int {
+    guard(|| {
+        if proof.is_null() || proof_len.is_null() {
+            return Err(FIBVAR_INVALID_ARGUMENT);
+        }
+        let spec = FiboSpec { num, ..FiboSpec::default() };
+        spec.check_num().map_err(code)?;
+        let terms = witness(field(a)?, field(b)?, field(c)?, spec.layout_terms());
+        let instance = vec![terms[num - 1]];
+        let circuit = FiboCircuit { spec, ..FiboCircuit::from_witness(terms.into_iter().map(Value::known).collect()) };
+        let k = circuit.spec.min_k();
+        let params = cache(params_dir)?.get(k).map_err(|_| FIBVAR_IO_ERROR)?;
+        let pk = prover::keygen(&params, &circuit).map_err(code)?;
+        let bytes = prover::prove(&params, &pk, &circuit, &instance).map_err(code)?;
+        let vk_hash = VkHash::of(pk.get_vk());
+        let bytes = Proof::new(k, circuit.spec, instance, bytes, vk_hash).to_bytes().into_boxed_slice();
+        *proof_len = bytes.len();
+        *proof = Box::into_raw(bytes).cast();
+        Ok(())
+    })
+}
+
+/// Checks the `proof_len` bytes at `proof` against the public inputs they
+/// carry. `params_dir` may be null for `params`.
+///
+/// # Safety
+///
+/// `proof` must be valid for reads of `proof_len` bytes, and `params_dir` a
+/// valid C string or null.
+#[no_mangle]
+pub unsafe extern "C" fn fibvar_verify(
+    proof: *const u8,
+    proof_len: usize,
+    params_dir: *const c_char,
+) -> c_int {
+    guard(|| {
+        if proof.is_null() {
+            return Err(FIBVAR_INVALID_ARGUMENT);
+        }
+        let proof = Proof::from_bytes(slice::from_raw_parts(proof, proof_len)).map_err(|_| FIBVAR_PROOF_ERROR)?;
+        proof.check_shape().map_err(code)?;
+        let params = cache(params_dir)?.get(proof.k).map_err(|_| FIBVAR_IO_ERROR)?;
+        proof.verify(&params).map_err(code)
+    })
+}
+
+/// Frees a proof from `fibvar_prove`, given the length it came with. Null is
+/// ignored.
+///
+/// # Safety
+///
+/// `proof` and `proof_len` must be as `fibvar_prove` returned them, and the
+/// proof not freed already.
+#[no_mangle]
+pub unsafe extern "C" fn fibvar_free_proof(proof: *mut u8, proof_len: usize) {
+    if !proof.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(proof, proof_len)));
+    }
+}
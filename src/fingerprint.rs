@@ -0,0 +1,82 @@
+//! A short, stable name for a verifying key. halo2 hashes every key into
+//! the start of every transcript as one scalar, Blake2b over the key's
+//! pinned form: the domain, the constraint system (gates, column counts,
+//! queries and the equality set) and the fixed and permutation commitments.
+//! `VkHash` is that scalar's bytes. Two keys share one exactly when a proof
+//! for one is a proof for the other, and any change to the constraints,
+//! intended or not, changes it.
+
+use std::str::FromStr;
+use std::{error, fmt, io};
+
+use halo2_proofs::pasta::group::ff::PrimeField;
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::VerifyingKey;
+use halo2_proofs::transcript::{Challenge255, EncodedChallenge, Transcript};
+
+/// The fingerprint of a verifying key; prints as 64 lowercase hex digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VkHash(pub [u8; 32]);
+
+impl VkHash {
+    pub fn of(vk: &VerifyingKey<EqAffine>) -> Self {
+        let mut capture = Capture(None);
+        vk.hash_into(&mut capture).expect("capturing a scalar cannot fail");
+        let repr = capture.0.expect("hash_into writes the key's scalar");
+        Self(repr.to_repr())
+    }
+}
+
+impl fmt::Display for VkHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl FromStr for VkHash {
+    type Err = ParseVkHashError;
+
+    /// Parses the 64 hex digits `Display` prints, in either case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 64 {
+            return Err(ParseVkHashError);
+        }
+        let mut bytes = [0; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let digits = s.get(2 * i..2 * i + 2).ok_or(ParseVkHashError)?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| ParseVkHashError)?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+/// The string is not 64 hex digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseVkHashError;
+
+impl fmt::Display for ParseVkHashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a fingerprint is 64 hex digits")
+    }
+}
+
+impl error::Error for ParseVkHashError {}
+
+/// Keeps the scalar `VerifyingKey::hash_into` writes, which halo2 does not
+/// expose otherwise.
+struct Capture(Option<Fp>);
+
+impl Transcript<EqAffine, Challenge255<EqAffine>> for Capture {
+    fn squeeze_challenge(&mut self) -> Challenge255<EqAffine> {
+        Challenge255::new(&[0; 64])
+    }
+
+    fn common_point(&mut self, _: EqAffine) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn common_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.0 = Some(scalar);
+        Ok(())
+    }
+}
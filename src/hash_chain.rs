@@ -0,0 +1,78 @@
+//! Reference hash-chain circuit: `num` sequential Poseidon hashes of a seed.
+//! This is the usual way to prove a sequential computation of a given
+//! length, and serves as a cost baseline for the arithmetic recurrence.
+
+use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength, P128Pow5T3};
+use halo2_gadgets::poseidon::{Hash, Pow5Chip, Pow5Config};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance};
+
+type ChainHash = poseidon::Hash<Fp, P128Pow5T3, ConstantLength<1>, 3, 2>;
+
+#[derive(Clone, Debug)]
+pub struct HashChainConfig {
+    poseidon: Pow5Config<Fp, 3, 2>,
+    seed: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+/// Proves that hashing `seed` `num` times yields the value on instance row 0.
+#[derive(Default)]
+pub struct HashChainCircuit {
+    pub seed: Value<Fp>,
+    pub num: usize,
+}
+
+impl Circuit<Fp> for HashChainCircuit {
+    type Config = HashChainConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            seed: Value::unknown(),
+            num: self.num,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let state = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let partial_sbox = meta.advice_column();
+        let rc_a = [meta.fixed_column(), meta.fixed_column(), meta.fixed_column()];
+        let rc_b = [meta.fixed_column(), meta.fixed_column(), meta.fixed_column()];
+        meta.enable_constant(rc_b[0]);
+
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        HashChainConfig {
+            poseidon: Pow5Chip::configure::<P128Pow5T3>(meta, state, partial_sbox, rc_a, rc_b),
+            seed: state[0],
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let mut state = layouter.assign_region(
+            || "load seed",
+            |mut region| region.assign_advice(|| "seed", config.seed, 0, || self.seed),
+        )?;
+        for _ in 0..self.num {
+            let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<1>, 3, 2>::init(
+                Pow5Chip::construct(config.poseidon.clone()),
+                layouter.namespace(|| "init"),
+            )?;
+            state = hasher.hash(layouter.namespace(|| "hash"), [state])?;
+        }
+        layouter.constrain_instance(state.cell(), config.instance, 0)
+    }
+}
+
+/// Native counterpart of [`HashChainCircuit`].
+pub fn hash_chain(seed: Fp, num: usize) -> Fp {
+    (0..num).fold(seed, |state, _| ChainHash::init().hash([state]))
+}
@@ -0,0 +1,154 @@
+//! Outputs behind Poseidon. `OutputMode::Hashed` exposes the hash of the
+//! last term instead of the term, so a proof commits to the result without
+//! revealing it; `OutputMode::Committed` keeps `a, b, c` private behind a
+//! commitment and exposes the result next to it. The gadget's Poseidon is
+//! over the Pasta base field, so these circuits are too. The Poseidon chip
+//! shares the step chip's four advice columns and adds only its round
+//! constants.
+
+use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength, P128Pow5T3};
+use halo2_gadgets::poseidon::{Hash, Pow5Chip, Pow5Config};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+use crate::chip::{FiboChip, FiboConfig, Number, SequenceChip};
+use crate::circuit::{FiboCircuit, FiboSpec, Layout, OutputMode};
+use crate::recurrence::Recurrence;
+
+type ResultHash = poseidon::Hash<Fp, P128Pow5T3, ConstantLength<1>, 3, 2>;
+type InitialHash = poseidon::Hash<Fp, P128Pow5T3, ConstantLength<4>, 3, 2>;
+
+/// Rows the Poseidon regions take below the sequence.
+pub const HASH_ROWS: usize = 41;
+
+/// Rows the blinding and the Poseidon regions of a commitment take below
+/// the sequence.
+pub const COMMIT_ROWS: usize = 82;
+
+/// The value a hashed proof exposes for `result`.
+pub fn hash_result(result: Fp) -> Fp {
+    ResultHash::init().hash([result])
+}
+
+/// The commitment a committed proof exposes for `a, b, c`. `blinding`
+/// should be fresh randomness kept with the opening: without it, guessable
+/// initial values could be found by hashing candidates.
+pub fn commit_initial(a: Fp, b: Fp, c: Fp, blinding: Fp) -> Fp {
+    InitialHash::init().hash([a, b, c, blinding])
+}
+
+#[derive(Clone, Debug)]
+pub struct HashedConfig {
+    pub fibo: FiboConfig,
+    pub poseidon: Pow5Config<Fp, 3, 2>,
+}
+
+impl HashedConfig {
+    pub fn configure(meta: &mut ConstraintSystem<Fp>) -> Self {
+        let _span = tracing::info_span!("configure", poseidon = true).entered();
+        let [a, b, c, d] = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let rc_a = [(); 3].map(|_| meta.fixed_column());
+        let rc_b = [(); 3].map(|_| meta.fixed_column());
+        meta.enable_constant(rc_b[0]);
+
+        Self {
+            fibo: FiboChip::configure_with(meta, [a, b, c, d], instance),
+            poseidon: Pow5Chip::configure::<P128Pow5T3>(meta, [a, b, c], d, rc_a, rc_b),
+        }
+    }
+}
+
+/// Refuses a spec other than the four-column variant with `output`.
+pub(crate) fn check_spec(spec: &FiboSpec, output: OutputMode) -> Result<(), Error> {
+    if spec.output != output || spec.layout != Layout::Rows || spec.recurrence != Recurrence::Variant {
+        return Err(Error::Synthesis);
+    }
+    spec.check_num().map_err(|_| Error::Synthesis)
+}
+
+/// `FiboCircuit` in `OutputMode::Hashed`, which its spec must say, in the
+/// four-column layout and for the variant.
+#[derive(Clone, Default)]
+pub struct FiboCircuitHashed(pub FiboCircuit<Fp>);
+
+impl Circuit<Fp> for FiboCircuitHashed {
+    type Config = HashedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        HashedConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        check_spec(&self.0.spec, OutputMode::Hashed)?;
+        let _span = tracing::info_span!("synthesize", num = self.0.spec.num, output = "hashed").entered();
+        let chip = FiboChip::construct(config.fibo);
+        let cells = chip.load_sequence(layouter.namespace(|| "sequence"), &self.0.full_witness())?;
+        let result = cells.result(self.0.spec.num)?.clone();
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<1>, 3, 2>::init(
+            Pow5Chip::construct(config.poseidon),
+            layouter.namespace(|| "init"),
+        )?;
+        let digest = hasher.hash(layouter.namespace(|| "hash result"), [result.0])?;
+        chip.expose_public(layouter.namespace(|| "expose public"), [&Number(digest)], 0)
+    }
+}
+
+/// `FiboCircuit` in `OutputMode::Committed`, which its spec must say, in the
+/// four-column layout and for the variant. Instance rows: the commitment
+/// to `a, b, c` at 0, the last term at 1.
+#[derive(Clone, Default)]
+pub struct FiboCircuitCommitted {
+    pub circuit: FiboCircuit<Fp>,
+    /// The commitment's randomness; see `commit_initial`.
+    pub blinding: Value<Fp>,
+}
+
+impl Circuit<Fp> for FiboCircuitCommitted {
+    type Config = HashedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            circuit: self.circuit.without_witnesses(),
+            blinding: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        HashedConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        check_spec(&self.circuit.spec, OutputMode::Committed)?;
+        let _span = tracing::info_span!("synthesize", num = self.circuit.spec.num, output = "committed").entered();
+        let chip = FiboChip::construct(config.fibo);
+        let cells = chip.load_sequence(layouter.namespace(|| "sequence"), &self.circuit.full_witness())?;
+        let blinding = layouter.assign_region(
+            || "blinding",
+            |mut region| region.assign_advice(|| "blinding", config.fibo.a, 0, || self.blinding),
+        )?;
+        let result = cells.result(self.circuit.spec.num)?.clone();
+        let [a, b, c] = cells.initial;
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<4>, 3, 2>::init(
+            Pow5Chip::construct(config.poseidon),
+            layouter.namespace(|| "init"),
+        )?;
+        let commitment = hasher.hash(layouter.namespace(|| "commit initial"), [a.0, b.0, c.0, blinding])?;
+        chip.expose_public(layouter.namespace(|| "expose public"), [&Number(commitment), &result], 0)
+    }
+}
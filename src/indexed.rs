@@ -0,0 +1,243 @@
+//! Proving the term at a public index: "term `index` is `x`", not only
+//! "the last term is `x`". The circuit holds `num` terms, one per row, next
+//! to a counter `n` of the row's term index; every row compares `n` with
+//! the target `t` and adds its term to a running sum where they match.
+//!
+//! The match flag `hit` is an is-zero check on `n - t`, with `inv` its
+//! inverse when there is one. A running count of hits must end at exactly
+//! one, so an index past the end cannot pass off zero as its term. `t` is
+//! copied down every row and tied to the instance at row 0 only.
+//!
+//! Instance rows: the term at 0, the index at 1, and `a, b, c` at 2 to 4,
+//! copied from the first three terms; a private start would let a prover
+//! pick one whose term at the index is any value it likes.
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+
+use crate::sequence::{field_from_usize, witness};
+
+#[derive(Clone, Debug, Copy)]
+pub struct IndexedConfig {
+    /// The terms, one per row.
+    pub x: Column<Advice>,
+    /// Index of the row's term.
+    pub n: Column<Advice>,
+    /// The target index, the same on every row.
+    pub t: Column<Advice>,
+    /// One where `n = t`, zero elsewhere.
+    pub hit: Column<Advice>,
+    /// `1 / (n - t)`, or zero on the hit.
+    pub inv: Column<Advice>,
+    /// Sum of `hit * x` up to and including this row.
+    pub acc: Column<Advice>,
+    /// Sum of `hit` up to and including this row.
+    pub found: Column<Advice>,
+    pub i: Column<Instance>,
+    /// Every row.
+    pub s: Selector,
+    /// The first row only.
+    pub s_first: Selector,
+    /// Every row but the first.
+    pub s_chain: Selector,
+    /// Rows from the fourth term on.
+    pub s_step: Selector,
+    /// The last row only.
+    pub s_last: Selector,
+}
+
+impl IndexedConfig {
+    pub fn configure<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        let [x, n, t, hit, inv, acc, found] = [(); 7].map(|_| meta.advice_column());
+        let i = meta.instance_column();
+        let [s, s_first, s_chain, s_step, s_last] = [(); 5].map(|_| meta.selector());
+
+        meta.enable_equality(x);
+        meta.enable_equality(t);
+        meta.enable_equality(acc);
+        meta.enable_equality(i);
+
+        meta.create_gate("indexed hit", |meta| {
+            let s = meta.query_selector(s);
+            let n = meta.query_advice(n, Rotation::cur());
+            let t = meta.query_advice(t, Rotation::cur());
+            let hit = meta.query_advice(hit, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            vec![
+                s.clone() * (n.clone() - t.clone()) * hit.clone(),
+                s * (one - hit - (n - t) * inv),
+            ]
+        });
+
+        meta.create_gate("indexed first row", |meta| {
+            let s = meta.query_selector(s_first);
+            let x = meta.query_advice(x, Rotation::cur());
+            let n = meta.query_advice(n, Rotation::cur());
+            let hit = meta.query_advice(hit, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let found = meta.query_advice(found, Rotation::cur());
+            vec![
+                s.clone() * n,
+                s.clone() * (acc - hit.clone() * x),
+                s * (found - hit),
+            ]
+        });
+
+        meta.create_gate("indexed chain", |meta| {
+            let s = meta.query_selector(s_chain);
+            let n_prev = meta.query_advice(n, Rotation::prev());
+            let t_prev = meta.query_advice(t, Rotation::prev());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let found_prev = meta.query_advice(found, Rotation::prev());
+            let x = meta.query_advice(x, Rotation::cur());
+            let n = meta.query_advice(n, Rotation::cur());
+            let t = meta.query_advice(t, Rotation::cur());
+            let hit = meta.query_advice(hit, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let found = meta.query_advice(found, Rotation::cur());
+            vec![
+                s.clone() * (n - n_prev - Expression::Constant(F::ONE)),
+                s.clone() * (t - t_prev),
+                s.clone() * (acc - acc_prev - hit.clone() * x),
+                s * (found - found_prev - hit),
+            ]
+        });
+
+        meta.create_gate("indexed step", |meta| {
+            let s = meta.query_selector(s_step);
+            let x3 = meta.query_advice(x, Rotation(-3));
+            let x2 = meta.query_advice(x, Rotation(-2));
+            let x1 = meta.query_advice(x, Rotation::prev());
+            let x0 = meta.query_advice(x, Rotation::cur());
+            vec![s * ((x1 + x3) * x2 - x0)]
+        });
+
+        meta.create_gate("indexed last row", |meta| {
+            let s = meta.query_selector(s_last);
+            let found = meta.query_advice(found, Rotation::cur());
+            vec![s * (found - Expression::Constant(F::ONE))]
+        });
+
+        Self { x, n, t, hit, inv, acc, found, i, s, s_first, s_chain, s_step, s_last }
+    }
+}
+
+/// The first `num` terms, proving the one at `index`. Only `num` shapes the
+/// circuit; `index` is a witness, checked against instance row 1, and
+/// `a, b, c` against rows 2 to 4.
+#[derive(Clone, Debug, Default)]
+pub struct FiboCircuitIndexed<F> {
+    pub num: usize,
+    pub index: Value<usize>,
+    pub a: Value<F>,
+    pub b: Value<F>,
+    pub c: Value<F>,
+}
+
+impl<F: Field> FiboCircuitIndexed<F> {
+    /// Cell values per row: `x, n, t, hit, inv, acc, found`.
+    fn assignments(&self) -> Value<Vec<[F; 7]>> {
+        let num = self.num;
+        self.a.zip(self.b).zip(self.c).zip(self.index).map(|(((a, b), c), index)| {
+            let t = field_from_usize::<F>(index);
+            let (mut acc, mut found) = (F::ZERO, F::ZERO);
+            witness(a, b, c, num)
+                .into_iter()
+                .enumerate()
+                .map(|(row, x)| {
+                    let n = field_from_usize::<F>(row);
+                    let hit = if row == index { F::ONE } else { F::ZERO };
+                    let inv = (n - t).invert().unwrap_or(F::ZERO);
+                    acc += hit * x;
+                    found += hit;
+                    [x, n, t, hit, inv, acc, found]
+                })
+                .collect()
+        })
+    }
+}
+
+impl<F: Field> Circuit<F> for FiboCircuitIndexed<F> {
+    type Config = IndexedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            num: self.num,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        IndexedConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        if self.num < 4 {
+            return Err(Error::Synthesis);
+        }
+        let cells = self.assignments();
+        let columns = [
+            config.x, config.n, config.t, config.hit, config.inv, config.acc, config.found,
+        ];
+
+        let (target, value, initial) = layouter.assign_region(
+            || "indexed rows",
+            |mut region| {
+                let (mut target, mut value, mut initial) = (None, None, vec![]);
+                for row in 0..self.num {
+                    config.s.enable(&mut region, row)?;
+                    if row == 0 {
+                        config.s_first.enable(&mut region, row)?;
+                    } else {
+                        config.s_chain.enable(&mut region, row)?;
+                    }
+                    if row >= 3 {
+                        config.s_step.enable(&mut region, row)?;
+                    }
+                    if row == self.num - 1 {
+                        config.s_last.enable(&mut region, row)?;
+                    }
+                    for (j, column) in columns.into_iter().enumerate() {
+                        let cell = region.assign_advice(
+                            || format!("row {} column {}", row, j),
+                            column,
+                            row,
+                            || cells.as_ref().map(|cells| cells[row][j]),
+                        )?;
+                        // `t` of the first row, `acc` of the last and
+                        // `a, b, c` for the instance.
+                        if row == 0 && column == config.t {
+                            target = Some(cell);
+                        } else if row == self.num - 1 && column == config.acc {
+                            value = Some(cell);
+                        } else if row < 3 && column == config.x {
+                            initial.push(cell);
+                        }
+                    }
+                }
+                Ok((
+                    target.expect("the layout has a first row"),
+                    value.expect("the layout has a last row"),
+                    initial,
+                ))
+            },
+        )?;
+
+        layouter.constrain_instance(value.cell(), config.i, 0)?;
+        layouter.constrain_instance(target.cell(), config.i, 1)?;
+        for (row, cell) in initial.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.i, row + 2)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+//! What a circuit costs before proving it: rows, columns, degree and the
+//! quotient and extended domain it makes, the smallest `k` and the proof
+//! size, so the parameters can be sized up front. The columns and degree
+//! come from the constraint system, the rows, selectors and copies from a
+//! recorded layout, and the proof size from halo2's dev cost model.
+//!
+//! `QueryReport` adds what the verifier pays for: the distinct cells
+//! relative to a row, `(column, rotation)`, the gates query, each one an
+//! evaluation in the proof, and how the multi-open argument batches them.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::{fmt, iter};
+
+use halo2_proofs::circuit::Layouter;
+use halo2_proofs::dev::CircuitCost;
+use halo2_proofs::pasta::{Eq, Fp};
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+use halo2_proofs::poly::EvaluationDomain;
+
+use crate::circuit::FiboCircuit;
+use crate::dispatch::{with_circuit, Unsupported, WithCircuit};
+use crate::planner::{Planner, WithPlanner};
+use crate::row_usage::RowUsage;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitReport {
+    /// `k` the circuit was laid out at.
+    pub k: u32,
+    /// Rows up to and including the last one assigned in any column.
+    pub rows: usize,
+    pub advice_columns: usize,
+    /// Fixed columns the circuit allocates, before keygen adds those its
+    /// selectors are compressed into.
+    pub fixed_columns: usize,
+    /// Selectors the layout enables.
+    pub selectors: usize,
+    pub instance_columns: usize,
+    /// Columns the layout copies between. Each has equality enabled, so the
+    /// permutation argument spans at least these; halo2 does not expose the
+    /// columns it was enabled on without being copied.
+    pub copied_columns: usize,
+    /// The largest gate degree, with that of the permutation and lookups.
+    pub degree: usize,
+    /// The degree of the quotient polynomial at `k`, `(degree - 1) * 2^k - 1`;
+    /// the prover commits to it in `degree - 1` pieces.
+    pub quotient_degree: usize,
+    /// Points of the extended domain the quotient is evaluated over at `k`,
+    /// the smallest power of two holding it; proving time grows with it.
+    pub extended_domain: usize,
+    /// The smallest `k` whose rows fit `rows` and halo2's blinding rows.
+    pub min_k: u32,
+    /// Bytes of a proof with one instance of the circuit.
+    pub proof_size: usize,
+}
+
+impl CircuitReport {
+    /// Lays `circuit` out at `k`, which must be large enough for it. Panics,
+    /// as the cost model does, if synthesis fails.
+    pub fn measure<C: Circuit<Fp>>(k: u32, circuit: C) -> Self {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        C::configure(&mut cs);
+        let usage = RowUsage::measure(&circuit).expect("the circuit synthesizes");
+        let required = (usage.total_rows + cs.blinding_factors() + 1).max(cs.minimum_rows());
+        let proof_size = CircuitCost::<Eq, Costed<C>>::measure(k, &Costed(circuit)).proof_size(1).into();
+
+        let domain = EvaluationDomain::<Fp>::new(cs.degree() as u32, k);
+
+        let mut fresh = ConstraintSystem::<Fp>::default();
+        Self {
+            k,
+            rows: usage.total_rows,
+            advice_columns: count(cs.clone().advice_column(), || fresh.advice_column()),
+            fixed_columns: count(cs.clone().fixed_column(), || fresh.fixed_column()),
+            selectors: usage.selectors,
+            instance_columns: count(cs.clone().instance_column(), || fresh.instance_column()),
+            copied_columns: usage.copied_columns,
+            degree: cs.degree(),
+            quotient_degree: domain.get_quotient_poly_degree() * (1 << k) - 1,
+            extended_domain: domain.extended_len(),
+            min_k: required.next_power_of_two().trailing_zeros(),
+            proof_size,
+        }
+    }
+}
+
+/// How many columns of one type a constraint system has, given the next
+/// one it would allocate. halo2 keeps the counts private, but orders the
+/// columns of a type by index, so that many fresh columns sort before it.
+fn count<C: Ord>(next: C, fresh: impl FnMut() -> C) -> usize {
+    iter::repeat_with(fresh).take_while(|column| *column < next).count()
+}
+
+impl fmt::Display for CircuitReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rows used:           {}", self.rows)?;
+        writeln!(f, "advice columns:      {}", self.advice_columns)?;
+        writeln!(f, "fixed columns:       {}", self.fixed_columns)?;
+        writeln!(f, "selectors:           {}", self.selectors)?;
+        writeln!(f, "instance columns:    {}", self.instance_columns)?;
+        writeln!(f, "copied columns:      {}", self.copied_columns)?;
+        writeln!(f, "degree:              {}", self.degree)?;
+        writeln!(f, "quotient degree:     {}", self.quotient_degree)?;
+        writeln!(f, "extended domain:     {}", self.extended_domain)?;
+        writeln!(f, "minimum k:           {}", self.min_k)?;
+        write!(f, "proof size:          {} bytes", self.proof_size)
+    }
+}
+
+/// The `(column, rotation)` queries of a circuit's gates and the point sets
+/// the multi-open argument opens them in: every column, and every column of
+/// the permutation at rotation 0, is opened at the set of rotations it is
+/// queried at, so columns with the same set share one opening. On top of
+/// these the permutation and any lookups open sets of their own, and keygen
+/// compresses the selectors into fixed columns queried at rotation 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryReport {
+    pub advice_queries: usize,
+    pub fixed_queries: usize,
+    pub instance_queries: usize,
+    /// Every rotation queried, ascending.
+    pub rotations: Vec<i32>,
+    /// Each distinct set of rotations a column is opened at, ascending, with
+    /// how many columns are.
+    pub point_sets: Vec<(Vec<i32>, usize)>,
+}
+
+impl QueryReport {
+    /// Reads the queries off `C`'s constraint system. halo2 keeps them
+    /// private except in the `Debug` output of `ConstraintSystem::pinned`,
+    /// which is what keys are hashed over, so this parses that.
+    pub fn of<C: Circuit<Fp>>() -> Self {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        C::configure(&mut cs);
+        let pinned = format!("{:?}", cs.pinned());
+        let [advice, fixed, instance, permutation] =
+            ["advice_queries: [", "fixed_queries: [", "instance_queries: [", "permutation: Argument { columns: ["]
+                .map(|field| pinned_columns(&pinned, field));
+        let mut opened: BTreeMap<(&str, usize), BTreeSet<i32>> = BTreeMap::new();
+        for &(column_type, index, rotation) in advice.iter().chain(&fixed).chain(&instance).chain(&permutation) {
+            opened.entry((column_type, index)).or_default().insert(rotation);
+        }
+        let mut point_sets: BTreeMap<Vec<i32>, usize> = BTreeMap::new();
+        for rotations in opened.values() {
+            *point_sets.entry(rotations.iter().copied().collect()).or_default() += 1;
+        }
+        let rotations: BTreeSet<i32> = opened.values().flatten().copied().collect();
+        Self {
+            advice_queries: advice.len(),
+            fixed_queries: fixed.len(),
+            instance_queries: instance.len(),
+            rotations: rotations.into_iter().collect(),
+            point_sets: point_sets.into_iter().collect(),
+        }
+    }
+}
+
+/// The `(column type, index, rotation)` of each entry of the list starting
+/// at `field` in a pinned constraint system's `Debug` output; a column
+/// without a rotation, as in the permutation, is at rotation 0.
+fn pinned_columns<'a>(pinned: &'a str, field: &str) -> Vec<(&'a str, usize, i32)> {
+    let Some(start) = pinned.find(field) else {
+        return vec![];
+    };
+    let list = &pinned[start + field.len()..];
+    let list = &list[..list.find(']').unwrap_or(list.len())];
+    list.split("Column { index: ")
+        .skip(1)
+        .filter_map(|entry| {
+            let (index, rest) = entry.split_once(", column_type: ")?;
+            let column_type = rest.split(|c: char| !c.is_alphanumeric()).next()?;
+            let rotation = match rest.split_once("Rotation(") {
+                Some((_, rotation)) => rotation.split_once(')')?.0.parse().ok()?,
+                None => 0,
+            };
+            Some((column_type, index.parse().ok()?, rotation))
+        })
+        .collect()
+}
+
+impl fmt::Display for QueryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rotations = |rotations: &[i32]| rotations.iter().map(i32::to_string).collect::<Vec<_>>().join(", ");
+        writeln!(f, "advice queries:      {}", self.advice_queries)?;
+        writeln!(f, "fixed queries:       {}", self.fixed_queries)?;
+        writeln!(f, "instance queries:    {}", self.instance_queries)?;
+        writeln!(f, "rotations:           {}", rotations(&self.rotations))?;
+        let sets: Vec<String> =
+            self.point_sets.iter().map(|(set, columns)| format!("{{{}}} x {}", rotations(set), columns)).collect();
+        write!(f, "point sets:          {}", sets.join(", "))
+    }
+}
+
+/// The report for whichever circuit `circuit`'s spec calls for, laid out at
+/// `k`, e.g. the spec's `min_k`.
+pub fn inspect(circuit: FiboCircuit<Fp>, k: u32) -> Result<CircuitReport, Unsupported> {
+    with_circuit(circuit, Measure(k))
+}
+
+/// `inspect` with the circuit laid out by `planner` instead.
+pub fn inspect_planned(circuit: FiboCircuit<Fp>, k: u32, planner: Planner) -> Result<CircuitReport, Unsupported> {
+    with_circuit(circuit, WithPlanner { planner, f: Measure(k) })
+}
+
+/// The `QueryReport` of whichever circuit `circuit`'s spec calls for.
+pub fn inspect_queries(circuit: FiboCircuit<Fp>) -> Result<QueryReport, Unsupported> {
+    with_circuit(circuit, Queries)
+}
+
+struct Measure(u32);
+
+impl WithCircuit for Measure {
+    type Output = CircuitReport;
+
+    fn with<C: Circuit<Fp>>(self, circuit: C) -> Self::Output {
+        CircuitReport::measure(self.0, circuit)
+    }
+}
+
+struct Queries;
+
+impl WithCircuit for Queries {
+    type Output = QueryReport;
+
+    fn with<C: Circuit<Fp>>(self, _circuit: C) -> Self::Output {
+        QueryReport::of::<C>()
+    }
+}
+
+/// `C` for the cost model, whose `Debug` asks the circuit for one too.
+struct Costed<C>(C);
+
+impl<C> fmt::Debug for Costed<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Costed")
+    }
+}
+
+impl<C: Circuit<Fp>> Circuit<Fp> for Costed<C> {
+    type Config = C::Config;
+    type FloorPlanner = C::FloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
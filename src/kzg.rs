@@ -0,0 +1,558 @@
+//! Real proofs with the KZG commitment scheme over bn256, for verifiers that
+//! can check pairings but not IPA over the Pasta curves, such as a contract
+//! on Ethereum. halo2_proofs ships IPA only; this backend is halo2-axiom, a
+//! fork of the PSE halo2 published on crates.io.
+//!
+//! halo2-axiom has its own `Circuit` trait and region API, so `FiboChip`
+//! cannot lay the sequence out for it. Instead `FiboCircuit<Fr>` implements
+//! that trait here as well, with the same columns and gates built from
+//! halo2-axiom's types: the four-column layout, in one region tied together
+//! by the chain gate, exposing `Result`, `Continuation`, `InitialAndResult`
+//! or `Chunk`. The witness is the one `FiboCircuit` computes either way.
+//!
+//! Proofs use the SHPLONK multiopen argument and a Blake2b transcript.
+//! Unlike halo2_proofs' keys, halo2-axiom's can be written out and read
+//! back, so a verifier can ship the verifying key alone and skip keygen.
+//! With the `evm` feature, `evm` generates a Solidity verifier from the
+//! verifying key, for proofs made with a Keccak transcript instead.
+
+use std::array;
+use std::borrow::Cow;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use halo2_axiom::circuit::{Cell, Layouter, SimpleFloorPlanner, Value};
+use halo2_axiom::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column, ConstraintSystem, Error,
+    Expression, Instance, ProvingKey, Selector, VerifyingKey,
+};
+use halo2_axiom::poly::commitment::Params;
+use halo2_axiom::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_axiom::poly::kzg::multiopen::{ProverSHPLONK, VerifierSHPLONK};
+use halo2_axiom::poly::kzg::strategy::SingleStrategy;
+use halo2_axiom::poly::Rotation;
+use halo2_axiom::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, TranscriptReadBuffer, TranscriptWriterBuffer,
+};
+use halo2_axiom::SerdeFormat;
+
+pub use halo2_axiom::halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2_axiom::halo2curves::ff::PrimeField;
+
+use crate::chip::CHAIN_GATE_NAME;
+use crate::circuit::{FiboCircuit, FiboSpec, Layout, OutputMode};
+use crate::compat::os_rng;
+use crate::dispatch::Unsupported;
+use crate::error::FibError;
+use crate::fingerprint::VkHash;
+use crate::proof::{
+    from_hex, invalid, read_array, read_bytes, read_len, read_shape, to_hex, write_len, write_shape, KZG_SCHEME,
+};
+use crate::recurrence::{recurrence_witness, Fibonacci, Recurrence, RecurrenceCircuit, RecurrenceGate, Variant};
+use crate::sequence::field_from_usize;
+
+/// The largest `k` KZG parameters can be made for, the two-adicity of `Fr`.
+pub const MAX_K: u32 = 28;
+
+/// Parameters for `2^k` rows from fresh randomness. Whoever runs this knows
+/// the toxic waste and can forge proofs, so it is for tests and trials;
+/// on chain, read the parameters of a ceremony with `ParamsKZG::read`.
+///
+/// # Panics
+///
+/// If `k` is past `MAX_K`.
+pub fn setup(k: u32) -> ParamsKZG<Bn256> {
+    let _span = tracing::info_span!("kzg_setup", k).entered();
+    ParamsKZG::setup(k, os_rng())
+}
+
+/// `prover::keygen` over bn256: the proving key for the circuit `circuit`'s
+/// spec calls for, from its shape alone.
+pub fn keygen(params: &ParamsKZG<Bn256>, circuit: &FiboCircuit<Fr>) -> Result<ProvingKey<G1Affine>, FibError> {
+    let _span = tracing::info_span!("kzg_keygen", k = params.k()).entered();
+    circuit.spec.check_k(params.k())?;
+    let circuit = FiboCircuit { spec: circuit.spec, ..FiboCircuit::default() };
+    Ok(with_circuit(circuit, Keygen(params))??)
+}
+
+/// `proof::verifying_key` over bn256: the verifying key of the circuit
+/// `spec` calls for, without the proving key.
+pub fn verifying_key(params: &ParamsKZG<Bn256>, spec: FiboSpec) -> Result<VerifyingKey<G1Affine>, FibError> {
+    spec.check_k(params.k())?;
+    let circuit = FiboCircuit { spec, ..FiboCircuit::default() };
+    Ok(with_circuit(circuit, KeygenVk(params))??)
+}
+
+/// `prover::prove` over bn256: proves `circuit` against the values of its
+/// instance column and returns the proof bytes, blinded from the operating
+/// system.
+pub fn prove(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: &FiboCircuit<Fr>,
+    instance: &[Fr],
+) -> Result<Vec<u8>, FibError> {
+    let _span = tracing::info_span!("kzg_prove", k = params.k()).entered();
+    prove_with::<Blake2bWrite<_, _, Challenge255<_>>, _>(params, pk, circuit, instance)
+}
+
+/// `prove` with the transcript `T`, for `evm`'s Keccak one.
+pub(crate) fn prove_with<T, E>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: &FiboCircuit<Fr>,
+    instance: &[Fr],
+) -> Result<Vec<u8>, FibError>
+where
+    T: TranscriptWriterBuffer<Vec<u8>, G1Affine, E>,
+    E: EncodedChallenge<G1Affine>,
+{
+    circuit.spec.check_num()?;
+    circuit.spec.check_k(params.k())?;
+    Ok(with_circuit(circuit.clone(), Prove::<T, E> { params, pk, instance, transcript: PhantomData })??)
+}
+
+/// `prover::verify` over bn256. Whatever halo2-axiom finds wrong is
+/// `FibError::ProofVerificationFailed`.
+pub fn verify(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
+    instance: &[Fr],
+) -> Result<(), FibError> {
+    let _span = tracing::info_span!("kzg_verify", k = params.k()).entered();
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    verify_proof::<KZGCommitmentScheme<Bn256>, VerifierSHPLONK<'_, Bn256>, _, _, _>(
+        params,
+        vk,
+        SingleStrategy::new(params),
+        &[&[instance]],
+        &mut transcript,
+    )
+    .map_err(|_| FibError::ProofVerificationFailed)
+}
+
+/// Writes `vk` for `read_verifying_key`, with its points compressed.
+pub fn write_verifying_key(vk: &VerifyingKey<G1Affine>, writer: &mut impl io::Write) -> io::Result<()> {
+    vk.write(writer, SerdeFormat::Processed)
+}
+
+/// Reads a key `write_verifying_key` wrote for the circuit `spec` calls for.
+/// The file holds the commitments but not the constraints, which come from
+/// the spec's circuit: a key read under the wrong spec reads, then fails to
+/// verify anything.
+pub fn read_verifying_key(reader: &mut impl io::Read, spec: FiboSpec) -> Result<VerifyingKey<G1Affine>, FibError> {
+    let circuit = FiboCircuit { spec, ..FiboCircuit::default() };
+    Ok(with_circuit(circuit, ReadVerifyingKey(reader))??)
+}
+
+/// Writes `pk` for `read_proving_key`: its verifying key, then the
+/// polynomials keygen computed.
+pub fn write_proving_key(pk: &ProvingKey<G1Affine>, writer: &mut impl io::Write) -> io::Result<()> {
+    pk.write(writer, SerdeFormat::Processed)
+}
+
+/// `read_verifying_key` for a key `write_proving_key` wrote.
+///
+/// # Panics
+///
+/// If the file ends partway through a polynomial: halo2-axiom reads them
+/// without checking, so read only proving keys of your own.
+pub fn read_proving_key(reader: &mut impl io::Read, spec: FiboSpec) -> Result<ProvingKey<G1Affine>, FibError> {
+    let circuit = FiboCircuit { spec, ..FiboCircuit::default() };
+    Ok(with_circuit(circuit, ReadProvingKey(reader))??)
+}
+
+/// The fingerprint of a KZG verifying key, as `VkHash::of` takes it of an
+/// IPA one: the scalar halo2-axiom hashes the key into transcripts as.
+pub fn vk_hash(vk: &VerifyingKey<G1Affine>) -> VkHash {
+    VkHash(vk.transcript_repr().to_repr())
+}
+
+/// The values of the instance column for `spec`, from `a, b, c`.
+pub fn instance(spec: &FiboSpec, [a, b, c]: [Fr; 3]) -> Result<Vec<Fr>, Unsupported> {
+    let terms = spec.recurrence.terms(a, b, c, spec.num);
+    let last = |i: usize| terms[spec.num - i];
+    match spec.output {
+        OutputMode::Result => Ok(vec![last(1)]),
+        OutputMode::Continuation => Ok(vec![last(3), last(2), last(1), field_from_usize(spec.num)]),
+        OutputMode::InitialAndResult => Ok(vec![a, b, c, last(1)]),
+        OutputMode::Chunk => Ok(vec![a, b, c, last(3), last(2), last(1)]),
+        OutputMode::Hashed | OutputMode::Committed | OutputMode::MerkleRoot => {
+            Err(Unsupported { spec: *spec, reason: "the KZG backend has no Poseidon chip for hashed outputs" })
+        }
+    }
+}
+
+/// `proof::Proof` for the KZG backend: the proof bytes with `k`, the shape,
+/// the instance values and the key's fingerprint, encoded as `Proof` is with
+/// the scheme byte 2 and bn256 scalars for the instance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KzgProof {
+    pub k: u32,
+    pub spec: FiboSpec,
+    pub instance: Vec<Fr>,
+    pub bytes: Vec<u8>,
+    pub vk_hash: VkHash,
+}
+
+impl KzgProof {
+    /// `Proof::check_shape`, and `k` at most `MAX_K`.
+    pub fn check_shape(&self) -> Result<(), FibError> {
+        self.spec.check_num()?;
+        if self.k > MAX_K {
+            return Err(FibError::KTooLarge { k: self.k, max: MAX_K });
+        }
+        self.spec.check_k(self.k)?;
+        Ok(())
+    }
+
+    /// Regenerates the verifying key from the spec and checks the proof
+    /// against the bundled instance values. `params` must be for `self.k`.
+    pub fn verify(&self, params: &ParamsKZG<Bn256>) -> Result<(), FibError> {
+        self.check_shape()?;
+        if params.k() != self.k {
+            return Err(FibError::ParamsMismatch { params: params.k(), proof: self.k });
+        }
+        self.verify_with_key(params, &verifying_key(params, self.spec)?)
+    }
+
+    /// `verify` under `vk`, which must have the fingerprint the proof
+    /// records.
+    pub fn verify_with_key(&self, params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>) -> Result<(), FibError> {
+        let expected = vk_hash(vk);
+        if self.vk_hash != expected {
+            return Err(FibError::VkMismatch { recorded: self.vk_hash, expected });
+        }
+        verify(params, vk, &self.bytes, &self.instance)
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_shape(writer, self.k, &self.spec)?;
+        writer.write_all(&[KZG_SCHEME])?;
+        writer.write_all(&self.vk_hash.0)?;
+        write_len(writer, self.instance.len())?;
+        for value in &self.instance {
+            writer.write_all(&value.to_repr())?;
+        }
+        write_len(writer, self.bytes.len())?;
+        writer.write_all(&self.bytes)
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let (k, spec) = read_shape(reader)?;
+        if read_array::<_, 1>(reader)? != [KZG_SCHEME] {
+            return Err(invalid("not a KZG proof"));
+        }
+        let vk_hash = VkHash(read_array(reader)?);
+        let mut instance = vec![];
+        for _ in 0..read_len(reader)? {
+            let value = Fr::from_repr(read_array(reader)?);
+            instance.push(
+                Option::from(value)
+                    .ok_or_else(|| invalid("instance value is not a canonical field element"))?,
+            );
+        }
+        let bytes = read_bytes(reader)?;
+        Ok(Self { k, spec, instance, bytes, vk_hash })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write(&mut bytes).expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    /// Reads a proof that must span all of `bytes`.
+    pub fn from_bytes(mut bytes: &[u8]) -> io::Result<Self> {
+        let proof = Self::read(&mut bytes)?;
+        if !bytes.is_empty() {
+            return Err(invalid("trailing bytes after the proof"));
+        }
+        Ok(proof)
+    }
+
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.to_bytes())
+    }
+
+    /// Parses the hex encoding; surrounding whitespace is ignored.
+    pub fn from_hex(hex: &str) -> io::Result<Self> {
+        Self::from_bytes(&from_hex(hex)?)
+    }
+}
+
+/// The step gate of a `RecurrenceGate`, over halo2-axiom's expressions.
+pub trait KzgGate: RecurrenceGate<Fr> {
+    fn kzg_expression(a: Expression<Fr>, b: Expression<Fr>, c: Expression<Fr>) -> Expression<Fr>;
+}
+
+impl KzgGate for Variant {
+    fn kzg_expression(a: Expression<Fr>, b: Expression<Fr>, c: Expression<Fr>) -> Expression<Fr> {
+        (a + c) * b
+    }
+}
+
+impl KzgGate for Fibonacci {
+    fn kzg_expression(_a: Expression<Fr>, b: Expression<Fr>, c: Expression<Fr>) -> Expression<Fr> {
+        b + c
+    }
+}
+
+/// `FiboConfig` in halo2-axiom's column types.
+#[derive(Clone, Debug)]
+pub struct KzgConfig {
+    advice: [Column<Advice>; 4],
+    instance: Column<Instance>,
+    s: Selector,
+    s_chain: Selector,
+}
+
+impl KzgConfig {
+    /// `FiboChip::configure_recurrence::<G>`: the step gate and the chain
+    /// gate, and a fixed column for the term count.
+    fn configure<G: KzgGate>(meta: &mut ConstraintSystem<Fr>) -> Self {
+        let advice = [(); 4].map(|()| meta.advice_column());
+        let instance = meta.instance_column();
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        let [a, b, c, d] = advice;
+        let s = meta.selector();
+        let s_chain = meta.selector();
+
+        meta.create_gate(G::NAME, |meta| {
+            let s = meta.query_selector(s);
+            let [a, b, c, d] = [a, b, c, d].map(|column| meta.query_advice(column, Rotation::cur()));
+            vec![s * (G::kzg_expression(a, b, c) - d)]
+        });
+
+        meta.create_gate(CHAIN_GATE_NAME, |meta| {
+            let s = meta.query_selector(s_chain);
+            let [b_prev, c_prev, d_prev] = [b, c, d].map(|column| meta.query_advice(column, Rotation::prev()));
+            let [a, b, c] = [a, b, c].map(|column| meta.query_advice(column, Rotation::cur()));
+            vec![s.clone() * (a - b_prev), s.clone() * (b - c_prev), s * (c - d_prev)]
+        });
+
+        Self { advice, instance, s, s_chain }
+    }
+
+    /// `synthesize_sequence` for the outputs this backend supports.
+    fn synthesize(
+        &self,
+        spec: &FiboSpec,
+        witness: &[halo2_proofs::circuit::Value<Fr>],
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let _span = tracing::info_span!("synthesize", num = spec.num, output = %spec.output).entered();
+        spec.check_num().map_err(|_| Error::Synthesis)?;
+        if spec.layout != Layout::Rows || witness.len() < 4 {
+            return Err(Error::Synthesis);
+        }
+        // halo2-axiom's `SimpleFloorPlanner` starts every region at row 0,
+        // so the term count goes in the sequence's region, below the steps.
+        let (initial, last, count) = layouter.assign_region(
+            || "sequence",
+            |mut region| {
+                let steps = witness.len() - 3;
+                let mut initial = None;
+                let mut last = None;
+                for row in 0..steps {
+                    self.s.enable(&mut region, row)?;
+                    if row > 0 {
+                        self.s_chain.enable(&mut region, row)?;
+                    }
+                    let [a, b, c, d]: [Cell; 4] = array::from_fn(|j| {
+                        region.assign_advice(self.advice[j], row, to_axiom(witness[row + j])).cell()
+                    });
+                    initial.get_or_insert([a, b, c]);
+                    last = Some([b, c, d]);
+                }
+                let count = match spec.output {
+                    OutputMode::Continuation => {
+                        let count = field_from_usize::<Fr>(spec.num);
+                        Some(region.assign_advice_from_constant(|| "term count", self.advice[0], steps, count)?.cell())
+                    }
+                    _ => None,
+                };
+                Ok((initial.ok_or(Error::Synthesis)?, last.ok_or(Error::Synthesis)?, count))
+            },
+        )?;
+        let result = match spec.num {
+            1..=3 => initial[spec.num - 1],
+            _ => last[2],
+        };
+        match spec.output {
+            OutputMode::Result => self.expose(&mut layouter, &[result], 0),
+            OutputMode::Continuation => {
+                let [b, c, d] = last;
+                self.expose(&mut layouter, &[b, c, d, count.ok_or(Error::Synthesis)?], 0);
+            }
+            OutputMode::InitialAndResult => {
+                self.expose(&mut layouter, &initial, 0);
+                self.expose(&mut layouter, &[result], 3);
+            }
+            OutputMode::Chunk => {
+                self.expose(&mut layouter, &initial, 0);
+                self.expose(&mut layouter, &last, 3);
+            }
+            OutputMode::Hashed | OutputMode::Committed | OutputMode::MerkleRoot => return Err(Error::Synthesis),
+        }
+        Ok(())
+    }
+
+    /// Constrains `cells` to the instance column from row `offset` on.
+    fn expose(&self, layouter: &mut impl Layouter<Fr>, cells: &[Cell], offset: usize) {
+        for (row, &cell) in cells.iter().enumerate() {
+            layouter.constrain_instance(cell, self.instance, offset + row);
+        }
+    }
+}
+
+impl Circuit<Fr> for FiboCircuit<Fr> {
+    type Config = KzgConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self { spec: self.spec, ..Self::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        KzgConfig::configure::<Variant>(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        if self.spec.recurrence != Recurrence::Variant {
+            return Err(Error::Synthesis);
+        }
+        config.synthesize(&self.spec, &self.full_witness(), layouter)
+    }
+}
+
+impl<G: KzgGate> Circuit<Fr> for RecurrenceCircuit<Fr, G> {
+    type Config = KzgConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(FiboCircuit { spec: self.0.spec, ..FiboCircuit::default() })
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        KzgConfig::configure::<G>(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        let circuit = &self.0;
+        let witness = match &circuit.witness {
+            Some(witness) => Cow::Borrowed(witness),
+            None => {
+                let num = circuit.spec.layout_terms();
+                Cow::Owned(recurrence_witness::<Fr, G>(circuit.a, circuit.b, circuit.c, num))
+            }
+        };
+        config.synthesize(&circuit.spec, &witness, layouter)
+    }
+}
+
+/// halo2_proofs' `Value`, which `FiboCircuit` holds, as halo2-axiom's.
+/// Neither exposes what it holds other than through `map`.
+fn to_axiom(term: halo2_proofs::circuit::Value<Fr>) -> Value<Fr> {
+    let mut value = Value::unknown();
+    term.map(|term| value = Value::known(term));
+    value
+}
+
+/// `dispatch::WithCircuit` for halo2-axiom's `Circuit`.
+trait WithKzgCircuit {
+    type Output;
+
+    fn with<C: Circuit<Fr>>(self, circuit: C) -> Self::Output;
+}
+
+/// `dispatch::with_circuit` for the specs this backend supports.
+fn with_circuit<W: WithKzgCircuit>(circuit: FiboCircuit<Fr>, f: W) -> Result<W::Output, Unsupported> {
+    let spec = circuit.spec;
+    let unsupported = |reason| Err(Unsupported { spec, reason });
+    match (spec.layout, spec.recurrence, spec.output) {
+        (Layout::Column, _, _) => unsupported("the KZG backend only supports the rows layout"),
+        (_, _, OutputMode::Hashed | OutputMode::Committed | OutputMode::MerkleRoot) => {
+            unsupported("the KZG backend has no Poseidon chip for hashed outputs")
+        }
+        (Layout::Rows, Recurrence::Variant, _) => Ok(f.with(circuit)),
+        (Layout::Rows, Recurrence::Fibonacci, _) => Ok(f.with(RecurrenceCircuit::<_, Fibonacci>::new(circuit))),
+    }
+}
+
+struct Keygen<'a>(&'a ParamsKZG<Bn256>);
+
+impl WithKzgCircuit for Keygen<'_> {
+    type Output = Result<ProvingKey<G1Affine>, Error>;
+
+    fn with<C: Circuit<Fr>>(self, circuit: C) -> Self::Output {
+        let vk = tracing::info_span!("keygen_vk").in_scope(|| keygen_vk(self.0, &circuit))?;
+        tracing::info_span!("keygen_pk").in_scope(|| keygen_pk(self.0, vk, &circuit))
+    }
+}
+
+struct KeygenVk<'a>(&'a ParamsKZG<Bn256>);
+
+impl WithKzgCircuit for KeygenVk<'_> {
+    type Output = Result<VerifyingKey<G1Affine>, Error>;
+
+    fn with<C: Circuit<Fr>>(self, circuit: C) -> Self::Output {
+        tracing::info_span!("keygen_vk").in_scope(|| keygen_vk(self.0, &circuit))
+    }
+}
+
+struct Prove<'a, T, E> {
+    params: &'a ParamsKZG<Bn256>,
+    pk: &'a ProvingKey<G1Affine>,
+    instance: &'a [Fr],
+    transcript: PhantomData<(T, E)>,
+}
+
+impl<T, E> WithKzgCircuit for Prove<'_, T, E>
+where
+    T: TranscriptWriterBuffer<Vec<u8>, G1Affine, E>,
+    E: EncodedChallenge<G1Affine>,
+{
+    type Output = Result<Vec<u8>, Error>;
+
+    fn with<C: Circuit<Fr>>(self, circuit: C) -> Self::Output {
+        let mut transcript = T::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, _, _, _, _>(
+            self.params,
+            self.pk,
+            &[circuit],
+            &[&[self.instance]],
+            os_rng(),
+            &mut transcript,
+        )?;
+        Ok(transcript.finalize())
+    }
+}
+
+struct ReadVerifyingKey<'a, R>(&'a mut R);
+
+impl<R: io::Read> WithKzgCircuit for ReadVerifyingKey<'_, R> {
+    type Output = io::Result<VerifyingKey<G1Affine>>;
+
+    fn with<C: Circuit<Fr>>(self, _circuit: C) -> Self::Output {
+        VerifyingKey::read::<_, C>(self.0, SerdeFormat::Processed, C::Params::default())
+    }
+}
+
+struct ReadProvingKey<'a, R>(&'a mut R);
+
+impl<R: io::Read> WithKzgCircuit for ReadProvingKey<'_, R> {
+    type Output = io::Result<ProvingKey<G1Affine>>;
+
+    fn with<C: Circuit<Fr>>(self, _circuit: C) -> Self::Output {
+        ProvingKey::read::<_, C>(self.0, SerdeFormat::Processed, C::Params::default())
+    }
+}
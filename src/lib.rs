@@ -0,0 +1,91 @@
+//! Proving the Fibonacci-variant recurrence `s[i] = (s[i-1] + s[i-3]) * s[i-2]`
+//! in halo2.
+//!
+//! [`chip`] holds the step gate, built from a [`recurrence`], [`circuit`]
+//! lays a whole sequence out over it, [`column`] does the same in a single
+//! column, [`unrolled`] several steps per row, [`weighted`] with
+//! coefficients in the key, [`constant`] with the initial values in it, and
+//! [`sequence`] computes the same terms natively, for witnesses and for
+//! checking public outputs. [`prover`] turns a circuit into real proof
+//! bytes, or [`context`] many inputs of one shape under a key made once,
+//! and [`proof`] packs them with their public inputs and the
+//! [`fingerprint`] of their verifying key for another machine. [`params`]
+//! caches the IPA parameters those need on disk, and [`artifacts`] those
+//! and the keys per shape in memory; [`threads`] sizes the thread pool all
+//! of them prove on, and with the `session` feature `session` logs the
+//! runs of the command for reports. With the `kzg` feature,
+//! `kzg` proves the same circuit with KZG over bn256 instead, and
+//! [`backend`] picks between the two at run time; with `evm`, `evm`
+//! generates a Solidity verifier for those proofs. [`prelude`]
+//! re-exports the parts of all that most callers need.
+
+// Build against halo2_proofs 0.4 under the usual crate name; see `compat`.
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_proofs_0_4 as halo2_proofs;
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_gadgets_0_6 as halo2_gadgets;
+
+mod compat;
+pub mod artifacts;
+pub mod backend;
+pub mod batch;
+pub mod chip;
+pub mod chunked;
+pub mod circuit;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod column;
+pub mod constant;
+pub mod context;
+pub mod dispatch;
+pub mod error;
+#[cfg(feature = "evm")]
+pub mod evm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fingerprint;
+pub mod hash_chain;
+pub mod hashed;
+pub mod indexed;
+pub mod inspect;
+#[cfg(feature = "kzg")]
+pub mod kzg;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod malicious;
+pub mod membership;
+pub mod merkle;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+pub mod padded;
+pub mod params;
+pub mod parse;
+pub mod planner;
+pub mod prelude;
+pub mod proof;
+pub mod prover;
+#[cfg(feature = "python")]
+mod python;
+pub mod recurrence;
+pub mod row_usage;
+#[cfg(feature = "server")]
+pub mod rpc;
+pub mod sequence;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "session")]
+pub mod session;
+pub mod small_field;
+pub mod streaming;
+pub mod threads;
+pub mod transcript;
+pub mod unrolled;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod weighted;
+
+pub use chip::{FiboChip, FiboConfig};
+pub use circuit::{min_k_for, FiboCircuit, FiboCircuitFixed, FiboSpec, Layout, OutputMode};
+pub use column::FiboColumnCircuit;
+pub use error::FibError;
+pub use proof::Proof;
+pub use sequence::witness;
@@ -1,242 +1,817 @@
+// Build against halo2_proofs 0.4 under the usual crate name; see `compat`.
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_proofs_0_4 as halo2_proofs;
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use clap::{Args, Parser, Subcommand};
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::group::ff::PrimeField;
+use halo2_proofs::pasta::Fp;
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+use fibonacci_variant::artifacts::CircuitArtifacts;
+use fibonacci_variant::backend::{AnyProof, Backend};
+use fibonacci_variant::client::Client;
+use fibonacci_variant::dispatch::{with_circuit, Mock};
+use fibonacci_variant::fingerprint::VkHash;
+use fibonacci_variant::hashed::hash_result;
+use fibonacci_variant::inspect::{inspect_planned, inspect_queries};
+#[cfg(feature = "evm")]
+use fibonacci_variant::evm;
+#[cfg(feature = "kzg")]
+use fibonacci_variant::kzg::{self, Fr, KzgProof};
+use fibonacci_variant::params::{write_atomic, ParamsCache};
+use fibonacci_variant::parse::parse_field;
+use fibonacci_variant::planner::Planner;
+use fibonacci_variant::proof::{verify_batch, verifying_key};
+use fibonacci_variant::prover;
+use fibonacci_variant::recurrence::Recurrence;
+use fibonacci_variant::server::{serve, Element, ProveRequest, Service};
+use fibonacci_variant::session::{self, ProveStats, Run, Summary};
+use fibonacci_variant::threads;
+use fibonacci_variant::transcript::TranscriptHash;
+use fibonacci_variant::{FibError, FiboCircuit, FiboSpec, Layout, OutputMode, Proof};
+
+/// Fibonacci variant zkp in halo2 demo.
+#[derive(Parser)]
+struct Cli {
+    /// Directory caching the IPA and KZG parameters, one file per `k`; the
+    /// platform's cache directory by default, or `params` without one.
+    #[arg(long, global = true)]
+    params_dir: Option<PathBuf>,
+    /// Log configure, synthesize, keygen, prove and verify to stderr, each
+    /// with its time. `RUST_LOG` picks targets and levels instead.
+    #[arg(long, short, global = true)]
+    verbose: bool,
+    /// Threads for witness generation and proving; one per core by default.
+    #[arg(long, global = true, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    threads: Option<usize>,
+    #[command(subcommand)]
+    command: Command,
+}
 
-use std::{marker::PhantomData};
-use halo2_proofs::circuit::{Value, Layouter, AssignedCell, SimpleFloorPlanner};
-use halo2_proofs::poly::Rotation;
-use halo2_proofs::{plonk::*};
-use halo2_proofs::arithmetic::Field;
-
-
-struct Number<F: Field>(AssignedCell<F, F>);
-
-#[derive(Clone, Debug, Copy)]
-struct FiboConfig {
-    a: Column<Advice>,
-    b: Column<Advice>,
-    c: Column<Advice>,
-    d: Column<Advice>,
-    i: Column<Instance>,
-    s: Selector,
-}
-
-struct FiboChip<F: Field> {
-    config: FiboConfig,
-    _marker: PhantomData<F>,
-}
-
-impl<F: Field> FiboChip<F> {
-    fn construct(config: FiboConfig) -> Self {
-        Self {
-            config,
-            _marker: PhantomData,
-        }
-    }
-
-    fn configure(
-        meta: &mut ConstraintSystem<F>,
-    ) -> FiboConfig {
-        let a = meta.advice_column();
-        let b = meta.advice_column();
-        let c = meta.advice_column();
-        let d = meta.advice_column();
-        let i = meta.instance_column();
-        let s = meta.selector();
-
-        meta.enable_equality(a);
-        meta.enable_equality(b);
-        meta.enable_equality(c);
-        meta.enable_equality(d);
-        meta.enable_equality(i);
-
-        meta.create_gate("mul add gate", |meta| {
-            let s = meta.query_selector(s);
-            let a_tmp = meta.query_advice(a, Rotation::cur());
-            let b_tmp = meta.query_advice(b, Rotation::cur());
-            let c_tmp = meta.query_advice(c, Rotation::cur());
-            let d_tmp = meta.query_advice(d, Rotation::cur());
-            vec![s * (((a_tmp + c_tmp) * b_tmp) - d_tmp)]
-        });
-
-        FiboConfig {
-            a, b, c, d, i, s,
-        }
-    }
-    fn load_first_row(
-        &self,
-        mut layouter: impl Layouter<F>,
-        a: Value<F>,
-        b: Value<F>,
-        c: Value<F>,
-    ) -> Result<(Number<F>, Number<F>, Number<F>, Number<F>), Error> {
-        layouter.assign_region(
-            || "first row",
-            |mut region| {
-                self.config.s.enable(&mut region, 0)?;
-
-                let a_num = region.assign_advice(
-                    || "a",
-                    self.config.a,
-                    0,
-                    || a,
-                ).map(Number)?;
-
-                let b_num = region.assign_advice(
-                    || "b",
-                    self.config.b,
-                    0,
-                    || b,
-                ).map(Number)?;
-
-                let c_num = region.assign_advice(
-                    || "b",
-                    self.config.c,
-                    0,
-                    || c,
-                ).map(Number)?;
-                
-                let d_tmp = (a+ c) * b;
-                let d_num = region.assign_advice(
-                    || "c",
-                    self.config.d,
-                    0,
-                    || d_tmp,
-                ).map(Number)?;
-
-                Ok((a_num, b_num, c_num, d_num))
-            },
-        )
-    }
-
-    fn load_row(
-        &self,
-        mut layouter: impl Layouter<F>,
-        a: &Number<F>,
-        b: &Number<F>,
-        c: &Number<F>,
-    ) -> Result<Number<F>, Error> {
-        layouter.assign_region(
-            || "row-load",
-            |mut region| {
-                self.config.s.enable(&mut region, 0)?;
-
-                a.0.copy_advice(|| "a", &mut region, self.config.a, 0)?;
-                b.0.copy_advice(|| "b", &mut region, self.config.b, 0)?;
-                c.0.copy_advice(|| "c", &mut region, self.config.c, 0)?;
-                
-                let a_val = a.0.value().copied();
-                let b_val = b.0.value().copied();
-                let c_val = c.0.value().copied();
-                let d = b_val * (a_val + c_val);
-
-                region.assign_advice(
-                    || "d",
-                    self.config.d,
-                    0,
-                    || d,
-                ).map(Number)
+#[derive(Subcommand)]
+enum Command {
+    /// Prove the sequence and write the proof and its public inputs.
+    Prove {
+        #[command(flatten)]
+        seq: SeqArgs,
+        /// Where to write the proof, with its public inputs and shape.
+        #[arg(long)]
+        out: PathBuf,
+        /// Write the proof as hex instead of binary.
+        #[arg(long)]
+        hex: bool,
+        /// Where to write the public inputs, as JSON.
+        #[arg(long, default_value = "result.json")]
+        public: PathBuf,
+        /// Where to write timings, proof size and peak memory, as JSON.
+        #[arg(long)]
+        stats: Option<PathBuf>,
+        /// Append the same, with the shape and the time, as one line to this
+        /// session log, for `report`. Nothing is recorded without it.
+        #[arg(long)]
+        session_log: Option<PathBuf>,
+        /// `blake2b`, or `poseidon` for a proof a circuit can verify. The
+        /// proof records which, and `verify` reads it back.
+        #[arg(long, default_value = "blake2b")]
+        transcript: TranscriptHash,
+        /// Blind from a ChaCha20 stream seeded with this number instead of
+        /// the OS, so the same arguments give the same proof bytes. For test
+        /// vectors: anyone with the seed can undo the blinding.
+        #[arg(long)]
+        seed: Option<u64>,
+        /// `ipa`, or `kzg` for a proof over bn256 that a pairing check on
+        /// chain can verify. That one needs the `kzg` feature, and takes the
+        /// Blake2b transcript and fresh blinding only.
+        #[arg(long, default_value = "ipa")]
+        backend: Backend,
+        /// Have the proving service at this URL make the proof instead, e.g.
+        /// a machine with more cores, through its job queue. The result is
+        /// computed here and the proof refused unless it proves the same.
+        /// The service proves the default shape at the smallest `k` only.
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "k", "expose_initial", "hash_result", "layout", "recurrence",
+                "stats", "session_log", "transcript", "seed", "backend",
+            ]
+        )]
+        remote: Option<String>,
+    },
+    /// Verify a proof, binary or hex, of either backend. Checks it against
+    /// the inputs bundled in the proof unless `--public` supplies the claim
+    /// to check instead.
+    Verify {
+        #[arg(long)]
+        proof: PathBuf,
+        #[arg(long)]
+        public: Option<PathBuf>,
+    },
+    /// Prove every line of a file of inputs, writing the proofs to a
+    /// directory and one line of JSON per input to `--ndjson` as each is
+    /// proven or fails, for a monitor to tail. An input line is like the
+    /// body of `POST /prove`, `{"a": 1, "b": 2, "c": "0x3", "num": 100}`,
+    /// with `k`, `layout` and `recurrence` optional.
+    ProveBatch {
+        #[arg(long)]
+        inputs: PathBuf,
+        /// Where the proofs go, as `<line>.proof`.
+        #[arg(long)]
+        out_dir: PathBuf,
+        /// Where the lines go; standard output by default.
+        #[arg(long)]
+        ndjson: Option<PathBuf>,
+    },
+    /// Verify every proof file in a directory against its bundled inputs,
+    /// batching the proofs that share a verifying key.
+    VerifyBatch {
+        #[arg(long)]
+        dir: PathBuf,
+    },
+    /// Report the circuit's rows, columns, degree, quotient and extended
+    /// domain, smallest `k` and proof size, without proving.
+    Inspect {
+        #[command(flatten)]
+        seq: SeqArgs,
+        /// `simple`, the one the keys are made with, or `v1`, to compare
+        /// how tightly it packs the regions.
+        #[arg(long, default_value = "simple")]
+        floor_planner: Planner,
+    },
+    /// Print the fingerprint of the circuit's verifying key, the one its
+    /// proofs record; it changes whenever the constraints do.
+    InspectVk {
+        #[command(flatten)]
+        seq: SeqArgs,
+        /// Fail unless the fingerprint is this one, e.g. the one a deployed
+        /// verifier was made from.
+        #[arg(long)]
+        expect_fingerprint: Option<VkHash>,
+    },
+    /// Serve `POST /prove`, `POST /verify` and the `/jobs` queue over HTTP,
+    /// keeping parameters and keys in memory between requests.
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+        /// Refuse requests needing a larger `k`.
+        #[arg(long, default_value_t = 20)]
+        max_k: u32,
+        /// Jobs from `POST /jobs` proven at once.
+        #[arg(long, default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        workers: usize,
+        /// Requests answered at once.
+        #[arg(long, default_value_t = 4, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        handlers: usize,
+    },
+    /// Prove the same witness twice with fresh blinding, check the proofs
+    /// differ and both verify, and report their sizes, as a self-test of the
+    /// prover's randomness. With `--seed`, proving twice with the seed must
+    /// give the same proof instead, and the next seed a different one.
+    CheckZk {
+        #[command(flatten)]
+        seq: SeqArgs,
+        #[arg(long, default_value = "blake2b")]
+        transcript: TranscriptHash,
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Summarize the runs in one or more `prove --session-log` logs: per
+    /// shape, how many, when, how long keygen and proving took, proof size
+    /// and peak memory.
+    Report {
+        #[arg(required = true)]
+        logs: Vec<PathBuf>,
+        /// Print the summaries as JSON instead.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write a Solidity contract that verifies KZG proofs of the circuit on
+    /// Ethereum, from the parameters for `k`, and with `--calldata` a proof
+    /// of the sequence for it. Needs the `evm` feature; the contract
+    /// declares solc 0.8.19.
+    GenEvmVerifier {
+        #[command(flatten)]
+        seq: SeqArgs,
+        #[arg(long, default_value = "Verifier.sol")]
+        out: PathBuf,
+        /// Prove the sequence with the contract's Keccak transcript, check
+        /// the proof as the contract would, and write the calldata of the
+        /// call, its public inputs then the proof, here as 0x-prefixed hex.
+        #[arg(long)]
+        calldata: Option<PathBuf>,
+    },
+    /// Check the constraints with MockProver, without making a proof.
+    Mock {
+        #[command(flatten)]
+        seq: SeqArgs,
+    },
+}
+
+#[derive(Args)]
+struct SeqArgs {
+    /// Initial values, in decimal or 0x-prefixed hex.
+    #[arg(long, default_value = "1", value_parser = parse_field::<Fp>)]
+    a: Fp,
+    #[arg(long, default_value = "2", value_parser = parse_field::<Fp>)]
+    b: Fp,
+    #[arg(long, default_value = "3", value_parser = parse_field::<Fp>)]
+    c: Fp,
+    /// Number of terms, counting `a`, `b` and `c`. One to three ends the
+    /// sequence on `a`, `b` or `c`, which is then the result.
+    #[arg(long, default_value_t = 10, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    num: usize,
+    /// log2 of the number of rows; the smallest that fits `num` by default.
+    #[arg(long)]
+    k: Option<u32>,
+    /// Make `a`, `b` and `c` public inputs as well as the result.
+    #[arg(long, conflicts_with = "hash_result")]
+    expose_initial: bool,
+    /// Make the Poseidon hash of the result the public input, instead of
+    /// the result.
+    #[arg(long)]
+    hash_result: bool,
+    /// `rows` of four columns tied by copy constraints, or one `column`
+    /// read by rotation.
+    #[arg(long, default_value = "rows")]
+    layout: Layout,
+    /// The `variant` `(a + c) * b`, or `fibonacci`, `b + c`.
+    #[arg(long, default_value = "variant")]
+    recurrence: Recurrence,
+}
+
+impl SeqArgs {
+    fn circuit(&self) -> FiboCircuit<Fp> {
+        FiboCircuit {
+            spec: FiboSpec {
+                num: self.num,
+                output: self.output(),
+                layout: self.layout,
+                recurrence: self.recurrence,
             },
-        )
+            a: Value::known(self.a),
+            b: Value::known(self.b),
+            c: Value::known(self.c),
+            witness: None,
+        }
     }
 
-    fn expose_public(
-        &self,
-        mut layouter: impl Layouter<F>,
-        num: Number<F>,
-        row: usize,
-    ) -> Result<(), Error> {
-        layouter.constrain_instance(num.0.cell(), self.config.i, row)
+    /// `--k`, checked against the layout, or the smallest that fits.
+    fn k(&self) -> Result<u32, FibError> {
+        let spec = self.circuit().spec;
+        match self.k {
+            Some(k) => spec.check_k(k).map(|()| k),
+            None => Ok(spec.min_k()),
+        }
+    }
+
+    /// `circuit` with the witness computed up front.
+    fn witnessed(&self) -> FiboCircuit<Fp> {
+        let mut circuit = self.circuit();
+        let terms = self.recurrence.terms(self.a, self.b, self.c, circuit.spec.layout_terms());
+        circuit.witness = Some(terms.into_iter().map(Value::known).collect());
+        circuit
+    }
+
+    /// `circuit` over bn256, for the KZG backend, and its initial values.
+    #[cfg(feature = "kzg")]
+    fn kzg_circuit(&self) -> Result<(FiboCircuit<Fr>, [Fr; 3]), String> {
+        let [a, b, c] = [self.a, self.b, self.c].map(to_bn256);
+        let initial = [a?, b?, c?];
+        let circuit = FiboCircuit {
+            spec: self.circuit().spec,
+            a: Value::known(initial[0]),
+            b: Value::known(initial[1]),
+            c: Value::known(initial[2]),
+            witness: None,
+        };
+        Ok((circuit, initial))
+    }
+
+    fn output(&self) -> OutputMode {
+        if self.expose_initial {
+            OutputMode::InitialAndResult
+        } else if self.hash_result {
+            OutputMode::Hashed
+        } else {
+            OutputMode::Result
+        }
+    }
+
+    fn result(&self) -> Fp {
+        let terms = self.recurrence.terms(self.a, self.b, self.c, self.num);
+        terms[self.num - 1]
+    }
+
+    fn instance(&self) -> Vec<Fp> {
+        match self.output() {
+            OutputMode::InitialAndResult => vec![self.a, self.b, self.c, self.result()],
+            OutputMode::Hashed => vec![hash_result(self.result())],
+            _ => vec![self.result()],
+        }
     }
 }
 
-#[derive(Default)]
-struct FiboCircuit<F> {
-    a: Value<F>,
-    b: Value<F>,
-    c: Value<F>,
+/// One line of a `prove-batch` input file.
+#[derive(Deserialize)]
+struct BatchInput {
+    a: Element,
+    b: Element,
+    c: Element,
     num: usize,
+    #[serde(default)]
+    k: Option<u32>,
+    #[serde(default)]
+    layout: Option<String>,
+    #[serde(default)]
+    recurrence: Option<String>,
+}
+
+impl BatchInput {
+    /// The initial values, and the spec and `k` to prove them at.
+    fn parse(&self) -> Result<([Fp; 3], FiboSpec, u32), Box<dyn Error>> {
+        let initial = [self.a.parse()?, self.b.parse()?, self.c.parse()?];
+        let spec = FiboSpec {
+            num: self.num,
+            layout: self.layout.as_deref().map_or(Ok(Layout::default()), str::parse)?,
+            recurrence: self.recurrence.as_deref().map_or(Ok(Recurrence::default()), str::parse)?,
+            ..FiboSpec::default()
+        };
+        spec.check_num()?;
+        let k = self.k.unwrap_or_else(|| spec.min_k());
+        spec.check_k(k)?;
+        Ok((initial, spec, k))
+    }
+}
+
+/// One line of `prove-batch` output: the input line it is for, and how
+/// proving it went.
+#[derive(Serialize)]
+struct BatchRecord {
+    line: usize,
+    #[serde(flatten)]
+    outcome: BatchOutcome,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum BatchOutcome {
+    /// `secs` is the whole time spent on the line, with keygen if it was the
+    /// first of its shape.
+    Done { k: u32, num: usize, proof: PathBuf, proof_bytes: usize, secs: f64 },
+    Failed { error: String },
 }
 
-impl<F: Field> Circuit<F> for FiboCircuit<F> {
-    type Config = FiboConfig;
-    type FloorPlanner = SimpleFloorPlanner;
+/// What a verifier needs besides the proof: the circuit shape, which
+/// determines the verifying key, and the claimed values.
+#[derive(Serialize, Deserialize)]
+struct Public {
+    k: u32,
+    num: usize,
+    /// `rows` or `column`.
+    layout: String,
+    /// `variant` or `fibonacci`.
+    recurrence: String,
+    /// `a`, `b` and `c`, present when they are public inputs too.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    initial: Option<[String; 3]>,
+    /// Big-endian hex with a `0x` prefix, as `Fp` prints with `{:?}`.
+    /// Absent when only its hash is public.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<String>,
+    /// The Poseidon hash of the result, when that is the public input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result_hash: Option<String>,
+}
 
-    fn without_witnesses(&self) -> Self {
-        Self::default()
+impl Public {
+    /// The claim `prove` writes: the shape, then the initial values if they
+    /// are public, and the result or its hash.
+    fn new<F: fmt::Debug>(seq: &SeqArgs, k: u32, initial: [F; 3], result: F, result_hash: Option<F>) -> Self {
+        Public {
+            k,
+            num: seq.num,
+            layout: seq.layout.name().to_string(),
+            recurrence: seq.recurrence.name().to_string(),
+            initial: seq.expose_initial.then(|| initial.map(|x| format!("{:?}", x))),
+            result: result_hash.is_none().then(|| format!("{:?}", result)),
+            result_hash: result_hash.map(|hash| format!("{:?}", hash)),
+        }
     }
 
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        FiboChip::configure(meta)
+    /// The claimed instance values, parsed as `F`, and the output mode
+    /// they imply.
+    fn instance<F: PrimeField>(&self) -> Result<(Vec<F>, OutputMode), Box<dyn Error>> {
+        let mut instance = vec![];
+        let values = self.initial.iter().flatten().chain(&self.result).chain(&self.result_hash);
+        for value in values {
+            instance.push(parse_field(value)?);
+        }
+        let output = match (&self.initial, &self.result_hash) {
+            (Some(_), _) => OutputMode::InitialAndResult,
+            (None, Some(_)) => OutputMode::Hashed,
+            (None, None) => OutputMode::Result,
+        };
+        Ok((instance, output))
     }
 
-    fn synthesize(
-        &self,
-        config: Self::Config,
-        mut layouter: impl Layouter<F>
-    ) -> Result<(), Error> {
-        let chip = FiboChip::construct(config);
-        let (_, mut b, mut c, mut d) = chip.load_first_row(
-            layouter.namespace(|| "first row"),
-            self.a,
-            self.b,
-            self.c,
-        )?;
-        for _ in 4..self.num {
-            let new_d = chip.load_row(
-                layouter.namespace(|| "row-synthesize "),
-                &b,
-                &c,
-                &d,
-            )?;
-            b = c;
-            c = d;
-            d = new_d;
+    fn spec(&self, output: OutputMode) -> Result<FiboSpec, Box<dyn Error>> {
+        Ok(FiboSpec { num: self.num, output, layout: self.layout.parse()?, recurrence: self.recurrence.parse()? })
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    init_tracing(cli.verbose);
+    if let Err(err) = run(cli) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+/// Installs a subscriber printing each span as it closes, with its time, if
+/// `--verbose` or `RUST_LOG` asks for one.
+fn init_tracing(verbose: bool) {
+    let filter = match EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) if verbose => EnvFilter::new("fibonacci_variant=info"),
+        Err(_) => return,
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(io::stderr)
+        .init();
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    // The global pool, so the server's threads are held to it as well.
+    if let Some(threads) = cli.threads {
+        threads::set_threads(threads)?;
+    }
+    let dir = cli.params_dir.or_else(ParamsCache::default_dir).unwrap_or_else(|| PathBuf::from("params"));
+    let cache = ParamsCache::new(dir);
+    match cli.command {
+        Command::Prove { seq, out, hex, public, stats, session_log, transcript, seed, backend, remote } => {
+            if let Some(url) = remote {
+                let proof = prove_remote(&url, &seq)?;
+                let inputs = Public::new(&seq, proof.k, [seq.a, seq.b, seq.c], seq.result(), None);
+                write_proof(&AnyProof::Ipa(proof), &out, hex, &public, &inputs)?;
+                return Ok(());
+            }
+            let k = seq.k()?;
+            let start = Instant::now();
+            let (proof, inputs, times) = match backend {
+                Backend::Ipa => {
+                    let params = cache.get(k)?;
+                    let params_time = start.elapsed();
+                    let start = Instant::now();
+                    let circuit = seq.witnessed();
+                    let instance = seq.instance();
+                    let witness_time = start.elapsed();
+                    let output = prover::prove_spec(&params, &circuit, &instance, transcript, seed.map(seed_bytes))?;
+                    let inputs = Public::new(
+                        &seq,
+                        k,
+                        [seq.a, seq.b, seq.c],
+                        seq.result(),
+                        seq.hash_result.then(|| hash_result(seq.result())),
+                    );
+                    let times = (params_time, witness_time, output.timings);
+                    (AnyProof::Ipa(output.into_proof()), inputs, times)
+                }
+                #[cfg(feature = "kzg")]
+                Backend::Kzg => {
+                    if transcript != TranscriptHash::Blake2b || seed.is_some() {
+                        return Err("the KZG backend takes the Blake2b transcript and no --seed".into());
+                    }
+                    let params = cache.get_kzg(k)?;
+                    let params_time = start.elapsed();
+                    let start = Instant::now();
+                    let (circuit, initial) = seq.kzg_circuit()?;
+                    let instance = kzg::instance(&circuit.spec, initial)?;
+                    let witness_time = start.elapsed();
+                    let start = Instant::now();
+                    let pk = kzg::keygen(&params, &circuit)?;
+                    let keygen = start.elapsed();
+                    let start = Instant::now();
+                    let bytes = kzg::prove(&params, &pk, &circuit, &instance)?;
+                    let timings = prover::Timings { keygen, prove: start.elapsed() };
+                    let vk_hash = kzg::vk_hash(pk.get_vk());
+                    let result = instance[instance.len() - 1];
+                    let inputs = Public::new(&seq, k, initial, result, None);
+                    let proof = KzgProof { k, spec: circuit.spec, instance, bytes, vk_hash };
+                    (AnyProof::Kzg(proof), inputs, (params_time, witness_time, timings))
+                }
+                #[cfg(not(feature = "kzg"))]
+                Backend::Kzg => return Err("--backend kzg needs a build with the kzg feature".into()),
+            };
+            let (params_time, witness_time, timings) = times;
+            let report = ProveStats {
+                k,
+                num: seq.num,
+                proof_bytes: proof.bytes().len(),
+                params_secs: params_time.as_secs_f64(),
+                witness_secs: witness_time.as_secs_f64(),
+                keygen_secs: timings.keygen.as_secs_f64(),
+                prove_secs: timings.prove.as_secs_f64(),
+                peak_rss: peak_rss(),
+            };
+            if let Some(stats) = stats {
+                write_file(&stats, serde_json::to_string_pretty(&report)?)?;
+            }
+            if let Some(log) = session_log {
+                session::append(&log, &Run::now(backend, &proof.spec(), report))?;
+            }
+            write_proof(&proof, &out, hex, &public, &inputs)?;
+        }
+        Command::Verify { proof, public } => {
+            let mut proof = read_proof(&proof)?;
+            if let Some(public) = public {
+                let inputs: Public = serde_json::from_str(&fs::read_to_string(&public)?)?;
+                match &mut proof {
+                    AnyProof::Ipa(proof) => {
+                        let (instance, output) = inputs.instance()?;
+                        (proof.k, proof.spec, proof.instance) = (inputs.k, inputs.spec(output)?, instance);
+                    }
+                    #[cfg(feature = "kzg")]
+                    AnyProof::Kzg(proof) => {
+                        let (instance, output) = inputs.instance()?;
+                        (proof.k, proof.spec, proof.instance) = (inputs.k, inputs.spec(output)?, instance);
+                    }
+                }
+            }
+            proof.verify(&cache)?;
+            println!("proof verified");
+        }
+        Command::ProveBatch { inputs, out_dir, ndjson } => {
+            let mut sink: Box<dyn Write> = match ndjson {
+                Some(path) => Box::new(fs::File::create(path)?),
+                None => Box::new(io::stdout()),
+            };
+            fs::create_dir_all(&out_dir)?;
+            let artifacts = CircuitArtifacts::new(cache.clone());
+            let (mut done, mut failed) = (0, 0);
+            for (i, line) in fs::read_to_string(&inputs)?.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let start = Instant::now();
+                let proof = out_dir.join(format!("{}.proof", i + 1));
+                let proved = || -> Result<BatchOutcome, Box<dyn Error>> {
+                    let (initial, spec, k) = serde_json::from_str::<BatchInput>(line)?.parse()?;
+                    let output = artifacts.prover(k, spec)?.prove(initial)?;
+                    let proof_bytes = output.bytes.len();
+                    write_file(&proof, output.into_proof().to_bytes())?;
+                    Ok(BatchOutcome::Done { k, num: spec.num, proof, proof_bytes, secs: start.elapsed().as_secs_f64() })
+                };
+                let outcome = proved().unwrap_or_else(|err| BatchOutcome::Failed { error: err.to_string() });
+                match outcome {
+                    BatchOutcome::Done { .. } => done += 1,
+                    BatchOutcome::Failed { .. } => failed += 1,
+                }
+                // A line at a time, flushed, so a reader of the file never
+                // waits on a buffer or sees half a record.
+                let mut record = serde_json::to_string(&BatchRecord { line: i + 1, outcome })?;
+                record.push('\n');
+                sink.write_all(record.as_bytes())?;
+                sink.flush()?;
+            }
+            eprintln!("{} proven, {} failed", done, failed);
+            if failed > 0 {
+                return Err(format!("{} of {} inputs failed", failed, done + failed).into());
+            }
+        }
+        Command::VerifyBatch { dir } => {
+            let mut paths = vec![];
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_file() {
+                    paths.push(path);
+                }
+            }
+            paths.sort();
+            // IPA proofs of the same shape at the same `k` share a verifying
+            // key. halo2-axiom has no batch verifier, so KZG proofs are
+            // checked one at a time, each its own batch.
+            let mut batches: Vec<Vec<Proof>> = vec![];
+            let mut singles = 0;
+            for path in &paths {
+                let proof = read_proof(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+                proof.check_shape().map_err(|err| format!("{}: {}", path.display(), err))?;
+                let proof = match proof {
+                    AnyProof::Ipa(proof) => proof,
+                    #[allow(unreachable_patterns)]
+                    proof => {
+                        proof.verify(&cache).map_err(|err| format!("{}: {}", path.display(), err))?;
+                        singles += 1;
+                        continue;
+                    }
+                };
+                match batches.iter_mut().find(|batch| batch[0].k == proof.k && batch[0].spec == proof.spec) {
+                    Some(batch) => batch.push(proof),
+                    None => batches.push(vec![proof]),
+                }
+            }
+            let artifacts = CircuitArtifacts::new(cache.clone());
+            for batch in &batches {
+                let (k, spec) = (batch[0].k, batch[0].spec);
+                let params = artifacts.params(k)?;
+                verify_batch(batch, &artifacts.artifacts(k, spec)?.vk, &params)
+                    .map_err(|err| format!("{} proofs of {}: {}", batch.len(), batch[0].spec, err))?;
+            }
+            println!("{} proofs verified in {} batches", paths.len(), batches.len() + singles);
+        }
+        Command::Inspect { seq, floor_planner } => {
+            println!("{}, laid out by {}", seq.circuit().spec, floor_planner);
+            println!("{}", inspect_planned(seq.circuit(), seq.k()?, floor_planner)?);
+            println!("{}", inspect_queries(seq.circuit())?);
+        }
+        Command::InspectVk { seq, expect_fingerprint } => {
+            let k = seq.k()?;
+            let vk_hash = VkHash::of(&verifying_key(&cache.get(k)?, seq.circuit().spec)?);
+            println!("{}", vk_hash);
+            if let Some(expected) = expect_fingerprint {
+                if vk_hash != expected {
+                    return Err(format!("the fingerprint is not the expected {}", expected).into());
+                }
+            }
+        }
+        Command::Serve { addr, max_k, workers, handlers } => {
+            println!("serving on http://{}", addr);
+            serve(&addr, Service::new(cache, max_k), handlers, workers)?;
+        }
+        #[cfg(feature = "evm")]
+        Command::GenEvmVerifier { seq, out, calldata } => {
+            let k = seq.k()?;
+            let params = cache.get_kzg(k)?;
+            let spec = seq.circuit().spec;
+            let vk = kzg::verifying_key(&params, spec)?;
+            write_file(&out, evm::solidity_verifier(&params, &vk, spec.output.instance_rows()))?;
+            println!("verifier for {} at k = {} written to {}", spec, k, out.display());
+            if let Some(path) = calldata {
+                let (circuit, initial) = seq.kzg_circuit()?;
+                let instance = kzg::instance(&spec, initial)?;
+                let pk = kzg::keygen(&params, &circuit)?;
+                let proof = evm::prove(&params, &pk, &circuit, &instance)?;
+                evm::verify(&params, pk.get_vk(), &proof, &instance)?;
+                let hex: String = evm::calldata(&instance, &proof).iter().map(|byte| format!("{:02x}", byte)).collect();
+                write_file(&path, format!("0x{}\n", hex))?;
+                println!("calldata written to {}", path.display());
+            }
+        }
+        #[cfg(not(feature = "evm"))]
+        Command::GenEvmVerifier { .. } => return Err("gen-evm-verifier needs a build with the evm feature".into()),
+        Command::CheckZk { seq, transcript, seed } => {
+            let k = seq.k()?;
+            let params = cache.get(k)?;
+            let (circuit, instance) = (seq.witnessed(), seq.instance());
+            let prove = |seed: Option<u64>| -> Result<Proof, Box<dyn Error>> {
+                let output = prover::prove_spec(&params, &circuit, &instance, transcript, seed.map(seed_bytes))?;
+                let proof = output.into_proof();
+                proof.verify(&params)?;
+                Ok(proof)
+            };
+            let first = prove(seed)?;
+            if let Some(seed) = seed {
+                if prove(Some(seed))? != first {
+                    return Err(format!("two proofs with seed {} differ: the seeded blinding is not deterministic", seed)
+                        .into());
+                }
+            }
+            let second = prove(seed.map(|seed| seed.wrapping_add(1)))?;
+            if second.bytes == first.bytes {
+                return Err("two proofs of the same witness are identical: the blinding is not fresh".into());
+            }
+            println!(
+                "two proofs of the same witness differ and both verify: {} and {} bytes",
+                first.bytes.len(),
+                second.bytes.len()
+            );
+        }
+        Command::Report { logs, json } => {
+            let mut runs = vec![];
+            for log in &logs {
+                runs.extend(session::read(log)?);
+            }
+            let summaries = session::summarize(&runs);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&summaries)?);
+            } else {
+                summaries.iter().for_each(print_summary);
+            }
+        }
+        Command::Mock { seq } => {
+            let (k, circuit) = (seq.k()?, seq.circuit());
+            let prover = with_circuit(circuit, Mock(k, seq.instance()))??;
+            if let Err(failures) = prover.verify() {
+                for failure in &failures {
+                    println!("{}", failure);
+                }
+                return Err(format!("{} constraint failures", failures.len()).into());
+            }
+            println!("constraints satisfied");
         }
-        chip.expose_public(layouter.namespace(|| "expose public"), d, 0)?;
-        Ok(())
     }
+    Ok(())
 }
 
-fn get_fibovar_seq(a: u64, b: u64, c: u64, num: usize) -> Vec<u64> {
-    let mut seq = vec![0; num];
-    seq[0] = a;
-    seq[1] = b;
-    seq[2] = c;
-    for i in 3..num {
-        seq[i] = (seq[i - 1] + seq[i - 3]) * seq[i - 2];   
+/// Reads a proof file of either backend, binary or hex.
+fn read_proof(path: &Path) -> io::Result<AnyProof> {
+    let data = fs::read(path)?;
+    match std::str::from_utf8(&data) {
+        Ok(hex) if hex.trim().bytes().all(|byte| byte.is_ascii_hexdigit()) => AnyProof::from_hex(hex),
+        _ => AnyProof::from_bytes(&data),
     }
-    seq
 }
 
+/// Writes what `prove` makes: the proof to `out`, as hex with `hex`, and
+/// the claim to `public`.
+fn write_proof(proof: &AnyProof, out: &Path, hex: bool, public: &Path, inputs: &Public) -> Result<(), Box<dyn Error>> {
+    if hex {
+        write_file(out, proof.to_hex())?;
+    } else {
+        write_file(out, proof.to_bytes())?;
+    }
+    write_file(public, serde_json::to_string_pretty(inputs)?)?;
+    println!("wrote a {} byte proof to {}", proof.bytes().len(), out.display());
+    Ok(())
+}
 
-fn main() {
-    use halo2_proofs::{pasta::Fp, dev::MockProver};
-
-    let num = 10;
-    let seq = get_fibovar_seq(1, 2, 3, num);
-    let res = Fp::from(seq[num - 1]);
-    println!("{:?}", seq);
-
-    let circuit = FiboCircuit {
-        a: Value::known(Fp::from(1)),
-        b: Value::known(Fp::from(2)),
-        c: Value::known(Fp::from(3)),
-        num,
-    };
+/// `prove --remote`: the sequence proven by the service at `url`, which
+/// gets only the initial values and `num`. The proof must be of the shape
+/// and result computed here; whether it verifies is for `verify` to say.
+fn prove_remote(url: &str, seq: &SeqArgs) -> Result<Proof, Box<dyn Error>> {
+    let element = |x: &Fp| Element::String(format!("{:?}", x));
+    let request = ProveRequest { a: element(&seq.a), b: element(&seq.b), c: element(&seq.c), num: seq.num };
+    let client = Client::new(url);
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    let answer = runtime.block_on(client.prove_queued(&request, Duration::from_secs(1)))?;
+    let proof = answer.to_proof()?;
+    if proof.spec != seq.circuit().spec || proof.instance != seq.instance() {
+        return Err(format!("{} answered with a proof of another statement", client.base()).into());
+    }
+    Ok(proof)
+}
+
+/// One `report` line per shape, then the times indented below it.
+fn print_summary(summary: &Summary) {
+    let Summary { shape, runs, prove_secs, keygen_secs, .. } = summary;
+    println!(
+        "{} k={} num={} {} {} {}: {} run{}, {} proof bytes",
+        shape.backend,
+        shape.k,
+        shape.num,
+        shape.layout,
+        shape.recurrence,
+        shape.output,
+        runs,
+        if *runs == 1 { "" } else { "s" },
+        summary.proof_bytes,
+    );
+    println!(
+        "  prove  {:.3}s mean, {:.3}s to {:.3}s, {:.3}s last",
+        prove_secs.mean, prove_secs.min, prove_secs.max, summary.last_prove_secs
+    );
+    println!("  keygen {:.3}s mean, {:.3}s to {:.3}s", keygen_secs.mean, keygen_secs.min, keygen_secs.max);
+    if let Some(peak) = summary.peak_rss {
+        println!("  peak memory {} MiB", peak >> 20);
+    }
+}
 
-    let mut public_inputs = vec![res];
+/// `fs::write` through `write_atomic`, so another run reading `path`
+/// meanwhile never sees it half written.
+fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_atomic(path, |writer| writer.write_all(contents.as_ref()))
+}
 
-    let k = 8;
+/// `--a`, `--b` or `--c` as a bn256 scalar, for `--backend kzg`: the same
+/// integer, which must be below that field's slightly smaller modulus.
+#[cfg(feature = "kzg")]
+fn to_bn256(x: Fp) -> Result<Fr, String> {
+    Option::from(Fr::from_repr(x.to_repr())).ok_or_else(|| format!("{:?} is not below the bn256 scalar modulus", x))
+}
 
-    // verify.
-    println!("test the correct data");
-    let prover = MockProver::run(k, &circuit, vec![public_inputs.clone()]).unwrap();
-    assert_eq!(prover.verify(), Ok(()));
-    println!("done!");
+/// `--seed` as the ChaCha20 seed: its little-endian bytes, then zeros.
+fn seed_bytes(seed: u64) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    bytes
+}
 
-    // fail!
-    println!("test the wrong data 9999");
-    public_inputs[0] = Fp::from(9999);
-    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
-    assert!(prover.verify().is_err());
-    println!("done!");
+/// The process's peak resident set size in bytes, from `/proc` on Linux.
+fn peak_rss() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line.trim_start_matches("VmHWM:").trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kib * 1024)
 }
 
@@ -0,0 +1,55 @@
+//! Malicious witnesses, for checking soundness: a `WitnessOverride` swaps
+//! one term of an honest sequence for another value and leaves every other
+//! term honest, so a sound circuit must refuse it at the step gate reading
+//! the term first, not only at the public result. Only the variant in the
+//! rows and column layouts lays out `FiboCircuit::witness` as given.
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::Value;
+
+use crate::chip::GATE_NAME;
+use crate::circuit::{FiboCircuit, Layout};
+use crate::column::COLUMN_GATE_NAME;
+
+/// Term `term` of the sequence replaced by `value`. In the rows layout the
+/// term is replaced in every cell holding it, so the chain gate between
+/// rows sees nothing wrong and only the step gate can catch it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WitnessOverride<F> {
+    pub term: usize,
+    pub value: F,
+}
+
+impl<F: Field> WitnessOverride<F> {
+    /// `circuit` with its precomputed witness corrupted.
+    ///
+    /// # Panics
+    ///
+    /// If `term` is past the sequence.
+    pub fn apply(&self, circuit: &FiboCircuit<F>) -> FiboCircuit<F> {
+        let mut witness = circuit.full_witness().into_owned();
+        witness[self.term] = Value::known(self.value);
+        FiboCircuit {
+            witness: Some(witness),
+            ..circuit.clone()
+        }
+    }
+
+    /// The row, within the sequence region, of the first step gate reading
+    /// `term`: row `i` of the rows layout holds terms `i` to `i + 3`, and
+    /// row `i` of the column layout checks term `i` from the three before.
+    pub fn first_row(&self, layout: Layout) -> usize {
+        match layout {
+            Layout::Rows => self.term.saturating_sub(3),
+            Layout::Column => self.term.max(3),
+        }
+    }
+}
+
+/// The name of the step gate in `layout`, as MockProver reports failures.
+pub fn step_gate(layout: Layout) -> &'static str {
+    match layout {
+        Layout::Rows => GATE_NAME,
+        Layout::Column => COLUMN_GATE_NAME,
+    }
+}
@@ -0,0 +1,181 @@
+//! Proving that a public value `v` is one of the first `num` terms without
+//! saying which. The terms sit one per row; a running product `p` of
+//! `x - v` over the rows must end at zero, which it does exactly when some
+//! term equals `v`. `v` is copied down every row and tied to the instance
+//! at row 0, and the first three terms to rows 1 to 3: with the start left
+//! private, a prover could begin the sequence at `a = v` and prove any `v`.
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector};
+use halo2_proofs::poly::Rotation;
+
+use crate::sequence::witness;
+
+#[derive(Clone, Debug, Copy)]
+pub struct MembershipConfig {
+    /// The terms, one per row.
+    pub x: Column<Advice>,
+    /// The value looked for, the same on every row.
+    pub v: Column<Advice>,
+    /// Product of `x - v` up to and including this row.
+    pub p: Column<Advice>,
+    pub i: Column<Instance>,
+    /// The first row only.
+    pub s_first: Selector,
+    /// Every row but the first.
+    pub s_chain: Selector,
+    /// Rows from the fourth term on.
+    pub s_step: Selector,
+    /// The last row only.
+    pub s_last: Selector,
+}
+
+impl MembershipConfig {
+    pub fn configure<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        let [x, v, p] = [(); 3].map(|_| meta.advice_column());
+        let i = meta.instance_column();
+        let [s_first, s_chain, s_step, s_last] = [(); 4].map(|_| meta.selector());
+
+        meta.enable_equality(x);
+        meta.enable_equality(v);
+        meta.enable_equality(i);
+
+        meta.create_gate("membership first row", |meta| {
+            let s = meta.query_selector(s_first);
+            let x = meta.query_advice(x, Rotation::cur());
+            let v = meta.query_advice(v, Rotation::cur());
+            let p = meta.query_advice(p, Rotation::cur());
+            vec![s * (p - (x - v))]
+        });
+
+        meta.create_gate("membership chain", |meta| {
+            let s = meta.query_selector(s_chain);
+            let v_prev = meta.query_advice(v, Rotation::prev());
+            let p_prev = meta.query_advice(p, Rotation::prev());
+            let x = meta.query_advice(x, Rotation::cur());
+            let v = meta.query_advice(v, Rotation::cur());
+            let p = meta.query_advice(p, Rotation::cur());
+            vec![
+                s.clone() * (v.clone() - v_prev),
+                s * (p - p_prev * (x - v)),
+            ]
+        });
+
+        meta.create_gate("membership step", |meta| {
+            let s = meta.query_selector(s_step);
+            let x3 = meta.query_advice(x, Rotation(-3));
+            let x2 = meta.query_advice(x, Rotation(-2));
+            let x1 = meta.query_advice(x, Rotation::prev());
+            let x0 = meta.query_advice(x, Rotation::cur());
+            vec![s * ((x1 + x3) * x2 - x0)]
+        });
+
+        meta.create_gate("membership last row", |meta| {
+            let s = meta.query_selector(s_last);
+            let p = meta.query_advice(p, Rotation::cur());
+            vec![s * p]
+        });
+
+        Self { x, v, p, i, s_first, s_chain, s_step, s_last }
+    }
+}
+
+/// The first `num` terms, one of which is `value`. Only `num` shapes the
+/// circuit; `value` is checked against instance row 0 and `a, b, c`
+/// against rows 1 to 3.
+#[derive(Clone, Debug, Default)]
+pub struct FiboCircuitMembership<F> {
+    pub num: usize,
+    pub value: Value<F>,
+    pub a: Value<F>,
+    pub b: Value<F>,
+    pub c: Value<F>,
+}
+
+impl<F: Field> FiboCircuitMembership<F> {
+    /// Cell values per row: `x, v, p`.
+    fn assignments(&self) -> Value<Vec<[F; 3]>> {
+        let num = self.num;
+        self.a.zip(self.b).zip(self.c).zip(self.value).map(|(((a, b), c), v)| {
+            let mut p = F::ONE;
+            witness(a, b, c, num)
+                .into_iter()
+                .map(|x| {
+                    p *= x - v;
+                    [x, v, p]
+                })
+                .collect()
+        })
+    }
+}
+
+impl<F: Field> Circuit<F> for FiboCircuitMembership<F> {
+    type Config = MembershipConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            num: self.num,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MembershipConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        if self.num < 4 {
+            return Err(Error::Synthesis);
+        }
+        let cells = self.assignments();
+        let columns = [config.x, config.v, config.p];
+
+        let (value, initial) = layouter.assign_region(
+            || "membership rows",
+            |mut region| {
+                let mut value = None;
+                let mut initial = vec![];
+                for row in 0..self.num {
+                    if row == 0 {
+                        config.s_first.enable(&mut region, row)?;
+                    } else {
+                        config.s_chain.enable(&mut region, row)?;
+                    }
+                    if row >= 3 {
+                        config.s_step.enable(&mut region, row)?;
+                    }
+                    if row == self.num - 1 {
+                        config.s_last.enable(&mut region, row)?;
+                    }
+                    for (j, column) in columns.into_iter().enumerate() {
+                        let cell = region.assign_advice(
+                            || format!("row {} column {}", row, j),
+                            column,
+                            row,
+                            || cells.as_ref().map(|cells| cells[row][j]),
+                        )?;
+                        // `v` of the first row and `a, b, c` for the instance.
+                        if row == 0 && column == config.v {
+                            value = Some(cell);
+                        } else if row < 3 && column == config.x {
+                            initial.push(cell);
+                        }
+                    }
+                }
+                Ok((value.expect("the layout has a first row"), initial))
+            },
+        )?;
+
+        layouter.constrain_instance(value.cell(), config.i, 0)?;
+        for (row, cell) in initial.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.i, row + 1)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+//! `OutputMode::MerkleRoot`: the sequence as in `FiboCircuit`, exposing the
+//! root of a Poseidon Merkle tree over every term, so a later proof or a
+//! plain `MerklePath` can open any one of them. The leaves are the terms in
+//! order, padded with zeros to a power of two; each node hashes its two
+//! children with `ConstantLength<2>`.
+
+use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength, P128Pow5T3};
+use halo2_gadgets::poseidon::{Hash, Pow5Chip};
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+use crate::chip::{FiboChip, Number, SequenceChip};
+use crate::circuit::{FiboCircuit, OutputMode};
+use crate::hashed::{check_spec, HashedConfig, HASH_ROWS};
+
+type NodeHash = poseidon::Hash<Fp, P128Pow5T3, ConstantLength<2>, 3, 2>;
+
+/// Leaves of the tree over `num` terms.
+pub fn leaves(num: usize) -> usize {
+    num.next_power_of_two()
+}
+
+/// Rows the zero padding and the nodes take below a sequence of `num`
+/// terms. A node is one permutation, as is hashing the result. Saturates
+/// for a `num` too large to have a tree.
+pub fn tree_rows(num: usize) -> usize {
+    num.checked_next_power_of_two()
+        .map_or(usize::MAX, |leaves| (leaves - 1).saturating_mul(HASH_ROWS).saturating_add(1))
+}
+
+/// The parent of `left` and `right`.
+pub fn hash_node(left: Fp, right: Fp) -> Fp {
+    NodeHash::init().hash([left, right])
+}
+
+/// The levels of the tree over `terms`, leaves first and the root last.
+fn levels(terms: &[Fp]) -> Vec<Vec<Fp>> {
+    let mut level = terms.to_vec();
+    level.resize(leaves(terms.len()), Fp::ZERO);
+    let mut levels = vec![level];
+    while levels[levels.len() - 1].len() > 1 {
+        let next = levels[levels.len() - 1].chunks(2).map(|pair| hash_node(pair[0], pair[1])).collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// The root a Merkle proof exposes for `terms`.
+pub fn merkle_root(terms: &[Fp]) -> Fp {
+    levels(terms).pop().expect("a tree has a root")[0]
+}
+
+/// The siblings from a leaf up to the root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerklePath {
+    pub index: usize,
+    /// One per level, the leaf's sibling first.
+    pub siblings: Vec<Fp>,
+}
+
+impl MerklePath {
+    /// The root `leaf` hashes up to at `index`.
+    pub fn root(&self, leaf: Fp) -> Fp {
+        self.siblings.iter().enumerate().fold(leaf, |node, (level, &sibling)| {
+            if (self.index >> level) & 1 == 0 {
+                hash_node(node, sibling)
+            } else {
+                hash_node(sibling, node)
+            }
+        })
+    }
+
+    /// Whether `leaf` at `index` is in the tree with `root`.
+    pub fn verify(&self, leaf: Fp, root: Fp) -> bool {
+        self.root(leaf) == root
+    }
+}
+
+/// The path opening term `index` of `terms`, or `None` past the end.
+pub fn merkle_path(terms: &[Fp], index: usize) -> Option<MerklePath> {
+    if index >= terms.len() {
+        return None;
+    }
+    let levels = levels(terms);
+    let siblings = levels[..levels.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(level, nodes)| nodes[(index >> level) ^ 1])
+        .collect();
+    Some(MerklePath { index, siblings })
+}
+
+/// `FiboCircuit` in `OutputMode::MerkleRoot`, which its spec must say, in
+/// the four-column layout and for the variant.
+#[derive(Clone, Default)]
+pub struct FiboCircuitMerkle(pub FiboCircuit<Fp>);
+
+impl Circuit<Fp> for FiboCircuitMerkle {
+    type Config = HashedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        HashedConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        check_spec(&self.0.spec, OutputMode::MerkleRoot)?;
+        let _span = tracing::info_span!("synthesize", num = self.0.spec.num, output = "merkle root").entered();
+        let chip = FiboChip::construct(config.fibo);
+        let cells = chip.load_sequence(layouter.namespace(|| "sequence"), &self.0.full_witness())?;
+        let zero = chip.load_constant(layouter.namespace(|| "padding"), Fp::ZERO)?;
+
+        let mut level = cells.terms;
+        level.resize(leaves(level.len()), zero);
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| {
+                    let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, 3, 2>::init(
+                        Pow5Chip::construct(config.poseidon.clone()),
+                        layouter.namespace(|| "init"),
+                    )?;
+                    let message = [pair[0].0.clone(), pair[1].0.clone()];
+                    hasher.hash(layouter.namespace(|| "node"), message).map(Number)
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        chip.expose_public(layouter.namespace(|| "expose public"), &level, 0)
+    }
+}
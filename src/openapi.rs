@@ -0,0 +1,137 @@
+//! The OpenAPI document of the proving service, served at
+//! `GET /openapi.json` for client generators, and the Swagger UI over it at
+//! `/swagger-ui/`. The schemas come from the request and response types in
+//! `server`, so they cannot drift from what `Service::handle` parses and
+//! writes; the operations are described by the functions below, which
+//! `#[utoipa::path]` reads and nothing calls.
+#![allow(dead_code)]
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use utoipa::openapi::OpenApi as Document;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::server::{
+    Element, InfoResponse, InspectRequest, InspectResponse, JobResponse, JobStatus, ProveRequest, ProveResponse,
+    VerifyRequest, VerifyResponse,
+};
+
+/// The body of every error answer.
+#[derive(ToSchema)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Prove the `num` terms of the variant from `a`, `b` and `c`, exposing
+/// the last, and answer once the proof is made.
+#[utoipa::path(
+    post,
+    path = "/prove",
+    request_body = ProveRequest,
+    responses(
+        (status = 200, description = "The proof", body = ProveResponse),
+        (status = 400, description = "The request is malformed or past `--max-k`", body = ErrorBody),
+        (status = 500, description = "Reading parameters or proving failed", body = ErrorBody),
+    )
+)]
+fn prove() {}
+
+/// Verify a proof from `/prove` or the CLI, against `public_inputs` if
+/// given and against its own otherwise.
+#[utoipa::path(
+    post,
+    path = "/verify",
+    request_body = VerifyRequest,
+    responses(
+        (status = 200, description = "Whether the proof is valid", body = VerifyResponse),
+        (status = 400, description = "The proof does not parse, or no circuit has its shape", body = ErrorBody),
+    )
+)]
+fn verify() {}
+
+/// Queue a proof for a worker; poll `/jobs/{id}` for it.
+#[utoipa::path(
+    post,
+    path = "/jobs",
+    request_body = ProveRequest,
+    responses(
+        (status = 202, description = "The job, queued", body = JobResponse),
+        (status = 400, description = "The request is malformed or past `--max-k`", body = ErrorBody),
+    )
+)]
+fn submit() {}
+
+/// Where a job is. A finished, failed or cancelled job is reported once
+/// and then forgotten.
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    params(("id" = u64, Path, description = "The id `POST /jobs` answered with")),
+    responses(
+        (status = 200, description = "The job", body = JobResponse),
+        (status = 404, description = "No job has the id", body = ErrorBody),
+    )
+)]
+fn job() {}
+
+/// Cancel a job. One still queued never runs; one running finishes and its
+/// result is dropped.
+#[utoipa::path(
+    delete,
+    path = "/jobs/{id}",
+    params(("id" = u64, Path, description = "The id `POST /jobs` answered with")),
+    responses(
+        (status = 200, description = "The job, cancelled", body = JobResponse),
+        (status = 404, description = "No job has the id", body = ErrorBody),
+        (status = 409, description = "The job has finished", body = ErrorBody),
+    )
+)]
+fn cancel() {}
+
+/// JSON-RPC 2.0: `prove`, `verify`, `inspect` and `info`, a call or a batch
+/// of them. The params and results are the schemas of the same names.
+#[utoipa::path(
+    post,
+    path = "/rpc",
+    request_body(content = Object, description = "A JSON-RPC call, or an array of them"),
+    responses(
+        (status = 200, description = "The answer, or an array of answers", body = Object),
+        (status = 204, description = "Every call was a notification"),
+    )
+)]
+fn rpc() {}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "fibonacci_variant", description = "Proving the Fibonacci-variant recurrence in halo2, as a service."),
+    paths(prove, verify, submit, job, cancel, rpc),
+    components(schemas(
+        Element,
+        ProveRequest,
+        ProveResponse,
+        VerifyRequest,
+        VerifyResponse,
+        JobStatus,
+        JobResponse,
+        InspectRequest,
+        InspectResponse,
+        InfoResponse,
+        ErrorBody,
+    ))
+)]
+struct ApiDoc;
+
+/// The document `GET /openapi.json` serves.
+pub fn document() -> Document {
+    ApiDoc::openapi()
+}
+
+/// The Swagger UI file at `path` below `/swagger-ui/`, the page itself for
+/// an empty one, and its content type; `None` for a file the UI lacks.
+pub fn swagger_ui(path: &str) -> Option<(Cow<'static, [u8]>, String)> {
+    let config = Arc::new(utoipa_swagger_ui::Config::from("/openapi.json"));
+    // Failing only on a config that does not serialize, which this one does.
+    let file = utoipa_swagger_ui::serve(path, config).ok()??;
+    Some((file.bytes, file.content_type))
+}
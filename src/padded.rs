@@ -0,0 +1,206 @@
+//! Padded layout: the circuit is laid out for `max` terms and the actual
+//! length is a public input, so one verifying key serves every
+//! `4 <= num <= max`.
+//!
+//! Selectors are fixed columns and therefore part of the key, so rows past
+//! the end cannot be switched off by the selector alone. Each row instead
+//! carries an advice flag `q`: on an active row the step gate applies, on an
+//! inactive one `d` repeats the previous term, so the last row still holds
+//! term `num - 1`. `q` may only go from one to zero, and a running count `n`
+//! of the terms covered by active rows is exposed next to the result.
+//!
+//! The whole layout is one region, with each row reading the previous one
+//! by rotation, rather than a region per row as in `FiboChip`.
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+};
+use halo2_proofs::poly::Rotation;
+
+use crate::sequence::{field_from_usize, witness};
+
+#[derive(Clone, Debug, Copy)]
+pub struct PaddedConfig {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub d: Column<Advice>,
+    /// One on rows computing a new term, zero on padding.
+    pub q: Column<Advice>,
+    /// Terms covered up to and including this row.
+    pub n: Column<Advice>,
+    /// Instance rows: the result at 0, the term count at 1.
+    pub i: Column<Instance>,
+    /// Every row.
+    pub s: Selector,
+    /// Every row but the first.
+    pub s_chain: Selector,
+    /// The first row only.
+    pub s_first: Selector,
+}
+
+impl PaddedConfig {
+    pub fn configure<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        let [a, b, c, d, q, n] = [(); 6].map(|_| meta.advice_column());
+        let i = meta.instance_column();
+        let s = meta.selector();
+        let s_chain = meta.selector();
+        let s_first = meta.selector();
+
+        meta.enable_equality(d);
+        meta.enable_equality(n);
+        meta.enable_equality(i);
+
+        meta.create_gate("padded step", |meta| {
+            let s = meta.query_selector(s);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let d = meta.query_advice(d, Rotation::cur());
+            let q = meta.query_advice(q, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            vec![
+                s.clone() * q.clone() * (one.clone() - q.clone()),
+                s.clone() * q.clone() * ((a + c.clone()) * b - d.clone()),
+                s * (one - q) * (d - c),
+            ]
+        });
+
+        meta.create_gate("padded chain", |meta| {
+            let s = meta.query_selector(s_chain);
+            let b_prev = meta.query_advice(b, Rotation::prev());
+            let c_prev = meta.query_advice(c, Rotation::prev());
+            let d_prev = meta.query_advice(d, Rotation::prev());
+            let q_prev = meta.query_advice(q, Rotation::prev());
+            let n_prev = meta.query_advice(n, Rotation::prev());
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let q = meta.query_advice(q, Rotation::cur());
+            let n = meta.query_advice(n, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            vec![
+                s.clone() * (a - b_prev),
+                s.clone() * (b - c_prev),
+                s.clone() * (c - d_prev),
+                s.clone() * q.clone() * (one - q_prev),
+                s * (n - n_prev - q),
+            ]
+        });
+
+        meta.create_gate("padded first row", |meta| {
+            let s = meta.query_selector(s_first);
+            let q = meta.query_advice(q, Rotation::cur());
+            let n = meta.query_advice(n, Rotation::cur());
+            vec![
+                s.clone() * (Expression::Constant(F::ONE) - q),
+                s * (n - Expression::Constant(field_from_usize(4))),
+            ]
+        });
+
+        Self { a, b, c, d, q, n, i, s, s_chain, s_first }
+    }
+}
+
+/// The sequence of `num` terms laid out for `max`. Only `max` shapes the
+/// circuit; `num` is a witness, checked against instance row 1.
+#[derive(Clone, Debug, Default)]
+pub struct FiboCircuitPadded<F> {
+    pub max: usize,
+    pub num: Value<usize>,
+    pub a: Value<F>,
+    pub b: Value<F>,
+    pub c: Value<F>,
+}
+
+impl<F: Field> FiboCircuitPadded<F> {
+    /// Rows the layout occupies, whatever `num` is.
+    pub fn rows(&self) -> usize {
+        self.max.max(4) - 3
+    }
+
+    /// Cell values per row: `a, b, c, d, q, n`.
+    fn assignments(&self) -> Value<Vec<[F; 6]>> {
+        let rows = self.rows();
+        self.a.zip(self.b).zip(self.c).zip(self.num).map(|(((a, b), c), num)| {
+            let terms = witness(a, b, c, num);
+            let mut cells: Vec<[F; 6]> = Vec::with_capacity(rows);
+            for r in 0..rows {
+                cells.push(match cells.last() {
+                    Some(&[_, b, c, d, _, n]) if r + 3 >= num => [b, c, d, d, F::ZERO, n],
+                    _ => [
+                        terms[r],
+                        terms[r + 1],
+                        terms[r + 2],
+                        terms[r + 3],
+                        F::ONE,
+                        field_from_usize(r + 4),
+                    ],
+                });
+            }
+            cells
+        })
+    }
+}
+
+impl<F: Field> Circuit<F> for FiboCircuitPadded<F> {
+    type Config = PaddedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            max: self.max,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        PaddedConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        if self.max < 4 {
+            return Err(Error::Synthesis);
+        }
+        self.num.error_if_known_and(|&num| num < 4 || num > self.max)?;
+        let cells = self.assignments();
+        let columns = [config.a, config.b, config.c, config.d, config.q, config.n];
+
+        let (result, count) = layouter.assign_region(
+            || "padded rows",
+            |mut region| {
+                let mut last = None;
+                for r in 0..self.rows() {
+                    config.s.enable(&mut region, r)?;
+                    if r == 0 {
+                        config.s_first.enable(&mut region, r)?;
+                    } else {
+                        config.s_chain.enable(&mut region, r)?;
+                    }
+                    let mut assigned = vec![];
+                    for (j, column) in columns.into_iter().enumerate() {
+                        assigned.push(region.assign_advice(
+                            || format!("row {} column {}", r, j),
+                            column,
+                            r,
+                            || cells.as_ref().map(|cells| cells[r][j]),
+                        )?);
+                    }
+                    // Keep `d` and `n` of the last row for the instance.
+                    let n = assigned.pop().expect("one cell per column");
+                    last = Some((assigned.swap_remove(3), n));
+                }
+                Ok(last.expect("the layout has at least one row"))
+            },
+        )?;
+
+        layouter.constrain_instance(result.cell(), config.i, 0)?;
+        layouter.constrain_instance(count.cell(), config.i, 1)
+    }
+}
@@ -0,0 +1,166 @@
+//! On-disk cache of IPA parameters, and of KZG ones with the `kzg` feature.
+//! Generating them costs time linear in `2^k`, so they are made once per `k`
+//! and read back on later runs.
+//!
+//! Several processes may share one cache, say CLI runs next to a server. A
+//! file is written to a temporary name and renamed into place, so no reader
+//! sees part of one, and generated under an advisory lock on a `.lock` file
+//! beside it, so a `k` missing from the cache is generated once however many
+//! runs ask for it together. `write_atomic` does the first for other files.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use directories::ProjectDirs;
+use halo2_proofs::pasta::EqAffine;
+use halo2_proofs::poly::commitment::Params;
+
+#[cfg(feature = "kzg")]
+use halo2_axiom::halo2curves::bn256::Bn256;
+#[cfg(feature = "kzg")]
+use halo2_axiom::poly::kzg::commitment::ParamsKZG;
+#[cfg(feature = "kzg")]
+use halo2_axiom::SerdeFormat;
+
+use crate::circuit::MAX_K;
+#[cfg(feature = "kzg")]
+use crate::kzg;
+
+/// A directory holding one `params-<k>.bin` file per size generated so far,
+/// and one `params-kzg-<k>.bin` per KZG size.
+#[derive(Clone, Debug)]
+pub struct ParamsCache {
+    dir: PathBuf,
+}
+
+impl ParamsCache {
+    /// The directory is created on first write, not here.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The platform's cache directory for the crate, e.g.
+    /// `~/.cache/fibonacci_variant/params` on Linux, `~/Library/Caches/...`
+    /// on macOS and `%LOCALAPPDATA%\...\cache\params` on Windows. `None`
+    /// without a home directory to put it in.
+    pub fn default_dir() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "fibonacci_variant").map(|dirs| dirs.cache_dir().join("params"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, k: u32) -> PathBuf {
+        self.dir.join(format!("params-{}.bin", k))
+    }
+
+    /// Loads the parameters for `k`, generating and storing them if the cache
+    /// has none yet. A `k` past `MAX_K` is `InvalidInput`, not the panic
+    /// `Params::new` would give.
+    pub fn get(&self, k: u32) -> io::Result<Params<EqAffine>> {
+        if k > MAX_K {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("no parameters for k = {}; they go up to k = {}", k, MAX_K),
+            ));
+        }
+        let path = self.path(k);
+        let read = |file| {
+            let params = Params::read(&mut BufReader::new(file))?;
+            if params.k() != k {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} holds parameters for k = {}", path.display(), params.k()),
+                ));
+            }
+            Ok(params)
+        };
+        self.load_or_make(&path, read, || Params::new(k), |params, writer| params.write(writer))
+    }
+
+    /// `get` for the KZG parameters of `kzg::setup`, which go up to
+    /// `kzg::MAX_K`. They are stored uncompressed, so reading one back skips
+    /// the curve checks; the cache directory is trusted like the IPA files.
+    #[cfg(feature = "kzg")]
+    pub fn get_kzg(&self, k: u32) -> io::Result<ParamsKZG<Bn256>> {
+        use halo2_axiom::poly::commitment::Params as _;
+
+        if k > kzg::MAX_K {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("no KZG parameters for k = {}; they go up to k = {}", k, kzg::MAX_K),
+            ));
+        }
+        let path = self.dir.join(format!("params-kzg-{}.bin", k));
+        let read = |file| {
+            let params = ParamsKZG::read_custom(&mut BufReader::new(file), SerdeFormat::RawBytesUnchecked)?;
+            if params.k() != k {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} holds KZG parameters for k = {}", path.display(), params.k()),
+                ));
+            }
+            Ok(params)
+        };
+        let write = |params: &ParamsKZG<Bn256>, writer: &mut BufWriter<File>| {
+            params.write_custom(writer, SerdeFormat::RawBytesUnchecked)
+        };
+        self.load_or_make(&path, read, || kzg::setup(k), write)
+    }
+
+    /// Reads `path`, or on a miss makes the parameters and stores them
+    /// there. The making happens under the lock file, and a run that waited
+    /// for the lock reads what the holder stored instead of making its own.
+    fn load_or_make<P>(
+        &self,
+        path: &Path,
+        read: impl Fn(File) -> io::Result<P>,
+        make: impl FnOnce() -> P,
+        write: impl FnOnce(&P, &mut BufWriter<File>) -> io::Result<()>,
+    ) -> io::Result<P> {
+        match File::open(path) {
+            Ok(file) => return read(file),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        fs::create_dir_all(&self.dir)?;
+        let lock = OpenOptions::new().create(true).truncate(false).write(true).open(path.with_extension("lock"))?;
+        lock.lock()?;
+        match File::open(path) {
+            Ok(file) => return read(file),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        let params = make();
+        write_atomic(path, |writer| write(&params, writer))?;
+        Ok(params)
+    }
+}
+
+/// Writes `path` through a temporary file beside it, renamed into place
+/// once complete, so a concurrent or interrupted run never sees a partial
+/// file: readers find the old contents or the new, or none.
+pub fn write_atomic(path: &Path, write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>) -> io::Result<()> {
+    // Unique per thread as well as per process.
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".tmp-{}-{}", process::id(), n));
+    let tmp = PathBuf::from(tmp);
+    let result = File::create(&tmp).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()
+    });
+    match result.and_then(|()| fs::rename(&tmp, path)) {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            let _ = fs::remove_file(&tmp);
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Parsing field elements from decimal or `0x`-prefixed hex strings of full
+//! field width, rejecting anything at or above the modulus rather than
+//! reducing it.
+
+use std::error::Error;
+use std::fmt;
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::group::ff::PrimeField;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseFieldError {
+    Empty,
+    InvalidDigit(char),
+    /// The number is at least the field modulus.
+    OutOfRange,
+}
+
+impl fmt::Display for ParseFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseFieldError::Empty => write!(f, "no digits"),
+            ParseFieldError::InvalidDigit(c) => write!(f, "invalid digit {:?}", c),
+            ParseFieldError::OutOfRange => write!(f, "not less than the field modulus"),
+        }
+    }
+}
+
+impl Error for ParseFieldError {}
+
+/// Parses `s` as decimal, or as hex with a `0x` prefix, in either case.
+pub fn parse_field<F: PrimeField>(s: &str) -> Result<F, ParseFieldError> {
+    let (digits, radix) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => (hex, 16),
+        None => (s, 10),
+    };
+    if digits.is_empty() {
+        return Err(ParseFieldError::Empty);
+    }
+
+    // Accumulate little-endian in the width of the representation; a number
+    // that does not even fit shows up as a carry out of the top byte.
+    let mut repr = F::Repr::default();
+    let mut bytes = vec![0u32; repr.as_ref().len()];
+    for c in digits.chars() {
+        let mut carry = c.to_digit(radix).ok_or(ParseFieldError::InvalidDigit(c))?;
+        for byte in bytes.iter_mut() {
+            let acc = *byte * radix + carry;
+            *byte = acc & 0xff;
+            carry = acc >> 8;
+        }
+        if carry != 0 {
+            return Err(ParseFieldError::OutOfRange);
+        }
+    }
+
+    // `PrimeField` leaves the byte order to the field; find it from one.
+    let little_endian = F::ONE.to_repr().as_ref()[0] == 1;
+    let out = repr.as_mut();
+    for (i, byte) in bytes.into_iter().enumerate() {
+        let i = if little_endian { i } else { out.len() - 1 - i };
+        out[i] = byte as u8;
+    }
+    Option::from(F::from_repr(repr)).ok_or(ParseFieldError::OutOfRange)
+}
+
+/// `parse_field`, as a witness value for the circuit.
+pub fn parse_value<F: PrimeField>(s: &str) -> Result<Value<F>, ParseFieldError> {
+    parse_field(s).map(Value::known)
+}
@@ -0,0 +1,101 @@
+//! Choosing the floor planner. Every circuit here names `SimpleFloorPlanner`,
+//! which stacks each region below the last; `V1` packs regions into the
+//! free space of the columns they use. `Planned` swaps the planner of any
+//! circuit, and `Planner` picks one at run time, to compare the row usage of
+//! the two. The planner decides where cells land, so keys are per planner.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::floor_planner::V1;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error, FloorPlanner};
+
+use crate::dispatch::WithCircuit;
+
+/// `C` laid out by `P` instead of its own floor planner.
+pub struct Planned<C, P>(pub C, PhantomData<P>);
+
+impl<C, P> Planned<C, P> {
+    pub fn new(circuit: C) -> Self {
+        Self(circuit, PhantomData)
+    }
+}
+
+impl<F: Field, C: Circuit<F>, P: FloorPlanner> Circuit<F> for Planned<C, P> {
+    type Config = C::Config;
+    type FloorPlanner = P;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+/// A floor planner, chosen at run time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Planner {
+    /// `SimpleFloorPlanner`, what the circuits use on their own.
+    #[default]
+    Simple,
+    /// `V1`, packing regions into free space.
+    V1,
+}
+
+impl Planner {
+    /// The name `FromStr` accepts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Planner::Simple => "simple",
+            Planner::V1 => "v1",
+        }
+    }
+}
+
+impl fmt::Display for Planner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Planner::Simple => write!(f, "the simple floor planner"),
+            Planner::V1 => write!(f, "the V1 floor planner"),
+        }
+    }
+}
+
+impl FromStr for Planner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "simple" => Ok(Planner::Simple),
+            "v1" => Ok(Planner::V1),
+            _ => Err(format!("unknown floor planner {:?}, expected \"simple\" or \"v1\"", s)),
+        }
+    }
+}
+
+/// `f` on the circuit laid out by `planner`, for `with_circuit`.
+pub struct WithPlanner<W> {
+    pub planner: Planner,
+    pub f: W,
+}
+
+impl<W: WithCircuit> WithCircuit for WithPlanner<W> {
+    type Output = W::Output;
+
+    fn with<C: Circuit<Fp>>(self, circuit: C) -> Self::Output {
+        match self.planner {
+            Planner::Simple => self.f.with(Planned::<_, SimpleFloorPlanner>::new(circuit)),
+            Planner::V1 => self.f.with(Planned::<_, V1>::new(circuit)),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! What proving and verifying a sequence takes, in one import:
+//! `use fibonacci_variant::prelude::*;` brings the circuit, its chip and
+//! spec, the prover and verifier and what they return, the proof format and
+//! parameters cache, and the halo2 types their signatures use.
+
+pub use halo2_proofs::circuit::Value;
+pub use halo2_proofs::pasta::{EqAffine, Fp};
+pub use halo2_proofs::plonk::{Circuit, ProvingKey, VerifyingKey};
+pub use halo2_proofs::poly::commitment::Params;
+
+pub use crate::chip::{FiboChip, FiboConfig};
+pub use crate::circuit::{min_k_for, FiboCircuit, FiboSpec, Layout, OutputMode};
+pub use crate::context::{Keys, ProverContext, Setup};
+pub use crate::error::FibError;
+pub use crate::fingerprint::VkHash;
+pub use crate::params::ParamsCache;
+pub use crate::proof::{verify_batch, verifying_key, Proof};
+pub use crate::prover::{keygen, prove, prove_spec, prove_with_transcript, verify, verify_with_transcript, ProveOutput};
+pub use crate::recurrence::Recurrence;
+pub use crate::sequence::witness;
+pub use crate::transcript::TranscriptHash;
@@ -0,0 +1,315 @@
+//! A proof bundled with what it takes to check it elsewhere: the public
+//! inputs, `k`, and the circuit shape. halo2_proofs 0.3 cannot serialize a
+//! verifying key, so the verifier regenerates it from the shape, then
+//! compares its fingerprint with the one the proof records, telling a
+//! proof for a changed circuit apart from a proof that is wrong.
+//!
+//! The binary encoding is, with integers little-endian:
+//!
+//! | field    | bytes                                  |
+//! |----------|----------------------------------------|
+//! | `k`      | u32                                    |
+//! | `num`    | u64                                    |
+//! | `output` | u8: 0 `Result`, 1 `Continuation`, 2 `InitialAndResult`, 3 `Hashed`, 4 `Committed`, 5 `MerkleRoot`, 6 `Chunk` |
+//! | `layout` | u8: 0 `Rows`, 1 `Column`                |
+//! | `recurrence` | u8: 0 `Variant`, 1 `Fibonacci`     |
+//! | `scheme` | u8: the transcript over IPA, 0 `Blake2b` or 1 `Poseidon`; 2 for a `kzg::KzgProof` |
+//! | `vk_hash` | 32 bytes, the `VkHash` of the key      |
+//! | instance | u32 count, then 32-byte canonical `Fp` each |
+//! | proof    | u32 length, then the transcript bytes  |
+//!
+//! The hex encoding is the same bytes in lowercase hex. A KZG proof is
+//! encoded the same way, with bn256 scalars for the instance values;
+//! `backend::AnyProof` reads either kind by the scheme byte.
+
+use std::io::{self, Read, Write};
+
+use halo2_proofs::pasta::{group::ff::PrimeField, EqAffine, Fp};
+use halo2_proofs::plonk::{keygen_vk, BatchVerifier, Circuit, Error, VerifyingKey};
+use halo2_proofs::poly::commitment::Params;
+
+use crate::circuit::{FiboCircuit, FiboSpec, Layout, OutputMode};
+use crate::dispatch::{with_circuit, WithCircuit};
+use crate::error::FibError;
+use crate::fingerprint::VkHash;
+use crate::recurrence::Recurrence;
+use crate::prover;
+use crate::transcript::TranscriptHash;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    pub k: u32,
+    pub spec: FiboSpec,
+    /// Values of the instance column.
+    pub instance: Vec<Fp>,
+    /// The transcript `prover::prove_with_transcript` returned.
+    pub bytes: Vec<u8>,
+    /// The hash the transcript was made with.
+    pub transcript: TranscriptHash,
+    /// The fingerprint of the verifying key the proof was made under.
+    pub vk_hash: VkHash,
+}
+
+impl Proof {
+    /// A proof made with the default Blake2b transcript; set `transcript`
+    /// after for one made with another.
+    pub fn new(k: u32, spec: FiboSpec, instance: Vec<Fp>, bytes: Vec<u8>, vk_hash: VkHash) -> Self {
+        Self { k, spec, instance, bytes, transcript: TranscriptHash::Blake2b, vk_hash }
+    }
+
+    /// Regenerates the verifying key from the spec and checks the proof
+    /// against the bundled instance values. `params` must be for `self.k`.
+    pub fn verify(&self, params: &Params<EqAffine>) -> Result<(), FibError> {
+        self.check_shape()?;
+        if params.k() != self.k {
+            return Err(FibError::ParamsMismatch { params: params.k(), proof: self.k });
+        }
+        self.verify_with_key(params, &self.verifying_key(params)?)
+    }
+
+    /// Checks that the spec and `k` read from an untrusted proof describe a
+    /// circuit at all, before anything is sized by them: key generation
+    /// lays out `spec.num` terms, so a proof claiming `2^40` of them at
+    /// `k = 8` is refused here rather than allocated, and so is a `k` past
+    /// `circuit::MAX_K`, which `ParamsCache::get` would panic on. Call it
+    /// before loading parameters for `self.k`.
+    pub fn check_shape(&self) -> Result<(), FibError> {
+        self.spec.check_num()?;
+        self.spec.check_k(self.k)?;
+        Ok(())
+    }
+
+    /// `verify` under `vk`, which must have the fingerprint the proof
+    /// records.
+    pub fn verify_with_key(&self, params: &Params<EqAffine>, vk: &VerifyingKey<EqAffine>) -> Result<(), FibError> {
+        self.check_vk_hash(VkHash::of(vk))?;
+        prover::verify_with_transcript(params, vk, &self.bytes, &self.instance, self.transcript)
+    }
+
+    /// The verifying key the spec calls for. `params` must be for `self.k`.
+    pub fn verifying_key(&self, params: &Params<EqAffine>) -> Result<VerifyingKey<EqAffine>, FibError> {
+        verifying_key(params, self.spec)
+    }
+
+    fn check_vk_hash(&self, expected: VkHash) -> Result<(), FibError> {
+        if self.vk_hash == expected {
+            Ok(())
+        } else {
+            Err(FibError::VkMismatch { recorded: self.vk_hash, expected })
+        }
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_shape(writer, self.k, &self.spec)?;
+        writer.write_all(&[match self.transcript {
+            TranscriptHash::Blake2b => 0,
+            TranscriptHash::Poseidon => 1,
+        }])?;
+        writer.write_all(&self.vk_hash.0)?;
+        write_len(writer, self.instance.len())?;
+        for value in &self.instance {
+            writer.write_all(value.to_repr().as_ref())?;
+        }
+        write_len(writer, self.bytes.len())?;
+        writer.write_all(&self.bytes)
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let (k, spec) = read_shape(reader)?;
+        let transcript = match read_array::<_, 1>(reader)? {
+            [0] => TranscriptHash::Blake2b,
+            [1] => TranscriptHash::Poseidon,
+            [KZG_SCHEME] => return Err(invalid("a KZG proof; read it as a kzg::KzgProof")),
+            _ => return Err(invalid("unknown transcript")),
+        };
+        let vk_hash = VkHash(read_array(reader)?);
+        // Grow with the data actually read rather than trusting the lengths,
+        // so a corrupt header cannot ask for gigabytes up front.
+        let mut instance = vec![];
+        for _ in 0..read_len(reader)? {
+            let value = Fp::from_repr(read_array(reader)?);
+            instance.push(
+                Option::from(value)
+                    .ok_or_else(|| invalid("instance value is not a canonical field element"))?,
+            );
+        }
+        let bytes = read_bytes(reader)?;
+        Ok(Self { k, spec, instance, bytes, transcript, vk_hash })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write(&mut bytes).expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    /// Reads a proof that must span all of `bytes`.
+    pub fn from_bytes(mut bytes: &[u8]) -> io::Result<Self> {
+        let proof = Self::read(&mut bytes)?;
+        if !bytes.is_empty() {
+            return Err(invalid("trailing bytes after the proof"));
+        }
+        Ok(proof)
+    }
+
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.to_bytes())
+    }
+
+    /// Parses the hex encoding; surrounding whitespace is ignored.
+    pub fn from_hex(hex: &str) -> io::Result<Self> {
+        Self::from_bytes(&from_hex(hex)?)
+    }
+}
+
+/// The scheme byte of a KZG proof.
+pub(crate) const KZG_SCHEME: u8 = 2;
+
+/// Where the scheme byte is: after `k`, `num`, `output`, `layout` and
+/// `recurrence`.
+pub(crate) const SCHEME_OFFSET: usize = 4 + 8 + 3;
+
+/// Writes the fields before the scheme byte.
+pub(crate) fn write_shape<W: Write>(writer: &mut W, k: u32, spec: &FiboSpec) -> io::Result<()> {
+    writer.write_all(&k.to_le_bytes())?;
+    writer.write_all(&(spec.num as u64).to_le_bytes())?;
+    writer.write_all(&[match spec.output {
+        OutputMode::Result => 0,
+        OutputMode::Continuation => 1,
+        OutputMode::InitialAndResult => 2,
+        OutputMode::Hashed => 3,
+        OutputMode::Committed => 4,
+        OutputMode::MerkleRoot => 5,
+        OutputMode::Chunk => 6,
+    }])?;
+    writer.write_all(&[match spec.layout {
+        Layout::Rows => 0,
+        Layout::Column => 1,
+    }])?;
+    writer.write_all(&[match spec.recurrence {
+        Recurrence::Variant => 0,
+        Recurrence::Fibonacci => 1,
+    }])
+}
+
+/// Reads the fields `write_shape` wrote.
+pub(crate) fn read_shape<R: Read>(reader: &mut R) -> io::Result<(u32, FiboSpec)> {
+    let k = u32::from_le_bytes(read_array(reader)?);
+    let num = u64::from_le_bytes(read_array(reader)?)
+        .try_into()
+        .map_err(|_| invalid("term count does not fit in usize"))?;
+    let output = match read_array::<_, 1>(reader)? {
+        [0] => OutputMode::Result,
+        [1] => OutputMode::Continuation,
+        [2] => OutputMode::InitialAndResult,
+        [3] => OutputMode::Hashed,
+        [4] => OutputMode::Committed,
+        [5] => OutputMode::MerkleRoot,
+        [6] => OutputMode::Chunk,
+        _ => return Err(invalid("unknown output mode")),
+    };
+    let layout = match read_array::<_, 1>(reader)? {
+        [0] => Layout::Rows,
+        [1] => Layout::Column,
+        _ => return Err(invalid("unknown layout")),
+    };
+    let recurrence = match read_array::<_, 1>(reader)? {
+        [0] => Recurrence::Variant,
+        [1] => Recurrence::Fibonacci,
+        _ => return Err(invalid("unknown recurrence")),
+    };
+    Ok((k, FiboSpec { num, output, layout, recurrence }))
+}
+
+/// Reads a `u32` length and that many bytes, growing with the data actually
+/// read rather than trusting the length.
+pub(crate) fn read_bytes<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let len = read_len(reader)?;
+    let mut bytes = vec![];
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decodes hex; surrounding whitespace is ignored.
+pub(crate) fn from_hex(hex: &str) -> io::Result<Vec<u8>> {
+    let hex = hex.trim();
+    if !hex.len().is_multiple_of(2) {
+        return Err(invalid("odd number of hex digits"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| invalid("not a hex digit"))
+        })
+        .collect()
+}
+
+/// The verifying key of the circuit `spec` calls for, without a proof.
+/// `params` must be for the `k` it is proven at.
+pub fn verifying_key(params: &Params<EqAffine>, spec: FiboSpec) -> Result<VerifyingKey<EqAffine>, FibError> {
+    let circuit = FiboCircuit::<Fp> { spec, ..FiboCircuit::default() };
+    Ok(with_circuit(circuit, Keygen(params))??)
+}
+
+/// Checks every proof in `proofs` under `vk`, against each proof's bundled
+/// instance values, folding their final multi-scalar multiplications into
+/// one. All of them must be for the circuit `vk` was generated from and for
+/// `params`, and record its fingerprint. Fails if any proof is invalid,
+/// without saying which. halo2's batch verifier reads Blake2b transcripts
+/// only, so proofs made with another are checked one at a time.
+pub fn verify_batch(proofs: &[Proof], vk: &VerifyingKey<EqAffine>, params: &Params<EqAffine>) -> Result<(), FibError> {
+    let _span = tracing::info_span!("verify_batch", proofs = proofs.len()).entered();
+    let vk_hash = VkHash::of(vk);
+    let mut batch = BatchVerifier::new();
+    for proof in proofs {
+        proof.check_vk_hash(vk_hash)?;
+        match proof.transcript {
+            TranscriptHash::Blake2b => batch.add_proof(vec![vec![proof.instance.clone()]], proof.bytes.clone()),
+            hash => prover::verify_with_transcript(params, vk, &proof.bytes, &proof.instance, hash)?,
+        }
+    }
+    if batch.finalize(params, vk) {
+        Ok(())
+    } else {
+        Err(FibError::ProofVerificationFailed)
+    }
+}
+
+/// The verifying key of whichever circuit the spec calls for.
+struct Keygen<'a>(&'a Params<EqAffine>);
+
+impl WithCircuit for Keygen<'_> {
+    type Output = Result<VerifyingKey<EqAffine>, Error>;
+
+    fn with<C: Circuit<Fp>>(self, circuit: C) -> Self::Output {
+        tracing::info_span!("keygen_vk", k = self.0.k()).in_scope(|| keygen_vk(self.0, &circuit))
+    }
+}
+
+pub(crate) fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub(crate) fn write_len<W: Write>(writer: &mut W, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|_| invalid("length does not fit in u32"))?;
+    writer.write_all(&len.to_le_bytes())
+}
+
+pub(crate) fn read_len<R: Read>(reader: &mut R) -> io::Result<usize> {
+    Ok(u32::from_le_bytes(read_array(reader)?) as usize)
+}
+
+pub(crate) fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
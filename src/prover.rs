@@ -0,0 +1,261 @@
+//! Real proofs, beyond `MockProver`: key generation, proving and verifying
+//! with the IPA commitment scheme over the Pasta curves, the backend
+//! halo2_proofs ships, and a Blake2b or Poseidon transcript. `prove_spec`
+//! does the whole of it for a `FiboCircuit` and returns a `ProveOutput`.
+
+use std::time::{Duration, Instant};
+
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, ProvingKey, SingleVerifier,
+    VerifyingKey,
+};
+use halo2_proofs::poly::commitment::Params;
+use halo2_proofs::transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptRead, TranscriptWrite,
+};
+
+use crate::circuit::{FiboCircuit, FiboSpec, OutputMode};
+use crate::compat::{os_rng, seeded_rng};
+use crate::dispatch::{with_circuit, Unsupported, WithCircuit};
+use crate::error::FibError;
+use crate::fingerprint::VkHash;
+use crate::hashed::hash_result;
+use crate::merkle::merkle_root;
+use crate::proof::Proof;
+use crate::sequence::field_from_usize;
+use crate::transcript::{PoseidonRead, PoseidonWrite, TranscriptHash};
+
+/// A proof with everything needed to verify it later but the parameters:
+/// the shape, the exact instance values, the transcript and the fingerprint
+/// of the key it was made under. `into_proof` packs it for the wire.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProveOutput {
+    pub k: u32,
+    pub spec: FiboSpec,
+    pub bytes: Vec<u8>,
+    pub instance: Vec<Fp>,
+    pub vk_hash: VkHash,
+    pub transcript: TranscriptHash,
+    pub timings: Timings,
+}
+
+/// How long making a `ProveOutput` took.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// Zero for a proof under a key made beforehand.
+    pub keygen: Duration,
+    /// `create_proof`, which commits and opens in one call.
+    pub prove: Duration,
+}
+
+impl ProveOutput {
+    pub fn into_proof(self) -> Proof {
+        let proof = Proof::new(self.k, self.spec, self.instance, self.bytes, self.vk_hash);
+        Proof { transcript: self.transcript, ..proof }
+    }
+}
+
+impl From<ProveOutput> for Proof {
+    fn from(output: ProveOutput) -> Self {
+        output.into_proof()
+    }
+}
+
+/// The values of the instance column for `spec`, from `a, b, c`; those of
+/// `kzg::instance` over the Pasta field. A committed output is unsupported:
+/// its commitment needs a blinding the initial values do not give.
+pub fn instance(spec: &FiboSpec, [a, b, c]: [Fp; 3]) -> Result<Vec<Fp>, Unsupported> {
+    let terms = spec.recurrence.terms(a, b, c, spec.num);
+    let last = |i: usize| terms[spec.num - i];
+    match spec.output {
+        OutputMode::Result => Ok(vec![last(1)]),
+        OutputMode::Continuation => Ok(vec![last(3), last(2), last(1), field_from_usize(spec.num)]),
+        OutputMode::InitialAndResult => Ok(vec![a, b, c, last(1)]),
+        OutputMode::Chunk => Ok(vec![a, b, c, last(3), last(2), last(1)]),
+        OutputMode::Hashed => Ok(vec![hash_result(last(1))]),
+        OutputMode::MerkleRoot => Ok(vec![merkle_root(&terms)]),
+        OutputMode::Committed => Err(Unsupported { spec: *spec, reason: "a commitment needs a blinding as well" }),
+    }
+}
+
+/// Keygen and proving, timed, for whichever circuit `circuit`'s spec calls
+/// for: `keygen` then `prove_with_transcript`, or `prove_seeded` with a
+/// `seed`. `params` fixes `k`.
+pub fn prove_spec(
+    params: &Params<EqAffine>,
+    circuit: &FiboCircuit<Fp>,
+    instance: &[Fp],
+    hash: TranscriptHash,
+    seed: Option<[u8; 32]>,
+) -> Result<ProveOutput, FibError> {
+    circuit.spec.check_k(params.k())?;
+    let with = ProveSpec { params, spec: circuit.spec, instance, hash, seed };
+    with_circuit(circuit.clone(), with)?
+}
+
+struct ProveSpec<'a> {
+    params: &'a Params<EqAffine>,
+    spec: FiboSpec,
+    instance: &'a [Fp],
+    hash: TranscriptHash,
+    seed: Option<[u8; 32]>,
+}
+
+impl WithCircuit for ProveSpec<'_> {
+    type Output = Result<ProveOutput, FibError>;
+
+    fn with<C: Circuit<Fp>>(self, circuit: C) -> Self::Output {
+        let start = Instant::now();
+        let pk = keygen(self.params, &circuit)?;
+        let keygen = start.elapsed();
+        let start = Instant::now();
+        let bytes = prove_inner(self.params, &pk, &circuit, self.instance, self.hash, self.seed)?;
+        Ok(ProveOutput {
+            k: self.params.k(),
+            spec: self.spec,
+            bytes,
+            instance: self.instance.to_vec(),
+            vk_hash: VkHash::of(pk.get_vk()),
+            transcript: self.hash,
+            timings: Timings { keygen, prove: start.elapsed() },
+        })
+    }
+}
+
+/// Generates the proving key, which includes the verifying key. Only the
+/// shape of `circuit` is used, so a witness-free one is enough.
+pub fn keygen<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    circuit: &C,
+) -> Result<ProvingKey<EqAffine>, FibError> {
+    let _span = tracing::info_span!("keygen", k = params.k()).entered();
+    let circuit = circuit.without_witnesses();
+    let vk = tracing::info_span!("keygen_vk").in_scope(|| keygen_vk(params, &circuit))?;
+    Ok(tracing::info_span!("keygen_pk").in_scope(|| keygen_pk(params, vk, &circuit))?)
+}
+
+/// Proves `circuit` against the values of its single instance column and
+/// returns the proof bytes. Blinding comes from the operating system.
+pub fn prove<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instance: &[Fp],
+) -> Result<Vec<u8>, FibError> {
+    prove_with_transcript(params, pk, circuit, instance, TranscriptHash::Blake2b)
+}
+
+/// `prove` with the transcript `hash`; `verify_with_transcript` must be
+/// given the same.
+pub fn prove_with_transcript<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instance: &[Fp],
+    hash: TranscriptHash,
+) -> Result<Vec<u8>, FibError> {
+    prove_inner(params, pk, circuit, instance, hash, None)
+}
+
+/// `prove_with_transcript` with the blinding drawn from a ChaCha20 stream
+/// seeded by `seed`, so the same inputs give byte-identical proofs. For test
+/// vectors and debugging only: the blinding is what keeps the witness
+/// private, and anyone with the seed can recompute it.
+pub fn prove_seeded<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instance: &[Fp],
+    hash: TranscriptHash,
+    seed: [u8; 32],
+) -> Result<Vec<u8>, FibError> {
+    prove_inner(params, pk, circuit, instance, hash, Some(seed))
+}
+
+fn prove_inner<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instance: &[Fp],
+    hash: TranscriptHash,
+    seed: Option<[u8; 32]>,
+) -> Result<Vec<u8>, FibError> {
+    let _span = tracing::info_span!("prove", k = params.k(), transcript = hash.name(), seeded = seed.is_some())
+        .entered();
+    match hash {
+        TranscriptHash::Blake2b => {
+            let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+            create(params, pk, circuit, instance, seed, &mut transcript)?;
+            Ok(transcript.finalize())
+        }
+        TranscriptHash::Poseidon => {
+            let mut transcript = PoseidonWrite::init(vec![]);
+            create(params, pk, circuit, instance, seed, &mut transcript)?;
+            Ok(transcript.finalize())
+        }
+    }
+}
+
+/// `create_proof` for one circuit, blinded from `seed` or, without one, the
+/// operating system.
+fn create<C: Circuit<Fp>, T: TranscriptWrite<EqAffine, Challenge255<EqAffine>>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: &C,
+    instance: &[Fp],
+    seed: Option<[u8; 32]>,
+    transcript: &mut T,
+) -> Result<(), FibError> {
+    let circuits = std::slice::from_ref(circuit);
+    match seed {
+        Some(seed) => create_proof(params, pk, circuits, &[&[instance]], seeded_rng(seed), transcript)?,
+        None => create_proof(params, pk, circuits, &[&[instance]], os_rng(), transcript)?,
+    }
+    Ok(())
+}
+
+/// Checks `proof` against the values of the instance column. Whatever halo2
+/// finds wrong, a truncated transcript as much as a failed check, is
+/// `FibError::ProofVerificationFailed`.
+pub fn verify(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    instance: &[Fp],
+) -> Result<(), FibError> {
+    verify_with_transcript(params, vk, proof, instance, TranscriptHash::Blake2b)
+}
+
+/// `verify` for a proof made with the transcript `hash`.
+pub fn verify_with_transcript(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    instance: &[Fp],
+    hash: TranscriptHash,
+) -> Result<(), FibError> {
+    let _span = tracing::info_span!("verify", k = params.k(), transcript = hash.name()).entered();
+    match hash {
+        TranscriptHash::Blake2b => {
+            check(params, vk, instance, &mut Blake2bRead::<_, _, Challenge255<_>>::init(proof))
+        }
+        TranscriptHash::Poseidon => check(params, vk, instance, &mut PoseidonRead::init(proof)),
+    }
+}
+
+fn check<T: TranscriptRead<EqAffine, Challenge255<EqAffine>>>(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    instance: &[Fp],
+    transcript: &mut T,
+) -> Result<(), FibError> {
+    verify_proof(
+        params,
+        vk,
+        SingleVerifier::new(params),
+        &[&[instance]],
+        transcript,
+    )
+    .map_err(|_| FibError::ProofVerificationFailed)
+}
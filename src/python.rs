@@ -0,0 +1,114 @@
+//! The `fibonacci_variant` Python module, behind the `python` feature and
+//! built with maturin (`maturin develop`; see `pyproject.toml`). Field
+//! elements go in as Python ints or decimal or `0x` hex strings and come
+//! out as ints; proofs are `bytes` in `Proof`'s binary encoding, and the
+//! IPA parameters come from a `ParamsCache` directory, made on first use.
+
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::Fp;
+
+use crate::circuit::{FiboCircuit, FiboSpec};
+use crate::fingerprint::VkHash;
+use crate::params::ParamsCache;
+use crate::parse::parse_field;
+use crate::proof::Proof;
+use crate::prover;
+use crate::sequence::witness;
+
+fn value_error(err: impl ToString) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// An int or a string, as `parse_field` reads it.
+fn field(value: &Bound<'_, PyAny>) -> PyResult<Fp> {
+    parse_field(value.str()?.to_str()?).map_err(value_error)
+}
+
+/// `value` as a Python int.
+fn to_int<'py>(py: Python<'py>, value: Fp) -> PyResult<Bound<'py, PyAny>> {
+    py.import("builtins")?.getattr("int")?.call1((format!("{:?}", value), 16))
+}
+
+/// The terms from `a, b, c` the circuit for `num` lays out, at least four,
+/// checking `num` is at least one.
+fn terms(a: Fp, b: Fp, c: Fp, num: usize) -> PyResult<Vec<Fp>> {
+    let spec = FiboSpec { num, ..FiboSpec::default() };
+    spec.check_num().map_err(value_error)?;
+    Ok(witness(a, b, c, spec.layout_terms()))
+}
+
+/// Proves the `num` terms of the variant from `a, b, c` at the smallest `k`,
+/// exposing the last, and returns the proof.
+#[pyfunction]
+#[pyo3(signature = (a, b, c, num, params_dir = PathBuf::from("params")))]
+fn prove<'py>(
+    py: Python<'py>,
+    a: &Bound<'py, PyAny>,
+    b: &Bound<'py, PyAny>,
+    c: &Bound<'py, PyAny>,
+    num: usize,
+    params_dir: PathBuf,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let (a, b, c) = (field(a)?, field(b)?, field(c)?);
+    let terms = terms(a, b, c, num)?;
+    let instance = vec![terms[num - 1]];
+    let spec = FiboSpec { num, ..FiboSpec::default() };
+    let circuit = FiboCircuit { spec, ..FiboCircuit::from_witness(terms.into_iter().map(Value::known).collect()) };
+    let k = circuit.spec.min_k();
+    let (bytes, vk_hash) = py.allow_threads(|| -> PyResult<_> {
+        let params = ParamsCache::new(params_dir).get(k)?;
+        let pk = prover::keygen(&params, &circuit).map_err(value_error)?;
+        let bytes = prover::prove(&params, &pk, &circuit, &instance).map_err(value_error)?;
+        Ok((bytes, VkHash::of(pk.get_vk())))
+    })?;
+    let proof = Proof::new(k, circuit.spec, instance, bytes, vk_hash);
+    Ok(PyBytes::new(py, &proof.to_bytes()))
+}
+
+/// Checks `proof` against `public_inputs`, or against the inputs it carries
+/// if there are none, raising `ValueError` if it does not verify.
+#[pyfunction]
+#[pyo3(signature = (proof, public_inputs = Vec::new(), params_dir = PathBuf::from("params")))]
+fn verify(
+    py: Python<'_>,
+    proof: &[u8],
+    public_inputs: Vec<Bound<'_, PyAny>>,
+    params_dir: PathBuf,
+) -> PyResult<()> {
+    let mut proof = Proof::from_bytes(proof).map_err(value_error)?;
+    if !public_inputs.is_empty() {
+        proof.instance = public_inputs.iter().map(field).collect::<PyResult<_>>()?;
+    }
+    proof.check_shape().map_err(value_error)?;
+    py.allow_threads(|| {
+        let params = ParamsCache::new(params_dir).get(proof.k)?;
+        proof.verify(&params).map_err(value_error)
+    })
+}
+
+/// The last of the `num` terms from `a, b, c`: what `prove` exposes.
+#[pyfunction]
+fn expected_output<'py>(
+    py: Python<'py>,
+    a: &Bound<'py, PyAny>,
+    b: &Bound<'py, PyAny>,
+    c: &Bound<'py, PyAny>,
+    num: usize,
+) -> PyResult<Bound<'py, PyAny>> {
+    let terms = terms(field(a)?, field(b)?, field(c)?, num)?;
+    to_int(py, terms[num - 1])
+}
+
+#[pymodule]
+fn fibonacci_variant(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(prove, module)?)?;
+    module.add_function(wrap_pyfunction!(verify, module)?)?;
+    module.add_function(wrap_pyfunction!(expected_output, module)?)?;
+    Ok(())
+}
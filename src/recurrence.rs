@@ -0,0 +1,168 @@
+//! Third-order recurrences other than the variant. A `RecurrenceGate` says
+//! how the next term follows from the three before it, once as the gate's
+//! polynomial and once natively for the witness; `FiboChip` builds its
+//! step gate from it, and `RecurrenceCircuit` proves a sequence of it.
+//! `Fibonacci` is the classic sequence, and `Recurrence` names the gates
+//! the CLI and proof files know.
+
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::{fmt, str::FromStr};
+use halo2_proofs::circuit::{Value, Layouter, SimpleFloorPlanner};
+use halo2_proofs::{plonk::*};
+use halo2_proofs::arithmetic::Field;
+
+use crate::chip::{FiboChip, FiboConfig, GATE_NAME};
+use crate::circuit::{synthesize_sequence, FiboCircuit, Layout};
+use crate::sequence::terms_with;
+
+/// `d = f(a, b, c)` for consecutive terms `a, b, c, d`. The two methods must
+/// agree, or honest witnesses fail the gate; `MockProver` shows which row.
+pub trait RecurrenceGate<F: Field> {
+    /// Name of the step gate in the constraint system.
+    const NAME: &'static str;
+
+    /// `f` over the queried cells.
+    fn expression(a: Expression<F>, b: Expression<F>, c: Expression<F>) -> Expression<F>;
+
+    /// `f` over field elements.
+    fn next(a: F, b: F, c: F) -> F;
+}
+
+/// The variant this crate is named for: `d = (a + c) * b`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Variant;
+
+impl<F: Field> RecurrenceGate<F> for Variant {
+    const NAME: &'static str = GATE_NAME;
+
+    fn expression(a: Expression<F>, b: Expression<F>, c: Expression<F>) -> Expression<F> {
+        (a + c) * b
+    }
+
+    fn next(a: F, b: F, c: F) -> F {
+        (a + c) * b
+    }
+}
+
+/// The classic Fibonacci sequence, each term the sum of the two before it:
+/// `d = b + c`. `a` only fills the first window; for the textbook sequence
+/// start from `a, b, a + b`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fibonacci;
+
+impl<F: Field> RecurrenceGate<F> for Fibonacci {
+    const NAME: &'static str = "fibonacci gate";
+
+    fn expression(_a: Expression<F>, b: Expression<F>, c: Expression<F>) -> Expression<F> {
+        b + c
+    }
+
+    fn next(_a: F, b: F, c: F) -> F {
+        b + c
+    }
+}
+
+/// The recurrences a `FiboSpec` can name, so that a proof says which one
+/// it is for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Recurrence {
+    /// `Variant`: `FiboCircuit`.
+    #[default]
+    Variant,
+    /// `Fibonacci`: `RecurrenceCircuit<F, Fibonacci>`.
+    Fibonacci,
+}
+
+impl Recurrence {
+    /// The name `FromStr` accepts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Recurrence::Variant => "variant",
+            Recurrence::Fibonacci => "fibonacci",
+        }
+    }
+
+    /// The first `num` terms from `a, b, c`, natively.
+    pub fn terms<F: Field>(&self, a: F, b: F, c: F, num: usize) -> Vec<F> {
+        let next = match self {
+            Recurrence::Variant => <Variant as RecurrenceGate<F>>::next,
+            Recurrence::Fibonacci => <Fibonacci as RecurrenceGate<F>>::next,
+        };
+        terms_with(a, b, c, num, next)
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Variant => write!(f, "the variant (a + c) * b"),
+            Recurrence::Fibonacci => write!(f, "Fibonacci b + c"),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "variant" => Ok(Recurrence::Variant),
+            "fibonacci" => Ok(Recurrence::Fibonacci),
+            _ => Err(format!("unknown recurrence {:?}, expected \"variant\" or \"fibonacci\"", s)),
+        }
+    }
+}
+
+/// The first `num` terms of `G` from `a, b, c`; `sequence::witness` for
+/// any recurrence.
+pub fn recurrence_witness<F: Field, G: RecurrenceGate<F>>(
+    a: Value<F>,
+    b: Value<F>,
+    c: Value<F>,
+    num: usize,
+) -> Vec<Value<F>> {
+    terms_with(a, b, c, num, |a, b, c| a.zip(b).zip(c).map(|((a, b), c)| G::next(a, b, c)))
+}
+
+/// `FiboCircuit` proving the recurrence `G` rather than the variant, in the
+/// four-column layout. A precomputed `witness` must follow `G`. The spec's
+/// `recurrence` is not consulted, `G` may be one it has no name for.
+#[derive(Clone, Default)]
+pub struct RecurrenceCircuit<F, G>(pub FiboCircuit<F>, pub PhantomData<G>);
+
+impl<F: Field, G: RecurrenceGate<F>> RecurrenceCircuit<F, G> {
+    pub fn new(circuit: FiboCircuit<F>) -> Self {
+        Self(circuit, PhantomData)
+    }
+}
+
+impl<F: Field, G: RecurrenceGate<F>> Circuit<F> for RecurrenceCircuit<F, G> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure_recurrence::<G>(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        let spec = &self.0.spec;
+        if spec.layout != Layout::Rows {
+            return Err(Error::Synthesis);
+        }
+        let witness = match &self.0.witness {
+            Some(witness) => Cow::Borrowed(witness),
+            None => Cow::Owned(recurrence_witness::<F, G>(self.0.a, self.0.b, self.0.c, spec.layout_terms())),
+        };
+        let chip = FiboChip::construct(config);
+        synthesize_sequence(&chip, spec, &witness, layouter)
+    }
+}
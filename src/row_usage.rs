@@ -0,0 +1,244 @@
+//! Row-usage instrumentation: runs a circuit's floor planner against a
+//! recording backend and reports, per region name, how many cells the
+//! regions reserve versus how many they assign.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::layouter::RegionColumn;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{
+    Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+    FloorPlanner, Instance, Selector,
+};
+
+/// Usage of all regions sharing one name.
+pub struct RegionUsage {
+    pub name: String,
+    /// Number of regions with this name.
+    pub count: usize,
+    /// Rows spanned by the regions.
+    pub rows: usize,
+    /// Cells reserved: each region's rows times the columns it touches.
+    pub allocated: usize,
+    /// Cells actually assigned or selectors enabled.
+    pub used: usize,
+}
+
+impl RegionUsage {
+    pub fn waste(&self) -> usize {
+        self.allocated - self.used
+    }
+}
+
+/// Row usage of a whole circuit, sorted by waste.
+pub struct RowUsage {
+    pub regions: Vec<RegionUsage>,
+    /// Rows up to and including the last one anything was assigned in.
+    pub total_rows: usize,
+    /// Rows below `total_rows` not covered by any region.
+    pub gap_rows: usize,
+    /// Selectors enabled anywhere.
+    pub selectors: usize,
+    /// Columns on either side of a copy constraint.
+    pub copied_columns: usize,
+}
+
+impl RowUsage {
+    /// Synthesizes `circuit` with its own floor planner and records where
+    /// every region landed.
+    pub fn measure<F: Field, C: Circuit<F>>(circuit: &C) -> Result<Self, Error> {
+        let mut cs = ConstraintSystem::default();
+        let config = C::configure(&mut cs);
+        // The configured constants columns are not readable from outside
+        // halo2_proofs, so give the planner a column of our own; constants
+        // are assigned outside regions and do not affect the report.
+        let constants = vec![cs.fixed_column()];
+
+        let mut recorder = Recorder::default();
+        C::FloorPlanner::synthesize(&mut recorder, circuit, config, constants)?;
+        Ok(recorder.finish())
+    }
+}
+
+impl fmt::Display for RowUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>6} {:>6} {:>10} {:>6} {:>6}",
+            "region", "count", "rows", "allocated", "used", "waste"
+        )?;
+        for region in &self.regions {
+            writeln!(
+                f,
+                "{:<24} {:>6} {:>6} {:>10} {:>6} {:>6}",
+                region.name,
+                region.count,
+                region.rows,
+                region.allocated,
+                region.used,
+                region.waste()
+            )?;
+        }
+        write!(
+            f,
+            "total rows: {} ({} outside any region)",
+            self.total_rows, self.gap_rows
+        )
+    }
+}
+
+struct RecordedRegion {
+    name: String,
+    cells: HashSet<(RegionColumn, usize)>,
+}
+
+#[derive(Default)]
+struct Recorder {
+    regions: Vec<RecordedRegion>,
+    current: Option<RecordedRegion>,
+    last_row: Option<usize>,
+    selectors: HashSet<Selector>,
+    copied: HashSet<Column<Any>>,
+}
+
+impl Recorder {
+    fn record(&mut self, column: RegionColumn, row: usize) {
+        self.last_row = self.last_row.max(Some(row));
+        if let Some(region) = self.current.as_mut() {
+            region.cells.insert((column, row));
+        }
+    }
+
+    fn finish(self) -> RowUsage {
+        let mut by_name: BTreeMap<String, RegionUsage> = BTreeMap::new();
+        let mut covered = BTreeSet::new();
+        for region in self.regions {
+            let rows: BTreeSet<usize> = region.cells.iter().map(|&(_, row)| row).collect();
+            let columns: HashSet<RegionColumn> =
+                region.cells.iter().map(|&(column, _)| column).collect();
+            let span = match (rows.first(), rows.last()) {
+                (Some(first), Some(last)) => *first..*last + 1,
+                _ => 0..0,
+            };
+            let usage = by_name.entry(region.name.clone()).or_insert(RegionUsage {
+                name: region.name,
+                count: 0,
+                rows: 0,
+                allocated: 0,
+                used: 0,
+            });
+            usage.count += 1;
+            usage.rows += span.len();
+            usage.allocated += span.len() * columns.len();
+            usage.used += region.cells.len();
+            covered.extend(span);
+        }
+
+        let total_rows = self.last_row.map_or(0, |row| row + 1);
+        let mut regions: Vec<RegionUsage> = by_name.into_values().collect();
+        regions.sort_by_key(|usage| Reverse(usage.waste()));
+        RowUsage {
+            regions,
+            total_rows,
+            gap_rows: total_rows - covered.len(),
+            selectors: self.selectors.len(),
+            copied_columns: self.copied.len(),
+        }
+    }
+}
+
+impl<F: Field> Assignment<F> for Recorder {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.current = Some(RecordedRegion {
+            name: name_fn().into(),
+            cells: HashSet::new(),
+        });
+    }
+
+    fn exit_region(&mut self) {
+        self.regions.extend(self.current.take());
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        _: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.selectors.insert(*selector);
+        self.record((*selector).into(), row);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.record(Column::<Any>::from(column).into(), row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.record(Column::<Any>::from(column).into(), row);
+        Ok(())
+    }
+
+    fn copy(&mut self, left: Column<Any>, _: usize, right: Column<Any>, _: usize) -> Result<(), Error> {
+        self.copied.extend([left, right]);
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
@@ -0,0 +1,121 @@
+//! JSON-RPC 2.0 over the proving service, at `POST /rpc`, for tooling that
+//! speaks it more readily than REST, such as Ethereum clients and node
+//! software. The methods are `prove`, `verify`, `inspect` and `info`; the
+//! params of the first two are the bodies of `POST /prove` and
+//! `POST /verify`, by name, and their results the REST responses. A batch
+//! is answered with one array, and a notification, a call without an `id`,
+//! is carried out without an answer.
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+
+use crate::server::{Service, ServiceError};
+
+/// The methods `handle` answers, as `info` lists them.
+pub const METHODS: &[&str] = &["prove", "verify", "inspect", "info"];
+
+/// Error codes the specification reserves.
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// Calls in one batch at most. Each may be a proof, and one HTTP request
+/// should not hold a handler for an unbounded number of them.
+pub const MAX_BATCH: usize = 64;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+impl From<ServiceError> for RpcError {
+    fn from(err: ServiceError) -> Self {
+        let code = match err {
+            ServiceError::BadRequest(_) => INVALID_PARAMS,
+            _ => INTERNAL_ERROR,
+        };
+        RpcError::new(code, err.to_string())
+    }
+}
+
+/// Answers the body of a `POST /rpc`: `None` when every call in it was a
+/// notification, and there is nothing to send back.
+pub fn handle(service: &Service, body: &str) -> Option<String> {
+    let answer = match serde_json::from_str::<Value>(body) {
+        Err(err) => Some(failure(Value::Null, RpcError::new(PARSE_ERROR, err.to_string()))),
+        Ok(Value::Array(calls)) if calls.is_empty() => {
+            Some(failure(Value::Null, RpcError::new(INVALID_REQUEST, "an empty batch")))
+        }
+        Ok(Value::Array(calls)) if calls.len() > MAX_BATCH => {
+            let message = format!("{} calls in a batch, past the limit of {}", calls.len(), MAX_BATCH);
+            Some(failure(Value::Null, RpcError::new(INVALID_REQUEST, message)))
+        }
+        Ok(Value::Array(calls)) => {
+            let answers: Vec<Value> = calls.into_iter().filter_map(|call| self::call(service, call)).collect();
+            (!answers.is_empty()).then_some(Value::Array(answers))
+        }
+        Ok(call) => self::call(service, call),
+    };
+    answer.map(|answer| answer.to_string())
+}
+
+/// Answers one call, unless it is a notification.
+fn call(service: &Service, call: Value) -> Option<Value> {
+    let Value::Object(mut call) = call else {
+        return Some(failure(Value::Null, RpcError::new(INVALID_REQUEST, "a call must be an object")));
+    };
+    let id = match call.remove("id") {
+        None => None,
+        Some(id @ (Value::Null | Value::Number(_) | Value::String(_))) => Some(id),
+        Some(_) => {
+            return Some(failure(Value::Null, RpcError::new(INVALID_REQUEST, "an id must be a number or string")));
+        }
+    };
+    let result = request(&call).and_then(|(method, params)| dispatch(service, method, params));
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+        Err(err) => failure(id, err),
+    })
+}
+
+/// The method and params of a well-formed call.
+fn request(call: &Map<String, Value>) -> Result<(&str, Option<&Value>), RpcError> {
+    if call.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Err(RpcError::new(INVALID_REQUEST, "\"jsonrpc\" must be \"2.0\""));
+    }
+    let method = call.get("method").and_then(Value::as_str);
+    let method = method.ok_or_else(|| RpcError::new(INVALID_REQUEST, "\"method\" must be a string"))?;
+    match call.get("params") {
+        None | Some(Value::Object(_)) => Ok((method, call.get("params"))),
+        Some(_) => Err(RpcError::new(INVALID_PARAMS, "params must be an object of named members")),
+    }
+}
+
+fn dispatch(service: &Service, method: &str, params: Option<&Value>) -> Result<Value, RpcError> {
+    let result = match method {
+        "prove" => serde_json::to_value(service.prove(&params_of(params)?)?),
+        "verify" => serde_json::to_value(service.verify(&params_of(params)?)?),
+        "inspect" => serde_json::to_value(service.inspect(&params_of(params)?)?),
+        "info" => serde_json::to_value(service.info()),
+        _ => return Err(RpcError::new(METHOD_NOT_FOUND, format!("no method {:?}", method))),
+    };
+    result.map_err(|err| RpcError::new(INTERNAL_ERROR, err.to_string()))
+}
+
+fn params_of<T: DeserializeOwned>(params: Option<&Value>) -> Result<T, RpcError> {
+    let params = params.cloned().unwrap_or_else(|| Value::Object(Map::new()));
+    serde_json::from_value(params).map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))
+}
+
+fn failure(id: Value, err: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "error": { "code": err.code, "message": err.message }, "id": id })
+}
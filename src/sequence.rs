@@ -0,0 +1,87 @@
+//! Native computation of the sequence, outside any circuit.
+
+use std::ops::{Add, Mul};
+use halo2_proofs::arithmetic::Field;
+
+use crate::compat::par_map;
+
+/// The first `num` terms from `a, b, c`, which for `num` below three are
+/// some of those three. Works over field elements as well as over `Value`s
+/// of them, which is how the circuit builds its witness. Over a field this
+/// is the value the circuit checks, however long the sequence; compare
+/// `get_fibovar_seq`.
+pub fn witness<T>(a: T, b: T, c: T, num: usize) -> Vec<T>
+where
+    T: Copy + Add<Output = T> + Mul<Output = T>,
+{
+    terms_with(a, b, c, num, |a, b, c| (a + c) * b)
+}
+
+/// The first `num` terms from `a, b, c` of the recurrence whose next term
+/// is `step` of the three before it; `witness` for any recurrence.
+pub fn terms_with<T: Copy>(a: T, b: T, c: T, num: usize, mut step: impl FnMut(T, T, T) -> T) -> Vec<T> {
+    let mut seq = Vec::with_capacity(num);
+    seq.extend([a, b, c].into_iter().take(num));
+    for i in 3..num {
+        seq.push(step(seq[i - 3], seq[i - 2], seq[i - 1]));
+    }
+    seq
+}
+
+/// `witness` for each `([a, b, c], num)` of `sequences`. The recurrence is
+/// serial within a sequence, so it is the sequences that run in parallel,
+/// with the `multicore` feature.
+pub fn witnesses<T>(sequences: &[([T; 3], usize)]) -> Vec<Vec<T>>
+where
+    T: Copy + Send + Sync + Add<Output = T> + Mul<Output = T>,
+{
+    par_map(sequences, |&([a, b, c], num)| witness(a, b, c, num))
+}
+
+/// Public outputs of a proof in `OutputMode::Continuation`: the state the
+/// recurrence stopped in. A proof of `num` terms from some start vouches for
+/// `last`, and `continue_from` picks up from there without the earlier terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Continuation<F> {
+    /// Terms `num - 3`, `num - 2` and `num - 1`.
+    pub last: [F; 3],
+    /// Terms computed so far, counting the three initial ones.
+    pub num: usize,
+}
+
+impl<F: Field> Continuation<F> {
+    /// The instance column the proof is verified against.
+    pub fn instance(&self) -> Vec<F> {
+        let mut instance = self.last.to_vec();
+        instance.push(field_from_usize(self.num));
+        instance
+    }
+}
+
+/// Extends the sequence a continuation proof stopped at by `extra_steps`
+/// terms, natively. The result is the statement a proof of
+/// `outputs.num + extra_steps` terms from the same start would expose.
+pub fn continue_from<F: Field>(outputs: &Continuation<F>, extra_steps: usize) -> Continuation<F> {
+    let [x, y, z] = outputs.last;
+    let terms = witness(x, y, z, 3 + extra_steps);
+    Continuation {
+        last: [terms[extra_steps], terms[extra_steps + 1], terms[extra_steps + 2]],
+        num: outputs.num + extra_steps,
+    }
+}
+
+/// `n` as a field element; `Field` itself has no integer conversion.
+pub fn field_from_usize<F: Field>(n: usize) -> F {
+    (0..usize::BITS).rev().fold(F::ZERO, |acc, bit| {
+        let acc = acc.double();
+        if n >> bit & 1 == 1 { acc + F::ONE } else { acc }
+    })
+}
+
+/// The sequence over `u64`, for display. The terms outgrow `u64` quickly
+/// (from 1, 2, 3 the eleventh overflows), after which they no longer match
+/// the circuit; use `witness` over the circuit's field for those.
+pub fn get_fibovar_seq(a: u64, b: u64, c: u64, num: usize) -> Vec<u64> {
+    witness(a, b, c, num)
+}
+
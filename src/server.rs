@@ -0,0 +1,540 @@
+//! Proving as a service: `POST /prove` and `POST /verify` over HTTP, with
+//! JSON bodies. A `Service` keeps the IPA parameters and the keys it has
+//! made in memory, in `CircuitArtifacts` on top of the on-disk
+//! `ParamsCache`, so only the first request for a shape pays for them. Requests are answered by a fixed
+//! number of threads; proving itself is parallel already.
+//!
+//! Long proofs can go through `POST /jobs` instead of `/prove`: the request
+//! joins a queue served by a fixed number of workers, and `GET /jobs/{id}`
+//! polls it. `DELETE /jobs/{id}` cancels a job. One still queued never runs;
+//! halo2 cannot stop a proof partway, so one already running finishes and
+//! its result is dropped. A finished job is reported once and then
+//! forgotten, as is one nobody asks about within `JOB_TTL`.
+//!
+//! `POST /rpc` answers the same as JSON-RPC 2.0; see `rpc`. With the
+//! `openapi` feature, `GET /openapi.json` describes the endpoints and
+//! `/swagger-ui/` browses them; see `openapi`.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use std::{error, fmt, thread};
+
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::poly::commitment::Params;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::artifacts::CircuitArtifacts;
+use crate::backend::Backend;
+use crate::circuit::{FiboCircuit, FiboSpec};
+use crate::inspect::inspect;
+#[cfg(feature = "openapi")]
+use crate::openapi;
+use crate::params::ParamsCache;
+use crate::parse::parse_field;
+use crate::proof::Proof;
+use crate::rpc;
+
+/// Bytes of a request body read; a longer one fails to parse.
+const MAX_BODY: u64 = 1 << 20;
+
+/// How long a finished job's result is kept for `GET /jobs/{id}`.
+pub const JOB_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// A field element in JSON: a number, or a decimal or `0x` hex string for
+/// values past `u64`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(untagged)]
+pub enum Element {
+    Number(u64),
+    String(String),
+}
+
+impl Element {
+    /// The field element, or `BadRequest` for a string that is none.
+    pub fn parse(&self) -> Result<Fp, ServiceError> {
+        match self {
+            Element::Number(n) => Ok(Fp::from(*n)),
+            Element::String(s) => parse_field(s).map_err(|err| ServiceError::BadRequest(err.to_string())),
+        }
+    }
+}
+
+/// `POST /prove`: the `num` terms of the variant from `a, b, c`, exposing
+/// the last.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProveRequest {
+    pub a: Element,
+    pub b: Element,
+    pub c: Element,
+    pub num: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProveResponse {
+    pub k: u32,
+    /// `Proof::to_hex`, for `POST /verify` or the CLI's `verify`.
+    pub proof: String,
+    /// The instance column, as `Fp` prints with `{:?}`.
+    pub public_inputs: Vec<String>,
+}
+
+impl ProveResponse {
+    /// The proof, for `Proof::verify` or a file.
+    pub fn to_proof(&self) -> io::Result<Proof> {
+        Proof::from_hex(&self.proof)
+    }
+}
+
+/// `POST /verify`: a proof from `/prove` or the CLI, checked against
+/// `public_inputs` if there are any and against its own otherwise.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VerifyRequest {
+    pub proof: String,
+    #[serde(default)]
+    pub public_inputs: Vec<Element>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VerifyResponse {
+    pub valid: bool,
+}
+
+/// `inspect` over JSON-RPC: the cost of proving `num` terms, laid out at
+/// `k` or the smallest that fits.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InspectRequest {
+    pub num: usize,
+    #[serde(default)]
+    pub k: Option<u32>,
+}
+
+/// `CircuitReport` for the circuit `/prove` would prove.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InspectResponse {
+    pub k: u32,
+    pub rows: usize,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub selectors: usize,
+    pub instance_columns: usize,
+    pub degree: usize,
+    pub min_k: u32,
+    pub proof_size: usize,
+}
+
+/// `info` over JSON-RPC: what this server proves and up to what size.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InfoResponse {
+    /// The crate version.
+    pub version: &'static str,
+    /// `Backend::name` of the proofs it makes.
+    pub backend: &'static str,
+    pub max_k: u32,
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>))]
+    pub methods: &'static [&'static str],
+}
+
+/// Where a job from `POST /jobs` is.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done { result: ProveResponse },
+    Failed { error: String },
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct JobResponse {
+    pub id: u64,
+    #[serde(flatten)]
+    pub status: JobStatus,
+}
+
+/// A parsed `ProveRequest`.
+#[derive(Clone, Copy, Debug)]
+struct Sequence {
+    initial: [Fp; 3],
+    num: usize,
+}
+
+#[derive(Default)]
+struct Jobs {
+    next_id: u64,
+    status: HashMap<u64, JobStatus>,
+    /// When each job that has a result got it.
+    finished: HashMap<u64, Instant>,
+    queue: VecDeque<(u64, Sequence)>,
+}
+
+impl Jobs {
+    fn forget(&mut self, id: u64) {
+        self.status.remove(&id);
+        self.finished.remove(&id);
+    }
+
+    /// Forgets the results no one has asked for within `JOB_TTL`.
+    fn prune(&mut self) {
+        let expired: Vec<u64> =
+            self.finished.iter().filter(|(_, at)| at.elapsed() > JOB_TTL).map(|(&id, _)| id).collect();
+        for id in expired {
+            self.forget(id);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ServiceError {
+    /// The request is malformed or asks for too much; answered with 400.
+    BadRequest(String),
+    /// No job has the id; answered with 404.
+    NotFound(u64),
+    /// The job has finished and cannot be cancelled; answered with 409.
+    Finished(u64),
+    /// Reading parameters or proving failed; answered with 500.
+    Internal(String),
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::BadRequest(msg) | ServiceError::Internal(msg) => write!(f, "{}", msg),
+            ServiceError::NotFound(id) => write!(f, "no job {}", id),
+            ServiceError::Finished(id) => write!(f, "job {} has finished", id),
+        }
+    }
+}
+
+impl error::Error for ServiceError {}
+
+impl ServiceError {
+    fn status(&self) -> u16 {
+        match self {
+            ServiceError::BadRequest(_) => 400,
+            ServiceError::NotFound(_) => 404,
+            ServiceError::Finished(_) => 409,
+            ServiceError::Internal(_) => 500,
+        }
+    }
+}
+
+fn internal(err: impl ToString) -> ServiceError {
+    ServiceError::Internal(err.to_string())
+}
+
+/// Parameters, keys and jobs shared by every request.
+pub struct Service {
+    artifacts: Arc<CircuitArtifacts>,
+    max_k: u32,
+    jobs: Mutex<Jobs>,
+    /// Signalled when a job joins the queue.
+    queued: Condvar,
+}
+
+impl Service {
+    /// Refuses proofs needing parameters past `max_k`, whose generation
+    /// alone could hold the server up for minutes.
+    pub fn new(cache: ParamsCache, max_k: u32) -> Self {
+        Self::with_artifacts(Arc::new(CircuitArtifacts::new(cache)), max_k)
+    }
+
+    /// `new` over `artifacts` shared with other users, who see the keys
+    /// the service makes and whose keys it uses.
+    pub fn with_artifacts(artifacts: Arc<CircuitArtifacts>, max_k: u32) -> Self {
+        Self { artifacts, max_k, jobs: Mutex::default(), queued: Condvar::new() }
+    }
+
+    pub fn artifacts(&self) -> &Arc<CircuitArtifacts> {
+        &self.artifacts
+    }
+
+    fn jobs(&self) -> MutexGuard<'_, Jobs> {
+        // Every update leaves the jobs consistent, so a panic elsewhere
+        // leaves them usable.
+        self.jobs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn check_k(&self, k: u32) -> Result<(), ServiceError> {
+        if k > self.max_k {
+            return Err(ServiceError::BadRequest(format!(
+                "k = {} is past this server's limit of {}",
+                k, self.max_k
+            )));
+        }
+        Ok(())
+    }
+
+    /// Parses `request`, refusing it up front if it is too short or too long,
+    /// so a queued job fails no sooner than `/prove` would.
+    fn sequence(&self, request: &ProveRequest) -> Result<Sequence, ServiceError> {
+        let initial = [request.a.parse()?, request.b.parse()?, request.c.parse()?];
+        let num = request.num;
+        let spec = FiboSpec { num, ..FiboSpec::default() };
+        spec.check_num().map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        self.check_k(spec.min_k())?;
+        Ok(Sequence { initial, num })
+    }
+
+    fn params(&self, k: u32) -> Result<Arc<Params<EqAffine>>, ServiceError> {
+        self.check_k(k)?;
+        self.artifacts.params(k).map_err(internal)
+    }
+
+    pub fn prove(&self, request: &ProveRequest) -> Result<ProveResponse, ServiceError> {
+        self.prove_sequence(self.sequence(request)?)
+    }
+
+    fn prove_sequence(&self, sequence: Sequence) -> Result<ProveResponse, ServiceError> {
+        let Sequence { initial, num } = sequence;
+        let spec = FiboSpec { num, ..FiboSpec::default() };
+        let k = spec.min_k();
+        self.params(k)?;
+        let prover = self.artifacts.prover(k, spec).map_err(internal)?;
+        let proof = prover.prove(initial).map_err(internal)?.into_proof();
+        Ok(ProveResponse {
+            k,
+            proof: proof.to_hex(),
+            public_inputs: proof.instance.iter().map(|x| format!("{:?}", x)).collect(),
+        })
+    }
+
+    pub fn verify(&self, request: &VerifyRequest) -> Result<VerifyResponse, ServiceError> {
+        let mut proof =
+            Proof::from_hex(&request.proof).map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        if !request.public_inputs.is_empty() {
+            proof.instance = request.public_inputs.iter().map(Element::parse).collect::<Result<_, _>>()?;
+        }
+        // The header is the client's: size nothing by it until it checks out.
+        proof.check_shape().map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        let params = self.params(proof.k)?;
+        let artifacts = self
+            .artifacts
+            .artifacts(proof.k, proof.spec)
+            .map_err(|_| ServiceError::BadRequest(format!("no circuit has the shape {}", proof.spec)))?;
+        let valid = proof.verify_with_key(&params, &artifacts.vk).is_ok();
+        Ok(VerifyResponse { valid })
+    }
+
+    /// The circuit's cost, measured without parameters or keys. Laying it
+    /// out still takes time linear in `2^k`, so `k` is held to `max_k`.
+    pub fn inspect(&self, request: &InspectRequest) -> Result<InspectResponse, ServiceError> {
+        let spec = FiboSpec { num: request.num, ..FiboSpec::default() };
+        spec.check_num().map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        let k = request.k.unwrap_or_else(|| spec.min_k());
+        spec.check_k(k).map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        self.check_k(k)?;
+        let circuit = FiboCircuit { spec, ..FiboCircuit::default() };
+        let report = inspect(circuit, k).map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        Ok(InspectResponse {
+            k: report.k,
+            rows: report.rows,
+            advice_columns: report.advice_columns,
+            fixed_columns: report.fixed_columns,
+            selectors: report.selectors,
+            instance_columns: report.instance_columns,
+            degree: report.degree,
+            min_k: report.min_k,
+            proof_size: report.proof_size,
+        })
+    }
+
+    pub fn info(&self) -> InfoResponse {
+        InfoResponse {
+            version: env!("CARGO_PKG_VERSION"),
+            backend: Backend::Ipa.name(),
+            max_k: self.max_k,
+            methods: rpc::METHODS,
+        }
+    }
+
+    /// Queues `request` for a worker.
+    pub fn submit(&self, request: &ProveRequest) -> Result<JobResponse, ServiceError> {
+        let sequence = self.sequence(request)?;
+        let mut jobs = self.jobs();
+        jobs.prune();
+        let id = jobs.next_id;
+        jobs.next_id += 1;
+        jobs.status.insert(id, JobStatus::Queued);
+        jobs.queue.push_back((id, sequence));
+        self.queued.notify_one();
+        Ok(JobResponse { id, status: JobStatus::Queued })
+    }
+
+    /// Where job `id` is. Once it is done, failed or cancelled, this is the
+    /// last answer: the job is forgotten and later calls are `NotFound`.
+    pub fn job(&self, id: u64) -> Result<JobResponse, ServiceError> {
+        let mut jobs = self.jobs();
+        let status = jobs.status.get(&id).cloned().ok_or(ServiceError::NotFound(id))?;
+        if let JobStatus::Done { .. } | JobStatus::Failed { .. } | JobStatus::Cancelled = status {
+            jobs.forget(id);
+        }
+        Ok(JobResponse { id, status })
+    }
+
+    /// Cancels job `id`. One still queued is forgotten at once; one running
+    /// is kept as cancelled until it finishes or is asked about.
+    pub fn cancel(&self, id: u64) -> Result<JobResponse, ServiceError> {
+        let mut jobs = self.jobs();
+        match jobs.status.get(&id) {
+            None => return Err(ServiceError::NotFound(id)),
+            Some(JobStatus::Done { .. } | JobStatus::Failed { .. }) => return Err(ServiceError::Finished(id)),
+            Some(JobStatus::Queued) => {
+                jobs.queue.retain(|&(queued, _)| queued != id);
+                jobs.forget(id);
+            }
+            Some(JobStatus::Running | JobStatus::Cancelled) => {
+                jobs.status.insert(id, JobStatus::Cancelled);
+            }
+        }
+        Ok(JobResponse { id, status: JobStatus::Cancelled })
+    }
+
+    /// Proves queued jobs one at a time, forever.
+    fn work(&self) {
+        loop {
+            let (id, sequence) = {
+                let mut jobs = self.jobs();
+                loop {
+                    if let Some((id, sequence)) = jobs.queue.pop_front() {
+                        jobs.status.insert(id, JobStatus::Running);
+                        break (id, sequence);
+                    }
+                    jobs = self.queued.wait(jobs).unwrap_or_else(PoisonError::into_inner);
+                }
+            };
+            let status = match self.prove_sequence(sequence) {
+                Ok(result) => JobStatus::Done { result },
+                Err(err) => JobStatus::Failed { error: err.to_string() },
+            };
+            let mut jobs = self.jobs();
+            match jobs.status.get_mut(&id) {
+                Some(current @ JobStatus::Running) => {
+                    *current = status;
+                    jobs.finished.insert(id, Instant::now());
+                }
+                Some(JobStatus::Cancelled) => jobs.forget(id),
+                _ => {}
+            }
+        }
+    }
+
+    /// Answers one request, routing it by method and path.
+    pub fn handle(&self, mut request: Request) -> io::Result<()> {
+        let mut body = String::new();
+        let read = request.as_reader().take(MAX_BODY).read_to_string(&mut body);
+        let job = request.url().strip_prefix("/jobs/").map(str::parse::<u64>);
+        let result = match (request.method(), request.url(), job) {
+            _ if read.is_err() => Err(ServiceError::BadRequest("the body is not UTF-8".to_string())),
+            (Method::Post, "/prove", _) => json_body(&body).and_then(|req| self.prove(&req)).and_then(ok),
+            (Method::Post, "/verify", _) => json_body(&body).and_then(|req| self.verify(&req)).and_then(ok),
+            (Method::Post, "/jobs", _) => json_body(&body)
+                .and_then(|req| self.submit(&req))
+                .and_then(to_json)
+                .map(|json| (202, json)),
+            (Method::Post, "/rpc", _) => match rpc::handle(self, &body) {
+                Some(json) => Ok((200, json)),
+                None => return request.respond(Response::empty(204)),
+            },
+            #[cfg(feature = "openapi")]
+            (Method::Get, "/openapi.json", _) => openapi::document().to_json().map_err(internal).map(|json| (200, json)),
+            #[cfg(feature = "openapi")]
+            (Method::Get, "/swagger-ui", _) => {
+                let location = Header::from_bytes(&b"Location"[..], &b"/swagger-ui/"[..]).expect("a valid header");
+                return request.respond(Response::empty(308).with_header(location));
+            }
+            #[cfg(feature = "openapi")]
+            (Method::Get, url, _) if url.starts_with("/swagger-ui/") => {
+                return match openapi::swagger_ui(&url["/swagger-ui/".len()..]) {
+                    Some((bytes, content_type)) => {
+                        let content_type =
+                            Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).expect("a valid header");
+                        request.respond(Response::from_data(bytes).with_header(content_type))
+                    }
+                    None => request.respond(json_response(404, &error_json("no such file"))),
+                };
+            }
+            (Method::Get, _, Some(Ok(id))) => self.job(id).and_then(ok),
+            (Method::Delete, _, Some(Ok(id))) => self.cancel(id).and_then(ok),
+            (_, "/prove" | "/verify" | "/jobs" | "/rpc", _) => {
+                return request.respond(json_response(405, &error_json("use POST")));
+            }
+            (_, _, Some(Ok(_))) => {
+                return request.respond(json_response(405, &error_json("use GET or DELETE")));
+            }
+            _ => return request.respond(json_response(404, &error_json("no such endpoint"))),
+        };
+        match result {
+            Ok((status, json)) => request.respond(json_response(status, &json)),
+            Err(err) => request.respond(json_response(err.status(), &error_json(&err.to_string()))),
+        }
+    }
+}
+
+fn json_body<T: DeserializeOwned>(body: &str) -> Result<T, ServiceError> {
+    serde_json::from_str(body).map_err(|err| ServiceError::BadRequest(err.to_string()))
+}
+
+fn to_json<T: Serialize>(value: T) -> Result<String, ServiceError> {
+    serde_json::to_string(&value).map_err(internal)
+}
+
+fn ok<T: Serialize>(value: T) -> Result<(u16, String), ServiceError> {
+    to_json(value).map(|json| (200, json))
+}
+
+fn error_json(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+fn json_response(status: u16, json: &str) -> Response<io::Cursor<Vec<u8>>> {
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("a valid header");
+    Response::from_string(json).with_status_code(status).with_header(content_type)
+}
+
+/// Serves `service` on `addr`, e.g. `127.0.0.1:8080`, until the process
+/// ends, with `handlers` threads answering requests and `workers` threads
+/// proving queued jobs. A request that panics is dropped, and its thread
+/// goes on to the next.
+pub fn serve(addr: &str, service: Service, handlers: usize, workers: usize) -> io::Result<()> {
+    let server = Arc::new(Server::http(addr).map_err(io::Error::other)?);
+    let service = Arc::new(service);
+    for _ in 0..workers {
+        let service = service.clone();
+        thread::spawn(move || service.work());
+    }
+    let handlers: Vec<_> = (0..handlers)
+        .map(|_| {
+            let (server, service) = (server.clone(), service.clone());
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    match panic::catch_unwind(AssertUnwindSafe(|| service.handle(request))) {
+                        Ok(Ok(())) => {}
+                        Ok(Err(err)) => eprintln!("error: answering a request: {}", err),
+                        Err(_) => eprintln!("error: answering a request panicked"),
+                    }
+                }
+            })
+        })
+        .collect();
+    for handler in handlers {
+        handler.join().map_err(|_| io::Error::other("a request handler panicked"))?;
+    }
+    Ok(())
+}
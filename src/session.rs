@@ -0,0 +1,175 @@
+//! An opt-in log of prove runs, for following the prover's performance over
+//! time without a service to send metrics to. `prove --session-log` appends
+//! one `Run` per line, as JSON, to a file of the user's choosing, and
+//! `report` reads one log or several and summarizes them per shape. Nothing
+//! is recorded unless asked for, and nothing leaves the machine.
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::Backend;
+use crate::circuit::FiboSpec;
+
+/// What `prove --stats` reports. Times are in seconds; halo2 commits and
+/// opens within one `create_proof` call, so `prove` covers both.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProveStats {
+    pub k: u32,
+    pub num: usize,
+    pub proof_bytes: usize,
+    pub params_secs: f64,
+    pub witness_secs: f64,
+    pub keygen_secs: f64,
+    pub prove_secs: f64,
+    /// Peak resident set size in bytes, where the OS reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_rss: Option<u64>,
+}
+
+/// One line of a session log: the stats of a run, with when it finished and
+/// the rest of the shape it proved.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    /// `Backend::name`.
+    pub backend: String,
+    /// `Layout::name`, `Recurrence::name` and `OutputMode::name`.
+    pub layout: String,
+    pub recurrence: String,
+    pub output: String,
+    #[serde(flatten)]
+    pub stats: ProveStats,
+}
+
+impl Run {
+    /// A run of `spec` on `backend` that finished now.
+    pub fn now(backend: Backend, spec: &FiboSpec, stats: ProveStats) -> Self {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+        Run {
+            time,
+            backend: backend.name().to_string(),
+            layout: spec.layout.name().to_string(),
+            recurrence: spec.recurrence.name().to_string(),
+            output: spec.output.name().to_string(),
+            stats,
+        }
+    }
+
+    fn shape(&self) -> Shape {
+        Shape {
+            backend: self.backend.clone(),
+            k: self.stats.k,
+            num: self.stats.num,
+            layout: self.layout.clone(),
+            recurrence: self.recurrence.clone(),
+            output: self.output.clone(),
+        }
+    }
+}
+
+/// Appends `run` to the log at `path`, creating it if need be. The line is
+/// written under a lock on the file, so runs sharing a log never interleave.
+pub fn append(path: &Path, run: &Run) -> io::Result<()> {
+    let mut line = serde_json::to_string(run)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.lock()?;
+    file.write_all(line.as_bytes())
+}
+
+/// Every run in the log at `path`, oldest first. Blank lines are skipped;
+/// any other line that is not a `Run` is `InvalidData`, naming it.
+pub fn read(path: &Path) -> io::Result<Vec<Run>> {
+    let mut runs = vec![];
+    for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let run = serde_json::from_str(line).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{} line {}: {}", path.display(), i + 1, err))
+        })?;
+        runs.push(run);
+    }
+    Ok(runs)
+}
+
+/// What runs are summarized together by: whatever decides the work done.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Shape {
+    pub backend: String,
+    pub k: u32,
+    pub num: usize,
+    pub layout: String,
+    pub recurrence: String,
+    pub output: String,
+}
+
+/// The smallest, mean and largest of a measurement over some runs.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Spread {
+    pub min: f64,
+    pub mean: f64,
+    pub max: f64,
+}
+
+impl Spread {
+    fn of(values: impl Iterator<Item = f64>) -> Self {
+        let (mut min, mut max, mut sum, mut n) = (f64::INFINITY, f64::NEG_INFINITY, 0.0, 0);
+        for value in values {
+            min = min.min(value);
+            max = max.max(value);
+            sum += value;
+            n += 1;
+        }
+        Spread { min, mean: sum / n as f64, max }
+    }
+}
+
+/// The runs of one shape: how many, over what time, and how long they took.
+/// `last_prove_secs` against the spread shows which way the latest went.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Summary {
+    #[serde(flatten)]
+    pub shape: Shape,
+    pub runs: usize,
+    /// `Run::time` of the first and the last run.
+    pub first: u64,
+    pub last: u64,
+    pub keygen_secs: Spread,
+    pub prove_secs: Spread,
+    pub last_prove_secs: f64,
+    /// The largest proof, which is the same size for every run of a shape.
+    pub proof_bytes: usize,
+    /// The highest peak, of the runs that report one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_rss: Option<u64>,
+}
+
+/// One `Summary` per shape among `runs`, ordered by shape, each over its
+/// runs in the order given.
+pub fn summarize(runs: &[Run]) -> Vec<Summary> {
+    let mut shapes: BTreeMap<Shape, Vec<&Run>> = BTreeMap::new();
+    for run in runs {
+        shapes.entry(run.shape()).or_default().push(run);
+    }
+    shapes
+        .into_iter()
+        .map(|(shape, runs)| Summary {
+            shape,
+            runs: runs.len(),
+            first: runs.iter().map(|run| run.time).min().unwrap_or(0),
+            last: runs.iter().map(|run| run.time).max().unwrap_or(0),
+            keygen_secs: Spread::of(runs.iter().map(|run| run.stats.keygen_secs)),
+            prove_secs: Spread::of(runs.iter().map(|run| run.stats.prove_secs)),
+            last_prove_secs: runs[runs.len() - 1].stats.prove_secs,
+            proof_bytes: runs.iter().map(|run| run.stats.proof_bytes).max().unwrap_or(0),
+            peak_rss: runs.iter().filter_map(|run| run.stats.peak_rss).max(),
+        })
+        .collect()
+}
@@ -0,0 +1,178 @@
+//! A seven-element prime field, small enough to enumerate every assignment
+//! of the step gate with `MockProver`.
+
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use halo2_proofs::arithmetic::Field;
+#[cfg(feature = "halo2-0-3")]
+use rand_core::RngCore;
+#[cfg(feature = "halo2-0-4")]
+use rand_core_0_10::{Rng, TryRng};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, CtOption};
+
+const MODULUS: u8 = 7;
+
+/// A fixed non-square, used by `sqrt_ratio` for non-square ratios.
+const NON_SQUARE: F7 = F7(3);
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct F7(u8);
+
+// Hex like the curve fields, which is what MockProver expects when it
+// formats failing cells.
+impl fmt::Debug for F7 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:02x}", self.0)
+    }
+}
+
+impl F7 {
+    /// Every element of the field, in order.
+    pub fn elements() -> impl Iterator<Item = Self> {
+        (0..MODULUS).map(F7)
+    }
+}
+
+impl ConditionallySelectable for F7 {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        F7(u8::conditional_select(&a.0, &b.0, choice))
+    }
+}
+
+impl ConstantTimeEq for F7 {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl Neg for F7 {
+    type Output = F7;
+
+    fn neg(self) -> F7 {
+        F7((MODULUS - self.0) % MODULUS)
+    }
+}
+
+fn add_mod(a: u8, b: u8) -> u8 {
+    (a + b) % MODULUS
+}
+
+fn sub_mod(a: u8, b: u8) -> u8 {
+    (a + MODULUS - b) % MODULUS
+}
+
+fn mul_mod(a: u8, b: u8) -> u8 {
+    (a * b) % MODULUS
+}
+
+macro_rules! impl_binop {
+    ($trait:ident, $fn:ident, $assign_trait:ident, $assign_fn:ident, $op:ident) => {
+        impl $trait for F7 {
+            type Output = F7;
+
+            fn $fn(self, rhs: F7) -> F7 {
+                F7($op(self.0, rhs.0))
+            }
+        }
+
+        impl<'a> $trait<&'a F7> for F7 {
+            type Output = F7;
+
+            fn $fn(self, rhs: &'a F7) -> F7 {
+                self.$fn(*rhs)
+            }
+        }
+
+        impl $assign_trait for F7 {
+            fn $assign_fn(&mut self, rhs: F7) {
+                *self = self.$fn(rhs);
+            }
+        }
+
+        impl<'a> $assign_trait<&'a F7> for F7 {
+            fn $assign_fn(&mut self, rhs: &'a F7) {
+                *self = self.$fn(*rhs);
+            }
+        }
+    };
+}
+
+impl_binop!(Add, add, AddAssign, add_assign, add_mod);
+impl_binop!(Sub, sub, SubAssign, sub_assign, sub_mod);
+impl_binop!(Mul, mul, MulAssign, mul_assign, mul_mod);
+
+impl Sum for F7 {
+    fn sum<I: Iterator<Item = F7>>(iter: I) -> F7 {
+        iter.fold(F7::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a F7> for F7 {
+    fn sum<I: Iterator<Item = &'a F7>>(iter: I) -> F7 {
+        iter.fold(F7::ZERO, Add::add)
+    }
+}
+
+impl Product for F7 {
+    fn product<I: Iterator<Item = F7>>(iter: I) -> F7 {
+        iter.fold(F7::ONE, Mul::mul)
+    }
+}
+
+impl<'a> Product<&'a F7> for F7 {
+    fn product<I: Iterator<Item = &'a F7>>(iter: I) -> F7 {
+        iter.fold(F7::ONE, Mul::mul)
+    }
+}
+
+impl Field for F7 {
+    const ZERO: Self = F7(0);
+    const ONE: Self = F7(1);
+
+    #[cfg(feature = "halo2-0-3")]
+    fn random(mut rng: impl RngCore) -> Self {
+        F7((rng.next_u32() % u32::from(MODULUS)) as u8)
+    }
+
+    #[cfg(feature = "halo2-0-4")]
+    fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        F7((rng.next_u32() % u32::from(MODULUS)) as u8)
+    }
+
+    #[cfg(feature = "halo2-0-4")]
+    fn try_random<R: TryRng + ?Sized>(rng: &mut R) -> Result<Self, R::Error> {
+        Ok(F7((rng.try_next_u32()? % u32::from(MODULUS)) as u8))
+    }
+
+    fn square(&self) -> Self {
+        *self * self
+    }
+
+    fn double(&self) -> Self {
+        *self + self
+    }
+
+    fn invert(&self) -> CtOption<Self> {
+        CtOption::new(self.pow_vartime([u64::from(MODULUS) - 2]), !self.is_zero())
+    }
+
+    fn sqrt_ratio(num: &Self, div: &Self) -> (Choice, Self) {
+        let ratio = match Option::<F7>::from(div.invert()) {
+            Some(inv) => *num * inv,
+            None => return (num.is_zero(), F7::ZERO),
+        };
+        // With seven elements a search is simpler than any square-root algorithm.
+        let root = |x: F7| F7::elements().find(|r| r.square() == x);
+        match root(ratio) {
+            Some(r) => (Choice::from(1), r),
+            None => (Choice::from(0), root(ratio * NON_SQUARE).unwrap()),
+        }
+    }
+}
+
+// MockProver on 0.4 requires variable-time inversion; the default falls back
+// to `invert`, which is all a seven-element field needs.
+#[cfg(feature = "halo2-0-4")]
+impl halo2_proofs::arithmetic::VartimeField for F7 {}
@@ -0,0 +1,102 @@
+//! The four-column layout for huge `num`, computing each term as its row is
+//! assigned instead of from a precomputed witness. `FiboCircuit` holds the
+//! `num` values of its witness and the cell of every term it lays out;
+//! `FiboCircuitStreaming` holds the last row's three values and the six
+//! cells it exposes from, whatever `num`. The rows and constraints are the
+//! same, so it shares `FiboCircuit`'s keys and its proofs verify as
+//! `FiboCircuit`'s do.
+//!
+//! What remains is the backend's: a prover keeps every column, `2^k` values
+//! each, and `MockProver` the same and more, so memory still grows with
+//! `2^k`; this leaves nothing else that grows with `num`.
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+use crate::chip::{FiboChip, FiboConfig, Number, SequenceCells, SequenceChip};
+use crate::circuit::{synthesize_sequence, FiboCircuit, Layout};
+use crate::recurrence::Recurrence;
+
+/// `FiboChip` laying out `rows` rows of the variant from the first three
+/// terms alone.
+pub struct StreamingChip<F: Field> {
+    fibo: FiboChip<F>,
+    rows: usize,
+}
+
+impl<F: Field> StreamingChip<F> {
+    pub fn construct(config: FiboConfig, rows: usize) -> Self {
+        Self {
+            fibo: FiboChip::construct(config),
+            rows,
+        }
+    }
+}
+
+impl<F: Field> SequenceChip<F> for StreamingChip<F> {
+    /// Reads `a, b, c` from the start of `witness` and computes the rest,
+    /// which `witness` need not hold. The returned `terms` are empty.
+    fn load_sequence(
+        &self,
+        layouter: impl Layouter<F>,
+        witness: &[Value<F>],
+    ) -> Result<SequenceCells<F>, Error> {
+        let initial = match witness {
+            [a, b, c, ..] => [*a, *b, *c],
+            _ => return Err(Error::Synthesis),
+        };
+        self.fibo.load_sequence_streaming(layouter, initial, self.rows, |[a, b, c]| (a + c) * b)
+    }
+
+    fn load_constant(
+        &self,
+        layouter: impl Layouter<F>,
+        value: F,
+    ) -> Result<Number<F>, Error> {
+        self.fibo.load_constant(layouter, value)
+    }
+
+    fn expose_public<'a>(
+        &self,
+        layouter: impl Layouter<F>,
+        nums: impl IntoIterator<Item = &'a Number<F>>,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        F: 'a,
+    {
+        self.fibo.expose_public(layouter, nums, row)
+    }
+}
+
+/// `FiboCircuit` in the four-column layout, for the variant, streamed from
+/// its `a, b, c`; a precomputed `witness` is ignored, so leave it `None`.
+#[derive(Clone, Default)]
+pub struct FiboCircuitStreaming<F>(pub FiboCircuit<F>);
+
+impl<F: Field> Circuit<F> for FiboCircuitStreaming<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FiboChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        let spec = &self.0.spec;
+        if spec.layout != Layout::Rows || spec.recurrence != Recurrence::Variant {
+            return Err(Error::Synthesis);
+        }
+        let chip = StreamingChip::construct(config, spec.layout_terms() - 3);
+        synthesize_sequence(&chip, spec, &[self.0.a, self.0.b, self.0.c], layouter)
+    }
+}
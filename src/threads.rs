@@ -0,0 +1,47 @@
+//! How many threads witness generation and proving use. Both run on rayon
+//! with `multicore`: `witnesses`, `BatchFiboCircuit::with_witnesses` and
+//! halo2's own FFTs and multiexps. By default that is rayon's global pool,
+//! one thread per core; these pin it to fewer, e.g. on a shared machine.
+//! Without `multicore` everything is serial already and they only run `f`.
+
+use std::io;
+
+/// Runs `f` on a pool of its own with `threads` threads, so everything it
+/// proves or generates in parallel uses at most that many; zero is one per
+/// core. Threads `f` spawns itself are not in the pool and use the global
+/// one.
+#[cfg(feature = "multicore")]
+pub fn with_threads<R: Send>(threads: usize, f: impl FnOnce() -> R + Send) -> io::Result<R> {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().map_err(io::Error::other)?;
+    Ok(pool.install(f))
+}
+
+#[cfg(not(feature = "multicore"))]
+pub fn with_threads<R: Send>(_threads: usize, f: impl FnOnce() -> R + Send) -> io::Result<R> {
+    Ok(f())
+}
+
+/// Sizes the global pool, for every thread of the process, as `--threads`
+/// does. Fails once anything has used the global pool, which is then sized
+/// already.
+#[cfg(feature = "multicore")]
+pub fn set_threads(threads: usize) -> io::Result<()> {
+    rayon::ThreadPoolBuilder::new().num_threads(threads).build_global().map_err(io::Error::other)
+}
+
+#[cfg(not(feature = "multicore"))]
+pub fn set_threads(_threads: usize) -> io::Result<()> {
+    Ok(())
+}
+
+/// Threads parallel work on the calling thread would use: those of the
+/// pool `with_threads` runs it on, or of the global pool.
+#[cfg(feature = "multicore")]
+pub fn current_threads() -> usize {
+    rayon::current_num_threads()
+}
+
+#[cfg(not(feature = "multicore"))]
+pub fn current_threads() -> usize {
+    1
+}
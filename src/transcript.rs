@@ -0,0 +1,201 @@
+//! The Fiat-Shamir transcript a proof is made and checked with. halo2_proofs
+//! ships Blake2b, cheap natively but costly to check inside another circuit;
+//! the Poseidon transcript here hashes over the Pasta base field instead, so
+//! a recursive verifier can recompute the challenges in-circuit. Both write
+//! the same points and scalars; only the challenges differ, so a proof
+//! verifies under the transcript it was made with and no other.
+//!
+//! The Poseidon state is a single `Fq`, starting from the 16 bytes
+//! `Halo2-Transcript` read as a little-endian integer. Each point coordinate
+//! and scalar `x` it absorbs, and each challenge, replaces the state `s`
+//! with `Poseidon(s, tag, x)`: the tag is 1 for a point coordinate, 2 for a
+//! scalar, and 3 with `x = 0` for a challenge, whose bytes are the new state.
+//! Scalars embed since the scalar field is the smaller of the two.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength, P128Pow5T3};
+use halo2_proofs::arithmetic::{Coordinates, CurveAffine};
+use halo2_proofs::pasta::group::ff::{Field, PrimeField};
+use halo2_proofs::pasta::group::GroupEncoding;
+use halo2_proofs::pasta::{EqAffine, Fp, Fq};
+use halo2_proofs::transcript::{
+    Challenge255, EncodedChallenge, Transcript, TranscriptRead, TranscriptWrite,
+};
+
+/// Which transcript a proof is made with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TranscriptHash {
+    /// halo2's `Blake2bWrite` and `Blake2bRead`.
+    #[default]
+    Blake2b,
+    /// `PoseidonWrite` and `PoseidonRead`, for recursion.
+    Poseidon,
+}
+
+impl TranscriptHash {
+    /// The name `FromStr` accepts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TranscriptHash::Blake2b => "blake2b",
+            TranscriptHash::Poseidon => "poseidon",
+        }
+    }
+}
+
+impl fmt::Display for TranscriptHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptHash::Blake2b => write!(f, "a Blake2b transcript"),
+            TranscriptHash::Poseidon => write!(f, "a Poseidon transcript"),
+        }
+    }
+}
+
+impl FromStr for TranscriptHash {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake2b" => Ok(TranscriptHash::Blake2b),
+            "poseidon" => Ok(TranscriptHash::Poseidon),
+            _ => Err(format!("unknown transcript {:?}, expected \"blake2b\" or \"poseidon\"", s)),
+        }
+    }
+}
+
+type StepHash = poseidon::Hash<Fq, P128Pow5T3, ConstantLength<3>, 3, 2>;
+
+const TAG_POINT: u64 = 1;
+const TAG_SCALAR: u64 = 2;
+const TAG_CHALLENGE: u64 = 3;
+
+/// The hash chain both directions share.
+#[derive(Clone, Debug)]
+struct PoseidonState(Fq);
+
+impl PoseidonState {
+    fn new() -> Self {
+        Self(Fq::from_u128(u128::from_le_bytes(*b"Halo2-Transcript")))
+    }
+
+    fn absorb(&mut self, tag: u64, value: Fq) {
+        self.0 = StepHash::init().hash([self.0, Fq::from(tag), value]);
+    }
+
+    fn squeeze(&mut self) -> Challenge255<EqAffine> {
+        self.absorb(TAG_CHALLENGE, Fq::ZERO);
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(self.0.to_repr().as_ref());
+        Challenge255::new(&bytes)
+    }
+
+    fn absorb_point(&mut self, point: EqAffine) -> io::Result<()> {
+        let coords: Coordinates<EqAffine> = Option::from(point.coordinates()).ok_or_else(|| {
+            io::Error::other("cannot write points at infinity to the transcript")
+        })?;
+        self.absorb(TAG_POINT, *coords.x());
+        self.absorb(TAG_POINT, *coords.y());
+        Ok(())
+    }
+
+    fn absorb_scalar(&mut self, scalar: Fp) {
+        let embedded = Fq::from_repr(scalar.to_repr()).expect("Fp is smaller than Fq");
+        self.absorb(TAG_SCALAR, embedded);
+    }
+}
+
+/// A Poseidon transcript being written, as `Blake2bWrite` is.
+#[derive(Clone, Debug)]
+pub struct PoseidonWrite<W: Write> {
+    state: PoseidonState,
+    writer: W,
+}
+
+impl<W: Write> PoseidonWrite<W> {
+    pub fn init(writer: W) -> Self {
+        Self { state: PoseidonState::new(), writer }
+    }
+
+    /// The writer, holding the proof.
+    pub fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Transcript<EqAffine, Challenge255<EqAffine>> for PoseidonWrite<W> {
+    fn squeeze_challenge(&mut self) -> Challenge255<EqAffine> {
+        self.state.squeeze()
+    }
+
+    fn common_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.state.absorb_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.state.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl<W: Write> TranscriptWrite<EqAffine, Challenge255<EqAffine>> for PoseidonWrite<W> {
+    fn write_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(point.to_bytes().as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(scalar.to_repr().as_ref())
+    }
+}
+
+/// A Poseidon transcript being read, as `Blake2bRead` is.
+#[derive(Clone, Debug)]
+pub struct PoseidonRead<R: Read> {
+    state: PoseidonState,
+    reader: R,
+}
+
+impl<R: Read> PoseidonRead<R> {
+    pub fn init(reader: R) -> Self {
+        Self { state: PoseidonState::new(), reader }
+    }
+}
+
+impl<R: Read> Transcript<EqAffine, Challenge255<EqAffine>> for PoseidonRead<R> {
+    fn squeeze_challenge(&mut self) -> Challenge255<EqAffine> {
+        self.state.squeeze()
+    }
+
+    fn common_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.state.absorb_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.state.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read> TranscriptRead<EqAffine, Challenge255<EqAffine>> for PoseidonRead<R> {
+    fn read_point(&mut self) -> io::Result<EqAffine> {
+        let mut compressed = <EqAffine as GroupEncoding>::Repr::default();
+        self.reader.read_exact(compressed.as_mut())?;
+        let point: EqAffine = Option::from(EqAffine::from_bytes(&compressed))
+            .ok_or_else(|| io::Error::other("invalid point encoding in proof"))?;
+        self.common_point(point)?;
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<Fp> {
+        let mut data = <Fp as PrimeField>::Repr::default();
+        self.reader.read_exact(data.as_mut())?;
+        let scalar: Fp = Option::from(Fp::from_repr(data))
+            .ok_or_else(|| io::Error::other("invalid field element encoding in proof"))?;
+        self.common_scalar(scalar)?;
+        Ok(scalar)
+    }
+}
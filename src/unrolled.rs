@@ -0,0 +1,285 @@
+//! The unrolled layout: each row holds three consecutive terms and the gate
+//! checks the next row's three against the recurrence run `N` steps on from
+//! this row's, so a sequence of `num` terms takes about `(num - 3) / N` rows
+//! instead of `num - 3`.
+//!
+//! The price is degree. Written out in the first three terms, term `3 + j`
+//! has degree growing like the Fibonacci numbers, so the gate reaches degree
+//! 9 at `N = 4`; the prover's extended domain and the quotient commitments
+//! grow with it. `configure` refuses anything over `MAX_DEGREE`, and
+//! `FiboCircuitUnrolled` refuses such an `N` at compile time.
+//!
+//! When `N` does not divide `num - 3`, the last row advances by the
+//! remainder, under a gate of its own: there is one gate and selector for
+//! every step count from 1 to `N`.
+
+use std::marker::PhantomData;
+use halo2_proofs::circuit::{Value, Layouter, SimpleFloorPlanner};
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::{plonk::*};
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::pasta::Fp;
+
+use crate::chip::{Number, SequenceCells, SequenceChip};
+use crate::circuit::{synthesize_sequence, FiboCircuit, OutputMode};
+
+/// The highest gate degree `configure` accepts.
+pub const MAX_DEGREE: usize = 9;
+
+/// The degree of the gate advancing `factor` steps: the selector times the
+/// last term, whose degree is that of the term before it plus the one
+/// before that, or of the one three back if higher.
+pub const fn gate_degree(factor: usize) -> usize {
+    let mut terms = [1, 1, 1];
+    let mut j = 0;
+    while j < factor {
+        let [t0, t1, t2] = terms;
+        terms = [t1, t2, if t2 > t0 { t2 } else { t0 } + t1];
+        j += 1;
+    }
+    1 + terms[2]
+}
+
+#[derive(Clone, Debug)]
+pub struct UnrolledConfig {
+    /// Three consecutive terms per row.
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub i: Column<Instance>,
+    /// `steps[m - 1]`: the next row is `m` steps on from this one.
+    pub steps: Vec<Selector>,
+}
+
+pub struct UnrolledChip<F: Field> {
+    config: UnrolledConfig,
+    /// The most steps a row advances.
+    factor: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> UnrolledChip<F> {
+    pub fn construct(config: UnrolledConfig) -> Self {
+        Self {
+            factor: config.steps.len(),
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Configures gates advancing by 1 up to `factor` steps.
+    ///
+    /// # Panics
+    ///
+    /// If `factor` is zero, or the gates' degree exceeds `MAX_DEGREE`.
+    pub fn configure(meta: &mut ConstraintSystem<F>, factor: usize) -> UnrolledConfig {
+        assert!(factor >= 1, "a row must advance at least one step");
+        let _span = tracing::info_span!("configure", factor).entered();
+        let [a, b, c] = [(); 3].map(|_| meta.advice_column());
+        let i = meta.instance_column();
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+        for column in [a, b, c] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(i);
+
+        let steps = (1..=factor)
+            .map(|m| {
+                let s = meta.selector();
+                meta.create_gate("unrolled step", |meta| {
+                    let s = meta.query_selector(s);
+                    let mut terms = vec![
+                        meta.query_advice(a, Rotation::cur()),
+                        meta.query_advice(b, Rotation::cur()),
+                        meta.query_advice(c, Rotation::cur()),
+                    ];
+                    for j in 0..m {
+                        let next = (terms[j + 2].clone() + terms[j].clone()) * terms[j + 1].clone();
+                        terms.push(next);
+                    }
+                    let next = [a, b, c].map(|column| meta.query_advice(column, Rotation::next()));
+                    next.into_iter()
+                        .zip(terms.split_off(m))
+                        .map(|(next, term)| s.clone() * (next - term))
+                        .collect::<Vec<_>>()
+                });
+                s
+            })
+            .collect();
+
+        assert!(
+            meta.degree() <= MAX_DEGREE,
+            "unrolling {} steps per row needs degree {}, over the maximum of {}",
+            factor,
+            meta.degree(),
+            MAX_DEGREE,
+        );
+        UnrolledConfig { a, b, c, i, steps }
+    }
+
+    /// First term of the window in each row, the last window ending on the
+    /// last term.
+    fn window_starts(&self, num: usize) -> Vec<usize> {
+        let last = num - 3;
+        (0..)
+            .map(|r| r * self.factor)
+            .take_while(|&start| start < last + self.factor)
+            .map(|start| start.min(last))
+            .collect()
+    }
+}
+
+impl<F: Field> SequenceChip<F> for UnrolledChip<F> {
+    /// Lays out all of `witness`, at least four terms, three per row in a
+    /// single region.
+    fn load_sequence(
+        &self,
+        mut layouter: impl Layouter<F>,
+        witness: &[Value<F>],
+    ) -> Result<SequenceCells<F>, Error> {
+        if witness.len() < 4 {
+            return Err(Error::Synthesis);
+        }
+        let starts = self.window_starts(witness.len());
+        let config = &self.config;
+        layouter.assign_region(
+            || "unrolled",
+            |mut region| {
+                let mut rows = vec![];
+                let mut terms = vec![];
+                for (row, &start) in starts.iter().enumerate() {
+                    if let Some(&next) = starts.get(row + 1) {
+                        config.steps[next - start - 1].enable(&mut region, row)?;
+                    }
+                    let mut cells = vec![];
+                    for (j, column) in [config.a, config.b, config.c].into_iter().enumerate() {
+                        let term = start + j;
+                        let cell = Number(region.assign_advice(
+                            || format!("term {}", term),
+                            column,
+                            row,
+                            || witness[term],
+                        )?);
+                        // The last window may overlap the one before it.
+                        if term == terms.len() {
+                            terms.push(cell.clone());
+                        }
+                        cells.push(cell);
+                    }
+                    rows.push(cells);
+                }
+                let window = |cells: &[Number<F>]| [0, 1, 2].map(|j| cells[j].clone());
+                Ok(SequenceCells {
+                    initial: window(&rows[0]),
+                    last: window(&rows[rows.len() - 1]),
+                    terms,
+                })
+            },
+        )
+    }
+
+    /// Assigns `value` fixed by the verifying key rather than by the prover,
+    /// in a row of its own below the sequence.
+    fn load_constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: F,
+    ) -> Result<Number<F>, Error> {
+        layouter.assign_region(
+            || "constant",
+            |mut region| {
+                region
+                    .assign_advice_from_constant(|| "constant", self.config.a, 0, value)
+                    .map(Number)
+            },
+        )
+    }
+
+    fn expose_public<'a>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        nums: impl IntoIterator<Item = &'a Number<F>>,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        F: 'a,
+    {
+        for (i, num) in nums.into_iter().enumerate() {
+            layouter.constrain_instance(num.0.cell(), self.config.i, row + i)?;
+        }
+        Ok(())
+    }
+}
+
+/// `FiboCircuit` advancing `N` steps per row. The unrolled layout is chosen
+/// by the type, so the spec's `layout` is not consulted, and its `rows` and
+/// `min_k` do not apply: use the ones here. An `N` of zero, or one whose
+/// gate is past `MAX_DEGREE`, fails to compile.
+#[derive(Clone, Default)]
+pub struct FiboCircuitUnrolled<F, const N: usize>(pub FiboCircuit<F>);
+
+impl<F: Field, const N: usize> FiboCircuitUnrolled<F, N> {
+    const VALID_N: () = assert!(
+        N >= 1 && gate_degree(N) <= MAX_DEGREE,
+        "a row must advance at least one step, and at most as many as MAX_DEGREE allows"
+    );
+
+    /// Rows the layout occupies: one per window of three terms, plus one
+    /// for the term count of a continuation.
+    pub fn rows(&self) -> usize {
+        let count = match self.0.spec.output {
+            // Hashes are refused by `synthesize`, so take no rows here.
+            OutputMode::Result
+            | OutputMode::InitialAndResult
+            | OutputMode::Chunk
+            | OutputMode::Hashed
+            | OutputMode::Committed
+            | OutputMode::MerkleRoot => 0,
+            OutputMode::Continuation => 1,
+        };
+        1 + (self.0.spec.layout_terms() - 3).div_ceil(N) + count
+    }
+
+    /// The degree of the constraint system, which sets the size of the
+    /// prover's extended domain.
+    pub fn degree() -> usize {
+        let () = Self::VALID_N;
+        let mut cs = ConstraintSystem::<Fp>::default();
+        UnrolledChip::configure(&mut cs, N);
+        cs.degree()
+    }
+
+    /// The smallest `k` whose `2^k` rows fit the layout and the rows halo2
+    /// reserves for blinding.
+    pub fn min_k(&self) -> u32 {
+        let () = Self::VALID_N;
+        let mut cs = ConstraintSystem::<Fp>::default();
+        UnrolledChip::configure(&mut cs, N);
+        let required = (self.rows() + cs.blinding_factors() + 1).max(cs.minimum_rows());
+        required.next_power_of_two().trailing_zeros()
+    }
+}
+
+impl<F: Field, const N: usize> Circuit<F> for FiboCircuitUnrolled<F, N> {
+    type Config = UnrolledConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let () = Self::VALID_N;
+        UnrolledChip::configure(meta, N)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        let chip = UnrolledChip::construct(config);
+        synthesize_sequence(&chip, &self.0.spec, &self.0.full_witness(), layouter)
+    }
+}
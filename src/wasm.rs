@@ -0,0 +1,65 @@
+//! Proving and verifying in the browser, behind the `wasm` feature. Nothing
+//! here touches a filesystem or spawns threads, so both run in a web worker;
+//! the caller hands in the IPA parameters, which are too slow to generate
+//! there for any useful `k`. halo2_proofs 0.3 cannot serialize a verifying
+//! key, so as in `Proof::verify` the key is regenerated from the shape the
+//! proof carries.
+//!
+//! Build without `multicore`, e.g. with wasm-pack:
+//! `wasm-pack build --target web -- --no-default-features --features halo2-0-3,wasm`.
+
+use wasm_bindgen::prelude::*;
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::poly::commitment::Params;
+
+use crate::circuit::{FiboCircuit, FiboSpec};
+use crate::fingerprint::VkHash;
+use crate::parse::parse_field;
+use crate::proof::Proof;
+use crate::prover;
+use crate::sequence::witness;
+
+/// Proves the `num` terms of the variant from `a, b, c`, decimal or `0x`
+/// hex, exposing the last; `num` must be at least one. Returns the proof
+/// in `Proof`'s binary encoding, for `verify`. The proof's `k` is that of
+/// `params`, which must be at least `min_k_for(num)`; see `verify` for
+/// their encoding.
+#[wasm_bindgen]
+pub fn prove(a: &str, b: &str, c: &str, num: usize, params: &[u8]) -> Result<Vec<u8>, JsError> {
+    let [a, b, c] = [a, b, c].map(parse_field::<Fp>);
+    let (a, b, c) = (a?, b?, c?);
+    let params = Params::<EqAffine>::read(&mut &params[..])?;
+    let k = params.k();
+    let spec = FiboSpec { num, ..FiboSpec::default() };
+    spec.check_num()?;
+    spec.check_k(k)?;
+    let terms = witness(a, b, c, spec.layout_terms());
+    let instance = vec![terms[num - 1]];
+    let circuit = FiboCircuit { spec, ..FiboCircuit::from_witness(terms.into_iter().map(Value::known).collect()) };
+    let pk = prover::keygen(&params, &circuit)?;
+    let bytes = prover::prove(&params, &pk, &circuit, &instance)?;
+    let vk_hash = VkHash::of(pk.get_vk());
+    Ok(Proof::new(k, circuit.spec, instance, bytes, vk_hash).to_bytes())
+}
+
+/// Checks `proof`, in `Proof`'s binary encoding, against `public_inputs`,
+/// decimal or `0x` hex, or against the inputs bundled in the proof if
+/// `public_inputs` is empty. `params` are the IPA parameters for the
+/// proof's `k`, as `Params::write` writes them, e.g. a `params-<k>.bin`
+/// from the CLI's cache.
+#[wasm_bindgen]
+pub fn verify(proof: &[u8], public_inputs: Vec<String>, params: &[u8]) -> Result<(), JsError> {
+    let mut proof = Proof::from_bytes(proof)?;
+    if !public_inputs.is_empty() {
+        proof.instance = public_inputs
+            .iter()
+            .map(|value| parse_field::<Fp>(value))
+            .collect::<Result<_, _>>()?;
+    }
+    proof.check_shape()?;
+    let params = Params::<EqAffine>::read(&mut &params[..])?;
+    proof.verify(&params)?;
+    Ok(())
+}
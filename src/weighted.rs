@@ -0,0 +1,171 @@
+//! The weighted family `d = (α·a + γ·c) · (β·b)`, one circuit per choice of
+//! coefficients. The coefficients sit in fixed columns next to every row,
+//! so they are part of the verifying key: a proof for one choice does not
+//! verify under the key for another. All three equal to one is the variant.
+
+use std::borrow::Cow;
+use halo2_proofs::circuit::{Value, Layouter, SimpleFloorPlanner};
+use halo2_proofs::{plonk::*};
+use halo2_proofs::arithmetic::Field;
+
+use crate::chip::{FiboChip, FiboConfig, Number, SequenceCells, SequenceChip};
+use crate::circuit::{synthesize_sequence, FiboCircuit, Layout};
+use crate::sequence::terms_with;
+
+pub const WEIGHTED_GATE_NAME: &str = "weighted gate";
+
+/// `α`, `β` and `γ` of `d = (α·a + γ·c) · (β·b)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Coefficients<F> {
+    pub alpha: F,
+    pub beta: F,
+    pub gamma: F,
+}
+
+impl<F: Field> Default for Coefficients<F> {
+    /// All ones: the variant.
+    fn default() -> Self {
+        Self {
+            alpha: F::ONE,
+            beta: F::ONE,
+            gamma: F::ONE,
+        }
+    }
+}
+
+impl<F: Field> Coefficients<F> {
+    pub fn next(&self, a: F, b: F, c: F) -> F {
+        (self.alpha * a + self.gamma * c) * (self.beta * b)
+    }
+
+    /// The first `num` terms from `a, b, c`.
+    pub fn witness(&self, a: Value<F>, b: Value<F>, c: Value<F>, num: usize) -> Vec<Value<F>> {
+        terms_with(a, b, c, num, |a, b, c| a.zip(b).zip(c).map(|((a, b), c)| self.next(a, b, c)))
+    }
+}
+
+#[derive(Clone, Debug, Copy)]
+pub struct WeightedConfig {
+    pub fibo: FiboConfig,
+    pub alpha: Column<Fixed>,
+    pub beta: Column<Fixed>,
+    pub gamma: Column<Fixed>,
+}
+
+impl WeightedConfig {
+    pub fn configure<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+        let [alpha, beta, gamma] = [(); 3].map(|_| meta.fixed_column());
+        let fibo = FiboChip::configure_step_with(
+            meta,
+            advice,
+            instance,
+            WEIGHTED_GATE_NAME,
+            |meta, [a, b, c]| {
+                let alpha = meta.query_fixed(alpha);
+                let beta = meta.query_fixed(beta);
+                let gamma = meta.query_fixed(gamma);
+                (alpha * a + gamma * c) * (beta * b)
+            },
+        );
+        Self { fibo, alpha, beta, gamma }
+    }
+}
+
+/// `FiboChip` with the coefficients filled in on every row it lays out.
+pub struct WeightedChip<F: Field> {
+    fibo: FiboChip<F>,
+    config: WeightedConfig,
+    coefficients: Coefficients<F>,
+}
+
+impl<F: Field> WeightedChip<F> {
+    pub fn construct(config: WeightedConfig, coefficients: Coefficients<F>) -> Self {
+        Self {
+            fibo: FiboChip::construct(config.fibo),
+            config,
+            coefficients,
+        }
+    }
+}
+
+impl<F: Field> SequenceChip<F> for WeightedChip<F> {
+    fn load_sequence(
+        &self,
+        layouter: impl Layouter<F>,
+        witness: &[Value<F>],
+    ) -> Result<SequenceCells<F>, Error> {
+        let Coefficients { alpha, beta, gamma } = self.coefficients;
+        self.fibo.load_sequence_with(layouter, witness, |region, row| {
+            region.assign_fixed(|| "alpha", self.config.alpha, row, || Value::known(alpha))?;
+            region.assign_fixed(|| "beta", self.config.beta, row, || Value::known(beta))?;
+            region.assign_fixed(|| "gamma", self.config.gamma, row, || Value::known(gamma))?;
+            Ok(())
+        })
+    }
+
+    fn load_constant(
+        &self,
+        layouter: impl Layouter<F>,
+        value: F,
+    ) -> Result<Number<F>, Error> {
+        self.fibo.load_constant(layouter, value)
+    }
+
+    fn expose_public<'a>(
+        &self,
+        layouter: impl Layouter<F>,
+        nums: impl IntoIterator<Item = &'a Number<F>>,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        F: 'a,
+    {
+        self.fibo.expose_public(layouter, nums, row)
+    }
+}
+
+/// `FiboCircuit` for the weighted recurrence. The coefficients are shape,
+/// not witness: `without_witnesses` keeps them, and keys are per choice.
+/// The spec's `recurrence` is not consulted.
+#[derive(Clone)]
+pub struct WeightedCircuit<F> {
+    pub coefficients: Coefficients<F>,
+    pub circuit: FiboCircuit<F>,
+}
+
+impl<F: Field> Circuit<F> for WeightedCircuit<F> {
+    type Config = WeightedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            coefficients: self.coefficients,
+            circuit: self.circuit.without_witnesses(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        WeightedConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        layouter: impl Layouter<F>
+    ) -> Result<(), Error> {
+        let circuit = &self.circuit;
+        if circuit.spec.layout != Layout::Rows {
+            return Err(Error::Synthesis);
+        }
+        let witness = match &circuit.witness {
+            Some(witness) => Cow::Borrowed(witness),
+            None => Cow::Owned(self.coefficients.witness(circuit.a, circuit.b, circuit.c, circuit.spec.layout_terms())),
+        };
+        let chip = WeightedChip::construct(config, self.coefficients);
+        synthesize_sequence(&chip, &circuit.spec, &witness, layouter)
+    }
+}
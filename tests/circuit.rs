@@ -0,0 +1,288 @@
+//! The plain circuit under MockProver: the statement the sequence proves,
+//! its edge cases, and the exact failure each wrong claim produces.
+
+// Build against halo2_proofs 0.4 under the usual crate name; see `compat`.
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_proofs_0_4 as halo2_proofs;
+
+mod common;
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::dev::{FailureLocation, MockProver, VerifyFailure};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::Any;
+
+use fibonacci_variant::hash_chain::{hash_chain, HashChainCircuit};
+use fibonacci_variant::parse::{parse_field, parse_value, ParseFieldError};
+use fibonacci_variant::sequence::{continue_from, get_fibovar_seq, Continuation};
+use fibonacci_variant::small_field::F7;
+use fibonacci_variant::circuit::MAX_K;
+use fibonacci_variant::{min_k_for, witness, FibError, FiboCircuit, FiboCircuitFixed, FiboSpec, OutputMode};
+
+use common::{circuit, from_a23, terms, K, NUM};
+
+/// What MockProver reports when the result cell, the last term at `offset`
+/// of the sequence region, differs from instance row 0.
+fn result_mismatch(offset: usize) -> Vec<VerifyFailure> {
+    cell_mismatch(3, offset)
+}
+
+/// The same for a result in advice `column` of the sequence region.
+fn cell_mismatch(column: usize, offset: usize) -> Vec<VerifyFailure> {
+    vec![
+        VerifyFailure::Permutation {
+            column: (Any::Advice, column).into(),
+            location: FailureLocation::InRegion { region: (0, "sequence").into(), offset },
+        },
+        VerifyFailure::Permutation {
+            column: (Any::Instance, 0).into(),
+            location: FailureLocation::OutsideRegion { row: 0 },
+        },
+    ]
+}
+
+#[test]
+fn field_native_sequence_matches_u64() {
+    let seq = get_fibovar_seq(1, 2, 3, NUM);
+    let terms = terms();
+    assert_eq!(terms, seq.iter().map(|&x| Fp::from(x)).collect::<Vec<_>>());
+    let longer = witness(Fp::from(1), Fp::from(2), Fp::from(3), 2 * NUM);
+    assert_eq!(longer[..NUM], terms[..]);
+    let prover = MockProver::run(K, &from_a23(1, 2 * NUM, OutputMode::Result), vec![vec![longer[2 * NUM - 1]]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn correct_data() {
+    let prover = MockProver::run(K, &from_a23(1, NUM, OutputMode::Result), vec![vec![terms()[NUM - 1]]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn wrong_public_input() {
+    let prover = MockProver::run(K, &from_a23(1, NUM, OutputMode::Result), vec![vec![Fp::from(9999)]]).unwrap();
+    assert_eq!(prover.verify(), Err(result_mismatch(NUM - 4)));
+}
+
+#[test]
+fn wrong_instance_row() {
+    // The right result, one row too low: row 0 reads as zero.
+    let instance = vec![Fp::from(0), terms()[NUM - 1]];
+    let prover = MockProver::run(K, &from_a23(1, NUM, OutputMode::Result), vec![instance]).unwrap();
+    assert_eq!(prover.verify(), Err(result_mismatch(NUM - 4)));
+}
+
+#[test]
+fn minimum_num() {
+    let num = 4;
+    let result = witness(Fp::from(1), Fp::from(2), Fp::from(3), num)[num - 1];
+    assert_eq!(result, Fp::from(8));
+    let k = min_k_for(num);
+    let prover = MockProver::run(k, &from_a23(1, num, OutputMode::Result), vec![vec![result]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(k, &from_a23(1, num, OutputMode::Result), vec![vec![result + Fp::from(1)]]).unwrap();
+    assert_eq!(prover.verify(), Err(result_mismatch(0)));
+}
+
+// Fewer than four terms end on an initial value, laid out in the first row.
+#[test]
+fn short_sequences_expose_an_initial_value() {
+    let initial = [1, 2, 3].map(Fp::from);
+    for num in 1..4 {
+        assert_eq!(witness(initial[0], initial[1], initial[2], num), initial[..num]);
+        assert_eq!(get_fibovar_seq(1, 2, 3, num), [1, 2, 3][..num]);
+        let circuit = from_a23(1, num, OutputMode::Result);
+        assert_eq!(circuit.spec.check_num().map_err(|err| err.to_string()), Ok(()));
+        let k = min_k_for(num);
+        let prover = MockProver::run(k, &circuit, vec![vec![initial[num - 1]]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(8)]]).unwrap();
+        assert_eq!(prover.verify(), Err(cell_mismatch(num - 1, 0)));
+        let public = from_a23(1, num, OutputMode::InitialAndResult);
+        let instance = initial.into_iter().chain([initial[num - 1]]).collect();
+        let prover = MockProver::run(public.spec.min_k(), &public, vec![instance]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+    assert_eq!(get_fibovar_seq(1, 2, 3, 0), Vec::<u64>::new());
+    // too short for the output: refused at synthesis, not an index panic.
+    assert!(MockProver::run(K, &from_a23(1, 0, OutputMode::Result), vec![vec![]]).is_err());
+    assert!(MockProver::run(K, &from_a23(1, 3, OutputMode::Continuation), vec![vec![]]).is_err());
+}
+
+#[test]
+fn zero_initial_values() {
+    let spec = FiboSpec { num: NUM, ..FiboSpec::default() };
+    let zeros = circuit(spec, [Fp::from(0); 3]);
+    let prover = MockProver::run(K, &zeros, vec![vec![Fp::from(0)]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(K, &zeros, vec![vec![Fp::from(1)]]).unwrap();
+    assert_eq!(prover.verify(), Err(result_mismatch(NUM - 4)));
+}
+
+#[test]
+fn maximum_field_value_inputs() {
+    let max = -Fp::from(1);
+    let result = witness(max, max, max, NUM)[NUM - 1];
+    // (-1 + -1) * -1 = 2, so the terms wrap back to small values.
+    assert_eq!(witness(max, max, max, 4)[3], Fp::from(2));
+    let spec = FiboSpec { num: NUM, ..FiboSpec::default() };
+    let prover = MockProver::run(K, &circuit(spec, [max; 3]), vec![vec![result]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(K, &circuit(spec, [max; 3]), vec![vec![result + Fp::from(1)]]).unwrap();
+    assert_eq!(prover.verify(), Err(result_mismatch(NUM - 4)));
+}
+
+// The same statement over the secp256k1 scalar field.
+#[cfg(feature = "secp256k1")]
+#[test]
+fn correct_data_over_secp256k1() {
+    use halo2curves::secp256k1::Fq;
+
+    let circuit = FiboCircuit {
+        spec: FiboSpec { num: NUM, ..FiboSpec::default() },
+        a: Value::known(Fq::from(1)),
+        b: Value::known(Fq::from(2)),
+        c: Value::known(Fq::from(3)),
+        witness: None,
+    };
+    let res = witness(Fq::from(1), Fq::from(2), Fq::from(3), NUM)[NUM - 1];
+    let prover = MockProver::run(K, &circuit, vec![vec![res]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+// Initial values from strings, exactly as wide as the field.
+#[test]
+fn parsing_field_elements() {
+    let res = terms()[NUM - 1];
+    assert_eq!(parse_field::<Fp>("8160"), Ok(Fp::from(8160)));
+    assert_eq!(parse_field::<Fp>("0x1FE0"), Ok(Fp::from(8160)));
+    assert_eq!(parse_field::<Fp>(&format!("{:?}", res)), Ok(res));
+    let minus_one = format!("{:?}", -Fp::from(1));
+    assert_eq!(parse_field::<Fp>(&minus_one), Ok(-Fp::from(1)));
+    let modulus = "28948022309329048855892746252171976963363056481941560715954676764349967630337";
+    assert_eq!(parse_field::<Fp>(modulus), Err(ParseFieldError::OutOfRange));
+    assert_eq!(parse_field::<Fp>(&format!("0x1{}", "0".repeat(64))), Err(ParseFieldError::OutOfRange));
+    assert_eq!(parse_field::<Fp>("12a"), Err(ParseFieldError::InvalidDigit('a')));
+    assert_eq!(parse_field::<Fp>("0x"), Err(ParseFieldError::Empty));
+    let circuit_from_strings = FiboCircuit {
+        spec: FiboSpec { num: NUM, ..FiboSpec::default() },
+        a: parse_value("1").unwrap(),
+        b: parse_value("0x2").unwrap(),
+        c: parse_value("3").unwrap(),
+        witness: None,
+    };
+    let prover = MockProver::run(K, &circuit_from_strings, vec![vec![res]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+// The smallest k is enough, and one less is not.
+#[test]
+fn minimal_k() {
+    assert_eq!(min_k_for(NUM), 4);
+    for n in [4, NUM, 100, 250] {
+        let circuit = from_a23(1, n, OutputMode::Result);
+        let spec = circuit.spec;
+        let min_k = min_k_for(n);
+        let result = witness(Fp::from(1), Fp::from(2), Fp::from(3), n)[n - 1];
+        let prover = MockProver::run(min_k, &circuit, vec![vec![result]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        assert!(spec.check_k(min_k).is_ok());
+        assert!(MockProver::run(min_k - 1, &circuit, vec![vec![result]]).is_err());
+        assert!(matches!(spec.check_k(min_k - 1), Err(FibError::KTooSmall(err)) if err.min_k == min_k));
+    }
+    let spec = FiboSpec::default();
+    assert!(spec.check_k(MAX_K).is_ok());
+    for k in [MAX_K + 1, 40, 63, 64, u32::MAX] {
+        assert!(matches!(spec.check_k(k), Err(FibError::KTooLarge { max: MAX_K, .. })));
+    }
+}
+
+#[test]
+fn precomputed_witness() {
+    let terms = terms();
+    let circuit = FiboCircuit::from_witness(terms.iter().copied().map(Value::known).collect());
+    let prover = MockProver::run(K, &circuit, vec![vec![terms[NUM - 1]]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    // shorter than the four terms the layout takes, down to one.
+    for num in 1..=3 {
+        let circuit = FiboCircuit::from_witness(terms[..num].iter().copied().map(Value::known).collect());
+        assert_eq!(circuit.spec.num, num);
+        let prover = MockProver::run(K, &circuit, vec![vec![terms[num - 1]]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(K, &circuit, vec![vec![terms[num - 1] + Fp::from(1)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+    assert!(MockProver::run(K, &FiboCircuit::<Fp>::from_witness(vec![]), vec![vec![]]).is_err());
+}
+
+// Length fixed by the type.
+#[test]
+fn fixed_length_circuit() {
+    let circuit = FiboCircuitFixed::<_, NUM>::new(
+        Value::known(Fp::from(1)),
+        Value::known(Fp::from(2)),
+        Value::known(Fp::from(3)),
+    );
+    let prover = MockProver::run(K, &circuit, vec![vec![terms()[NUM - 1]]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+// The same number of steps as a chain of Poseidon hashes.
+#[test]
+fn poseidon_hash_chain_baseline() {
+    let baseline = HashChainCircuit {
+        seed: Value::known(Fp::from(1)),
+        num: NUM,
+    };
+    let prover = MockProver::run(11, &baseline, vec![vec![hash_chain(Fp::from(1), NUM)]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+// Hand the last state of one proof to the next.
+#[test]
+fn continuation_statement() {
+    let terms = terms();
+    let circuit = from_a23(1, NUM, OutputMode::Continuation);
+    let outputs = Continuation {
+        last: [terms[NUM - 3], terms[NUM - 2], terms[NUM - 1]],
+        num: NUM,
+    };
+    let prover = MockProver::run(K, &circuit, vec![outputs.instance()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let wrong_count = Continuation { num: NUM + 1, ..outputs };
+    let prover = MockProver::run(K, &circuit, vec![wrong_count.instance()]).unwrap();
+    assert!(prover.verify().is_err());
+    let longer = from_a23(1, NUM + 2, OutputMode::Continuation);
+    let prover = MockProver::run(K, &longer, vec![continue_from(&outputs, 2).instance()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+// The starting values as part of the statement.
+#[test]
+fn public_initial_values() {
+    let res = terms()[NUM - 1];
+    let circuit = from_a23(1, NUM, OutputMode::InitialAndResult);
+    let public = [1, 2, 3].map(Fp::from);
+    let prover = MockProver::run(K, &circuit, vec![public.into_iter().chain([res]).collect()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let other = [1, 2, 4].map(Fp::from);
+    let prover = MockProver::run(K, &circuit, vec![other.into_iter().chain([res]).collect()]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+// Every step over a tiny field: the gate accepts exactly the valid ones.
+#[test]
+fn gate_exhaustively_over_f7() {
+    for a in F7::elements() {
+        for b in F7::elements() {
+            for c in F7::elements() {
+                for d in F7::elements() {
+                    let witness = [a, b, c, d].into_iter().map(Value::known).collect();
+                    let circuit = FiboCircuit::from_witness(witness);
+                    let prover = MockProver::run(4, &circuit, vec![vec![d]]).unwrap();
+                    assert_eq!(prover.verify().is_ok(), d == (a + c) * b);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Fixtures the integration tests share, each taking what it needs through
+//! `mod common;`.
+
+// Every test crate builds its own copy and uses only part of it.
+#![allow(dead_code, unused_imports)]
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::Circuit;
+use halo2_proofs::poly::commitment::Params;
+
+use fibonacci_variant::prover;
+use fibonacci_variant::transcript::TranscriptHash;
+use fibonacci_variant::{witness, FibError, FiboCircuit, FiboSpec, OutputMode};
+
+pub use fibonacci_variant::dispatch::Mock;
+
+pub const NUM: usize = 10;
+pub const K: u32 = 8;
+
+pub fn circuit(spec: FiboSpec, [a, b, c]: [Fp; 3]) -> FiboCircuit<Fp> {
+    FiboCircuit {
+        spec,
+        a: Value::known(a),
+        b: Value::known(b),
+        c: Value::known(c),
+        witness: None,
+    }
+}
+
+/// The terms of `spec` from 1, 2, 3.
+pub fn from_123(spec: FiboSpec) -> FiboCircuit<Fp> {
+    circuit(spec, [1, 2, 3].map(Fp::from))
+}
+
+/// The `num` terms from `a`, 2, 3, exposing `output`.
+pub fn from_a23(a: u64, num: usize, output: OutputMode) -> FiboCircuit<Fp> {
+    circuit(FiboSpec { num, output, ..FiboSpec::default() }, [a, 2, 3].map(Fp::from))
+}
+
+/// The first `NUM` terms from 1, 2, 3.
+pub fn terms() -> Vec<Fp> {
+    witness(Fp::from(1), Fp::from(2), Fp::from(3), NUM)
+}
+
+/// Generates a proving key for `circuit` and proves it.
+pub fn prove_with<C: Circuit<Fp>>(params: &Params<EqAffine>, circuit: &C, instance: &[Fp]) -> Result<Vec<u8>, FibError> {
+    prove_with_transcript(params, circuit, instance, TranscriptHash::Blake2b)
+}
+
+/// `prove_with` over the `transcript` hash.
+pub fn prove_with_transcript<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    circuit: &C,
+    instance: &[Fp],
+    transcript: TranscriptHash,
+) -> Result<Vec<u8>, FibError> {
+    let pk = prover::keygen(params, circuit)?;
+    prover::prove_with_transcript(params, &pk, circuit, instance, transcript)
+}
@@ -0,0 +1,297 @@
+//! The other layouts of the same statement: padded to one key for every
+//! length, one column read by rotation, several steps per row, the V1
+//! floor planner's packing, and the terms streamed a row at a time; and
+//! all of them held to the same public outputs.
+
+// Build against halo2_proofs 0.4 under the usual crate name; see `compat`.
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_proofs_0_4 as halo2_proofs;
+
+mod common;
+
+use halo2_proofs::circuit::floor_planner::V1;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{keygen_vk, Circuit};
+use halo2_proofs::poly::commitment::Params;
+
+use fibonacci_variant::fingerprint::VkHash;
+use fibonacci_variant::inspect::{inspect, inspect_planned, inspect_queries, QueryReport};
+use fibonacci_variant::padded::FiboCircuitPadded;
+use fibonacci_variant::planner::{Planned, Planner};
+use fibonacci_variant::prover;
+use fibonacci_variant::row_usage::RowUsage;
+use fibonacci_variant::sequence::Continuation;
+use fibonacci_variant::streaming::FiboCircuitStreaming;
+use fibonacci_variant::unrolled::{gate_degree, FiboCircuitUnrolled, MAX_DEGREE};
+use fibonacci_variant::{witness, FiboCircuit, FiboColumnCircuit, FiboSpec, Layout, OutputMode, Proof};
+
+use common::{circuit, from_123, prove_with, terms, K, NUM};
+
+fn continuation() -> Continuation<Fp> {
+    let terms = terms();
+    Continuation {
+        last: [terms[NUM - 3], terms[NUM - 2], terms[NUM - 1]],
+        num: NUM,
+    }
+}
+
+/// Whether MockProver accepts `circuit` at `K` against `instance`.
+fn accepts<C: Circuit<Fp>>(circuit: &C, instance: &[Fp]) -> bool {
+    MockProver::run(K, circuit, vec![instance.to_vec()]).unwrap().verify().is_ok()
+}
+
+// Every layout of the same sequence accepts the same public outputs, and
+// rejects the same wrong ones, so none can drift from the others unseen.
+#[test]
+fn layouts_agree() {
+    for (start, num) in [([1, 2, 3], 4), ([1, 2, 3], NUM), ([0, 1, 1], 17), ([5, 7, 11], 2 * NUM)] {
+        let [a, b, c] = start.map(Fp::from);
+        let terms = witness(a, b, c, num);
+        let last = [terms[num - 3], terms[num - 2], terms[num - 1]];
+        for (output, instance) in [
+            (OutputMode::Result, vec![last[2]]),
+            (OutputMode::InitialAndResult, vec![a, b, c, last[2]]),
+            (OutputMode::Continuation, Continuation { last, num }.instance()),
+        ] {
+            let spec = |layout| FiboSpec { num, output, layout, ..FiboSpec::default() };
+            let rows = circuit(spec(Layout::Rows), [a, b, c]);
+            let column = FiboColumnCircuit(circuit(spec(Layout::Column), [a, b, c]));
+            let verdicts = |instance: &[Fp]| {
+                [
+                    ("rows", accepts(&rows, instance)),
+                    ("column", accepts(&column, instance)),
+                    ("unrolled", accepts(&FiboCircuitUnrolled::<_, 3>(rows.clone()), instance)),
+                    ("streaming", accepts(&FiboCircuitStreaming(rows.clone()), instance)),
+                    ("V1", accepts(&Planned::<_, V1>::new(rows.clone()), instance)),
+                ]
+            };
+            let mut wrong = instance.clone();
+            *wrong.last_mut().unwrap() += Fp::from(1);
+            for (layout, accepted) in verdicts(&instance) {
+                assert!(accepted, "the {} layout rejects the {} of {} terms", layout, output, num);
+            }
+            for (layout, accepted) in verdicts(&wrong) {
+                assert!(!accepted, "the {} layout accepts a wrong {} of {} terms", layout, output, num);
+            }
+        }
+        // the padded circuit only exposes the result, with the count after it.
+        let padded = FiboCircuitPadded {
+            max: 2 * NUM,
+            num: Value::known(num),
+            a: Value::known(a),
+            b: Value::known(b),
+            c: Value::known(c),
+        };
+        assert!(accepts(&padded, &[last[2], Fp::from(num as u64)]));
+        assert!(!accepts(&padded, &[last[2] + Fp::from(1), Fp::from(num as u64)]));
+    }
+}
+
+// One key for every length up to the maximum.
+#[test]
+fn padded_layout() {
+    let params: Params<EqAffine> = Params::new(K);
+    let padded = |num| FiboCircuitPadded {
+        max: 2 * NUM,
+        num: Value::known(num),
+        a: Value::known(Fp::from(1)),
+        b: Value::known(Fp::from(2)),
+        c: Value::known(Fp::from(3)),
+    };
+    let vk = keygen_vk(&params, &padded(4).without_witnesses()).unwrap();
+    for n in [4, NUM, 2 * NUM] {
+        let result = witness(Fp::from(1), Fp::from(2), Fp::from(3), n)[n - 1];
+        let public = vec![result, Fp::from(n as u64)];
+        let prover = MockProver::run(K, &padded(n), vec![public.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let vk_n = keygen_vk(&params, &padded(n)).unwrap();
+        assert_eq!(format!("{:?}", vk.pinned()), format!("{:?}", vk_n.pinned()));
+        let wrong_count = vec![result, Fp::from(n as u64 + 1)];
+        let prover = MockProver::run(K, &padded(n), vec![wrong_count]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+    let pk = prover::keygen(&params, &padded(4)).unwrap();
+    let public = [terms()[NUM - 1], Fp::from(NUM as u64)];
+    let proof = prover::prove(&params, &pk, &padded(NUM), &public).unwrap();
+    prover::verify(&params, &vk, &proof, &public).unwrap();
+    assert!(MockProver::run(K, &padded(2 * NUM + 1), vec![public.to_vec()]).is_err());
+}
+
+// The same statements with the sequence in a single column.
+#[test]
+fn single_column_layout() {
+    let res = terms()[NUM - 1];
+    let column = |output| {
+        FiboColumnCircuit(from_123(FiboSpec { num: NUM, output, layout: Layout::Column, ..FiboSpec::default() }))
+    };
+    let circuit = column(OutputMode::Result);
+    let prover = MockProver::run(K, &circuit, vec![vec![res]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(9999)]]).unwrap();
+    assert!(prover.verify().is_err());
+    let prover = MockProver::run(K, &column(OutputMode::Continuation), vec![continuation().instance()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let public_initial = [1, 2, 3].map(Fp::from).into_iter().chain([res]).collect();
+    let prover = MockProver::run(K, &column(OutputMode::InitialAndResult), vec![public_initial]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    // a spec that does not match the circuit is refused, not laid out wrongly.
+    assert!(MockProver::run(K, &circuit.0, vec![vec![res]]).is_err());
+    let params: Params<EqAffine> = Params::new(K);
+    let bytes = prove_with(&params, &circuit, &[res]).unwrap();
+    let vk_hash = VkHash::of(&keygen_vk(&params, &circuit).unwrap());
+    let proof = Proof::new(K, circuit.0.spec, vec![res], bytes, vk_hash);
+    Proof::from_bytes(&proof.to_bytes()).unwrap().verify(&params).unwrap();
+}
+
+fn check_unrolled<const N: usize>() {
+    for n in [4, 5, 6, 10, 23] {
+        let circuit = FiboCircuitUnrolled::<_, N>(from_123(FiboSpec { num: n, ..FiboSpec::default() }));
+        let result = witness(Fp::from(1), Fp::from(2), Fp::from(3), n)[n - 1];
+        let k = circuit.min_k();
+        let prover = MockProver::run(k, &circuit, vec![vec![result]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(k, &circuit, vec![vec![result + Fp::from(1)]]).unwrap();
+        assert!(prover.verify().is_err());
+        let usage = RowUsage::measure(&circuit).unwrap();
+        assert_eq!(usage.total_rows, circuit.rows());
+    }
+    // the bound `FiboCircuitUnrolled` checks at compile time
+    assert_eq!(FiboCircuitUnrolled::<Fp, N>::degree(), gate_degree(N));
+}
+
+// Several steps per row, whether or not the factor divides the length.
+#[test]
+fn unrolled_gate() {
+    check_unrolled::<1>();
+    check_unrolled::<2>();
+    check_unrolled::<3>();
+    check_unrolled::<4>();
+    assert!(gate_degree(5) > MAX_DEGREE);
+    let unrolled = FiboCircuitUnrolled::<_, 3>(from_123(FiboSpec {
+        num: NUM,
+        output: OutputMode::Continuation,
+        ..FiboSpec::default()
+    }));
+    let outputs = continuation();
+    let prover = MockProver::run(K, &unrolled, vec![outputs.instance()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let params: Params<EqAffine> = Params::new(K);
+    let proof = prove_with(&params, &unrolled, &outputs.instance()).unwrap();
+    let vk = keygen_vk(&params, &unrolled.without_witnesses()).unwrap();
+    prover::verify(&params, &vk, &proof, &outputs.instance()).unwrap();
+}
+
+// The same circuits laid out by V1 instead, never in more rows.
+#[test]
+fn v1_floor_planner() {
+    let res = terms()[NUM - 1];
+    let public_initial: Vec<Fp> = [1, 2, 3].map(Fp::from).into_iter().chain([res]).collect();
+    for (output, instance) in [
+        (OutputMode::Result, vec![res]),
+        (OutputMode::InitialAndResult, public_initial),
+        (OutputMode::Continuation, continuation().instance()),
+    ] {
+        let circuit = from_123(FiboSpec { num: NUM, output, ..FiboSpec::default() });
+        let planned = Planned::<_, V1>::new(circuit.clone());
+        let prover = MockProver::run(K, &planned, vec![instance.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(K, &planned, vec![vec![Fp::from(9999); instance.len()]]).unwrap();
+        assert!(prover.verify().is_err());
+        let usage = RowUsage::measure(&planned).unwrap();
+        assert!(usage.total_rows <= RowUsage::measure(&circuit).unwrap().total_rows);
+        let simple = inspect(circuit.clone(), K).unwrap();
+        let v1 = inspect_planned(circuit, K, Planner::V1).unwrap();
+        assert_eq!((v1.advice_columns, v1.degree), (simple.advice_columns, simple.degree));
+        assert!(v1.rows <= simple.rows);
+    }
+    let hashed = from_123(FiboSpec { num: NUM, output: OutputMode::Hashed, ..FiboSpec::default() });
+    let k = hashed.spec.min_k();
+    assert!(inspect_planned(hashed.clone(), k, Planner::V1).unwrap().rows <= inspect(hashed, k).unwrap().rows);
+    for planner in [Planner::Simple, Planner::V1] {
+        assert_eq!(planner.name().parse(), Ok(planner));
+    }
+    assert!("v2".parse::<Planner>().is_err());
+
+    let planned = Planned::<_, V1>::new(from_123(FiboSpec { num: NUM, ..FiboSpec::default() }));
+    let params: Params<EqAffine> = Params::new(K);
+    let proof = prove_with(&params, &planned, &[res]).unwrap();
+    let vk = keygen_vk(&params, &planned.without_witnesses()).unwrap();
+    prover::verify(&params, &vk, &proof, &[res]).unwrap();
+}
+
+// Streamed row by row, with the keys and proofs of the precomputed layout.
+#[test]
+fn streaming_layout() {
+    for num in [4, 5, NUM, 23] {
+        let result = witness(Fp::from(1), Fp::from(2), Fp::from(3), num)[num - 1];
+        let streamed = FiboCircuitStreaming(from_123(FiboSpec { num, ..FiboSpec::default() }));
+        let prover = MockProver::run(K, &streamed, vec![vec![result]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(K, &streamed, vec![vec![result + Fp::from(1)]]).unwrap();
+        assert!(prover.verify().is_err());
+        assert_eq!(RowUsage::measure(&streamed).unwrap().total_rows, streamed.0.spec.rows());
+    }
+    let streamed = |output| FiboCircuitStreaming(from_123(FiboSpec { num: NUM, output, ..FiboSpec::default() }));
+    let prover = MockProver::run(K, &streamed(OutputMode::Continuation), vec![continuation().instance()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let public_initial = [1, 2, 3].map(Fp::from).into_iter().chain([terms()[NUM - 1]]).collect();
+    let prover = MockProver::run(K, &streamed(OutputMode::InitialAndResult), vec![public_initial]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let circuit = streamed(OutputMode::Result);
+    let params: Params<EqAffine> = Params::new(K);
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let precomputed_vk = keygen_vk(&params, &circuit.0).unwrap();
+    assert_eq!(format!("{:?}", vk.pinned()), format!("{:?}", precomputed_vk.pinned()));
+    let res = terms()[NUM - 1];
+    let bytes = prove_with(&params, &circuit, &[res]).unwrap();
+    let proof = Proof::new(K, circuit.0.spec, vec![res], bytes, VkHash::of(&vk));
+    proof.verify(&params).unwrap();
+}
+
+// A million terms at `k = 20`, which only the backend's columns grow with.
+// halo2 0.3's MockProver finds a region's cells by linear search, quadratic
+// in a region this long, so this proves for real.
+#[test]
+#[ignore = "proves at k = 20; run with `cargo test --release -- --ignored`"]
+fn streaming_a_million_terms() {
+    let num = 1_000_000;
+    let streamed = FiboCircuitStreaming(from_123(FiboSpec { num, ..FiboSpec::default() }));
+    let k = streamed.0.spec.min_k();
+    assert_eq!(k, 20);
+    let result = witness(Fp::from(1), Fp::from(2), Fp::from(3), num)[num - 1];
+    let params: Params<EqAffine> = Params::new(k);
+    let bytes = prove_with(&params, &streamed, &[result]).unwrap();
+    let vk_hash = VkHash::of(&keygen_vk(&params, &streamed).unwrap());
+    let proof = Proof::new(k, streamed.0.spec, vec![result], bytes, vk_hash);
+    proof.verify(&params).unwrap();
+}
+
+// What each layout costs the verifier in openings: its distinct queries and
+// the point sets they batch into. The column layout reads back three rows in
+// one column, the unrolled one reads the next row, and padding opens the
+// most columns.
+#[test]
+fn query_reports() {
+    let report = |queries: [usize; 3], rotations: &[i32], point_sets: &[(&[i32], usize)]| QueryReport {
+        advice_queries: queries[0],
+        fixed_queries: queries[1],
+        instance_queries: queries[2],
+        rotations: rotations.to_vec(),
+        point_sets: point_sets.iter().map(|&(set, columns)| (set.to_vec(), columns)).collect(),
+    };
+    let rows = report([7, 1, 1], &[-1, 0], &[(&[-1, 0], 3), (&[0], 3)]);
+    assert_eq!(QueryReport::of::<FiboCircuit<Fp>>(), rows);
+    assert_eq!(QueryReport::of::<FiboCircuitStreaming<Fp>>(), rows);
+    let column = report([4, 1, 1], &[-3, -2, -1, 0], &[(&[-3, -2, -1, 0], 1), (&[0], 2)]);
+    assert_eq!(QueryReport::of::<FiboColumnCircuit<Fp>>(), column);
+    let unrolled = report([6, 1, 1], &[0, 1], &[(&[0], 2), (&[0, 1], 3)]);
+    assert_eq!(QueryReport::of::<FiboCircuitUnrolled<Fp, 3>>(), unrolled);
+    let padded = report([11, 0, 1], &[-1, 0], &[(&[-1, 0], 5), (&[0], 2)]);
+    assert_eq!(QueryReport::of::<FiboCircuitPadded<Fp>>(), padded);
+    let spec = FiboSpec { num: NUM, layout: Layout::Column, ..FiboSpec::default() };
+    assert_eq!(inspect_queries(from_123(spec)).unwrap(), column);
+    assert_eq!(inspect_queries(from_123(FiboSpec { layout: Layout::Rows, ..spec })).unwrap(), rows);
+}
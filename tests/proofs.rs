@@ -0,0 +1,825 @@
+//! Real proofs rather than the constraint check: keys and their pinned
+//! fingerprints, the proof format, the transcripts, seeded blinding, the
+//! parameters cache, provers and keys made once and cached, the proving
+//! service's limits, JSON-RPC, OpenAPI document and client, batch
+//! verification, chunked proving, the cost report measured against them,
+//! the session log of prove runs and the EVM verifier.
+
+// Build against halo2_proofs 0.4 under the usual crate name; see `compat`.
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_proofs_0_4 as halo2_proofs;
+
+mod common;
+
+use std::io::Write;
+use std::sync::Arc;
+
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{keygen_vk, Circuit};
+use halo2_proofs::poly::commitment::Params;
+
+use fibonacci_variant::artifacts::CircuitArtifacts;
+use fibonacci_variant::chunked::{chunk_count, prove_chunks, verify_chunks, ChunkError};
+use fibonacci_variant::context::{ProverContext, Setup};
+use fibonacci_variant::fingerprint::{ParseVkHashError, VkHash};
+use fibonacci_variant::inspect::{inspect, CircuitReport};
+use fibonacci_variant::params::{write_atomic, ParamsCache};
+use fibonacci_variant::proof::{verify_batch, verifying_key};
+use fibonacci_variant::prover;
+use fibonacci_variant::recurrence::Recurrence;
+#[cfg(feature = "server")]
+use fibonacci_variant::server::{Element, JobStatus, ProveRequest, Service, ServiceError, VerifyRequest};
+use fibonacci_variant::threads;
+use fibonacci_variant::transcript::TranscriptHash;
+use fibonacci_variant::{min_k_for, witness, FibError, FiboCircuit, FiboSpec, Layout, OutputMode, Proof};
+
+use common::{from_a23, prove_with, prove_with_transcript, K, NUM};
+
+fn result(a: u64) -> Fp {
+    witness(Fp::from(a), Fp::from(2), Fp::from(3), NUM)[NUM - 1]
+}
+
+// Keygen over the witness-free circuit must see the same shape.
+#[test]
+fn keygen_without_witnesses() {
+    let circuit = from_a23(1, NUM, OutputMode::Result);
+    let params: Params<EqAffine> = Params::new(K);
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let vk_shape = keygen_vk(&params, &circuit.without_witnesses()).unwrap();
+    assert_eq!(format!("{:?}", vk.pinned()), format!("{:?}", vk_shape.pinned()));
+}
+
+#[test]
+fn real_proof() {
+    let (circuit, res) = (from_a23(1, NUM, OutputMode::Result), result(1));
+    let params: Params<EqAffine> = Params::new(K);
+    let pk = prover::keygen(&params, &circuit).unwrap();
+    let proof = prover::prove(&params, &pk, &circuit, &[res]).unwrap();
+    prover::verify(&params, pk.get_vk(), &proof, &[res]).unwrap();
+    assert!(matches!(
+        prover::verify(&params, pk.get_vk(), &proof, &[Fp::from(9999)]),
+        Err(FibError::ProofVerificationFailed)
+    ));
+    assert!(matches!(
+        prover::verify(&params, pk.get_vk(), &proof[..100], &[res]),
+        Err(FibError::ProofVerificationFailed)
+    ));
+    let vk_hash = VkHash::of(pk.get_vk());
+    let proof = Proof::new(K, circuit.spec, vec![res], proof, vk_hash);
+    assert_eq!(Proof::from_bytes(&proof.to_bytes()).unwrap(), proof);
+    assert_eq!(Proof::from_hex(&proof.to_hex()).unwrap(), proof);
+    proof.verify(&params).unwrap();
+    let empty = FiboSpec { num: 0, ..FiboSpec::default() };
+    assert!(matches!(empty.check_num(), Err(FibError::InvalidLength { num: 0, min: 1 })));
+    let short = FiboSpec { num: 3, output: OutputMode::Continuation, ..FiboSpec::default() };
+    assert!(matches!(short.check_num(), Err(FibError::InvalidLength { num: 3, min: 4 })));
+    let column = Proof { spec: FiboSpec { layout: Layout::Column, ..proof.spec }, ..proof.clone() };
+    let fibonacci_hashed = FiboSpec { recurrence: Recurrence::Fibonacci, output: OutputMode::Hashed, ..proof.spec };
+    assert!(matches!(column.verify(&params), Err(FibError::VkMismatch { recorded, .. }) if recorded == vk_hash));
+    let mut stale = proof.clone();
+    stale.vk_hash.0[0] ^= 1;
+    assert!(matches!(stale.verify(&params), Err(FibError::VkMismatch { expected, .. }) if expected == vk_hash));
+    assert!(matches!(
+        Proof { spec: fibonacci_hashed, ..proof.clone() }.verify(&params),
+        Err(FibError::Unsupported(_))
+    ));
+}
+
+// A proof file is untrusted: a header sizing a circuit that cannot fit its
+// `k`, naming no circuit at all, or with a `k` no parameters exist for, is
+// an error before any key or parameters are made.
+#[test]
+fn hostile_header() {
+    let params: Params<EqAffine> = Params::new(K);
+    let hostile = |num, k| {
+        let spec = FiboSpec { num, ..FiboSpec::default() };
+        let proof = Proof::new(k, spec, vec![Fp::from(1)], vec![], VkHash([0; 32]));
+        Proof::from_bytes(&proof.to_bytes()).unwrap()
+    };
+    for num in [1 << 40, 1 << 62] {
+        assert!(matches!(hostile(num, K).check_shape(), Err(FibError::KTooSmall(_))));
+        assert!(matches!(hostile(num, K).verify(&params), Err(FibError::KTooSmall(_))));
+    }
+    assert!(matches!(hostile(0, K).verify(&params), Err(FibError::InvalidLength { num: 0, min: 1 })));
+    // past what any parameters can be made for, so no cache may be asked
+    for k in [40, 64] {
+        assert!(matches!(hostile(NUM, k).check_shape(), Err(FibError::KTooLarge { .. })));
+        assert!(matches!(hostile(NUM, k).verify(&params), Err(FibError::KTooLarge { .. })));
+    }
+    assert!(matches!(
+        hostile(NUM, K + 1).verify(&params),
+        Err(FibError::ParamsMismatch { params: K, proof: 9 })
+    ));
+}
+
+// The fingerprints of the keys proofs are checked against, pinned, so a
+// change to the gates, columns or equality set of any shape fails here and
+// not in the field. Update them only for a change meant to retire the
+// proofs made before it.
+#[test]
+fn vk_fingerprints() {
+    let params: Params<EqAffine> = Params::new(K);
+    let spec = FiboSpec { num: NUM, ..FiboSpec::default() };
+    let pinned = [
+        (spec, "2eb3e632662eba2b86f11d7b29a95b20651200a3966460094bbad7f8ff767b1a"),
+        (FiboSpec { output: OutputMode::InitialAndResult, ..spec }, "5cc6b1bebebd84fbe7e44eec788e122953c2dc68fa971ff361b59a863be71d2b"),
+        (FiboSpec { layout: Layout::Column, ..spec }, "71e5bf7188137f65c34352bff31811192c87de727918f24585f91d990d25e40d"),
+        (FiboSpec { recurrence: Recurrence::Fibonacci, ..spec }, "1254d8486067facdd3d92cce96d44047379446c0b70ca0111d21214fb212660b"),
+        (FiboSpec { num: NUM + 1, ..spec }, "de2107a200a04d85c5973bdcc2f7f5c67081faa3a5224612525a46ae4e582d03"),
+    ];
+    for (spec, expected) in pinned {
+        let vk_hash = VkHash::of(&verifying_key(&params, spec).unwrap());
+        assert_eq!(vk_hash.to_string(), expected, "the verifying key of {} changed", spec);
+        assert_eq!(expected.parse::<VkHash>(), Ok(vk_hash));
+        assert_eq!(expected.to_uppercase().parse::<VkHash>(), Ok(vk_hash));
+    }
+    for bad in ["", "2eb3", &format!("{}0", pinned[0].1), &format!("{}g", &pinned[0].1[1..])] {
+        assert_eq!(bad.parse::<VkHash>(), Err(ParseVkHashError));
+    }
+    let vk = keygen_vk(&params, &from_a23(1, NUM, OutputMode::Result)).unwrap();
+    assert_eq!(VkHash::of(&vk).to_string(), pinned[0].1);
+}
+
+// A proof verifies under the transcript it was made with, which it records.
+#[test]
+fn poseidon_transcript() {
+    let (circuit, res) = (from_a23(1, NUM, OutputMode::Result), result(1));
+    let params: Params<EqAffine> = Params::new(K);
+    let pk = prover::keygen(&params, &circuit).unwrap();
+    let poseidon = prove_with_transcript(&params, &circuit, &[res], TranscriptHash::Poseidon).unwrap();
+    let blake2b = prove_with(&params, &circuit, &[res]).unwrap();
+    assert_eq!(poseidon.len(), blake2b.len());
+    prover::verify_with_transcript(&params, pk.get_vk(), &poseidon, &[res], TranscriptHash::Poseidon).unwrap();
+    assert!(matches!(
+        prover::verify_with_transcript(&params, pk.get_vk(), &poseidon, &[Fp::from(9999)], TranscriptHash::Poseidon),
+        Err(FibError::ProofVerificationFailed)
+    ));
+    assert!(matches!(
+        prover::verify(&params, pk.get_vk(), &poseidon, &[res]),
+        Err(FibError::ProofVerificationFailed)
+    ));
+    assert!(matches!(
+        prover::verify_with_transcript(&params, pk.get_vk(), &blake2b, &[res], TranscriptHash::Poseidon),
+        Err(FibError::ProofVerificationFailed)
+    ));
+
+    let proof = Proof {
+        transcript: TranscriptHash::Poseidon,
+        ..Proof::new(K, circuit.spec, vec![res], poseidon, VkHash::of(pk.get_vk()))
+    };
+    let decoded = Proof::from_bytes(&proof.to_bytes()).unwrap();
+    assert_eq!(decoded, proof);
+    decoded.verify(&params).unwrap();
+    let relabelled = Proof { transcript: TranscriptHash::Blake2b, ..proof.clone() };
+    assert!(matches!(relabelled.verify(&params), Err(FibError::ProofVerificationFailed)));
+    let mut unknown = proof.to_bytes();
+    unknown[15] = 2;
+    assert!(Proof::from_bytes(&unknown).is_err());
+
+    let blake2b = Proof { bytes: blake2b, transcript: TranscriptHash::Blake2b, ..proof.clone() };
+    verify_batch(&[proof.clone(), blake2b.clone()], pk.get_vk(), &params).unwrap();
+    assert!(verify_batch(&[relabelled, blake2b], pk.get_vk(), &params).is_err());
+    for hash in [TranscriptHash::Blake2b, TranscriptHash::Poseidon] {
+        assert_eq!(hash.name().parse(), Ok(hash));
+    }
+}
+
+// The same seed gives the same bytes; without one, every proof differs.
+#[test]
+fn seeded_proving() {
+    let (circuit, res) = (from_a23(1, NUM, OutputMode::Result), result(1));
+    let params: Params<EqAffine> = Params::new(K);
+    let pk = prover::keygen(&params, &circuit).unwrap();
+    for hash in [TranscriptHash::Blake2b, TranscriptHash::Poseidon] {
+        let seeded = |seed| prover::prove_seeded(&params, &pk, &circuit, &[res], hash, [seed; 32]).unwrap();
+        let proof = seeded(7);
+        assert_eq!(seeded(7), proof);
+        assert_ne!(seeded(8), proof);
+        prover::verify_with_transcript(&params, pk.get_vk(), &proof, &[res], hash).unwrap();
+        let random = || prover::prove_with_transcript(&params, &pk, &circuit, &[res], hash).unwrap();
+        assert_ne!(random(), random());
+    }
+}
+
+// `prove_spec` records everything `Proof` needs: the proof it packs
+// verifies, under the transcript it was made with, and a seed still fixes
+// the bytes.
+#[test]
+fn prove_output() {
+    let (circuit, res) = (from_a23(1, NUM, OutputMode::Result), result(1));
+    let params: Params<EqAffine> = Params::new(K);
+    let output = prover::prove_spec(&params, &circuit, &[res], TranscriptHash::Poseidon, Some([7; 32])).unwrap();
+    assert_eq!((output.k, output.spec, output.transcript), (K, circuit.spec, TranscriptHash::Poseidon));
+    assert_eq!(output.instance, vec![res]);
+    assert_eq!(output.vk_hash, VkHash::of(&verifying_key(&params, circuit.spec).unwrap()));
+    let again = prover::prove_spec(&params, &circuit, &[res], TranscriptHash::Poseidon, Some([7; 32])).unwrap();
+    assert_eq!(again.bytes, output.bytes);
+    let proof = Proof::from(output);
+    assert_eq!(proof.transcript, TranscriptHash::Poseidon);
+    proof.verify(&params).unwrap();
+    let small: Params<EqAffine> = Params::new(3);
+    assert!(matches!(
+        prover::prove_spec(&small, &circuit, &[res], TranscriptHash::Blake2b, None),
+        Err(FibError::KTooSmall(_))
+    ));
+}
+
+// One `ProverContext` proves different inputs of its shape, from several
+// threads at once, and every proof verifies under the key it made.
+#[test]
+fn prover_context() {
+    fn assert_sync<T: Send + Sync>() {}
+    assert_sync::<ProverContext>();
+
+    let params: Params<EqAffine> = Params::new(K);
+    let spec = FiboSpec { num: NUM, output: OutputMode::InitialAndResult, ..FiboSpec::default() };
+    let context = ProverContext::new(params.clone(), spec).unwrap();
+    assert_eq!(context.keys().vk_hash(), VkHash::of(&verifying_key(&params, spec).unwrap()));
+    let outputs: Vec<_> = std::thread::scope(|scope| {
+        let context = &context;
+        let proving: Vec<_> = (1..=2)
+            .map(|a| scope.spawn(move || context.prove([a, 2, 3].map(Fp::from)).unwrap()))
+            .collect();
+        proving.into_iter().map(|thread| thread.join().unwrap()).collect()
+    });
+    for (a, output) in (1..=2).zip(outputs) {
+        assert_eq!(output.instance, vec![Fp::from(a), Fp::from(2), Fp::from(3), result(a)]);
+        assert_eq!(output.timings.keygen, std::time::Duration::ZERO);
+        Proof::from(output).verify(&params).unwrap();
+    }
+    let seeded = |seed| context.prove_seeded([1, 2, 3].map(Fp::from), [seed; 32]).unwrap().bytes;
+    assert_eq!(seeded(7), seeded(7));
+
+    let poseidon = ProverContext::new(params.clone(), FiboSpec { layout: Layout::Column, ..spec })
+        .unwrap()
+        .with_transcript(TranscriptHash::Poseidon);
+    Proof::from(poseidon.prove([1, 2, 3].map(Fp::from)).unwrap()).verify(&params).unwrap();
+    let long = FiboSpec { num: 1 << K, ..spec };
+    assert!(matches!(ProverContext::new(params.clone(), long), Err(FibError::KTooSmall(_))));
+    let committed = FiboSpec { output: OutputMode::Committed, ..spec };
+    let committed = ProverContext::new(Params::new(8), committed).unwrap();
+    assert!(matches!(committed.prove([1, 2, 3].map(Fp::from)), Err(FibError::Unsupported(_))));
+}
+
+// `Setup -> Keys -> ProverContext`: keys made once check the proofs of
+// their own prover, and tell a proof of another shape or `k` apart.
+#[test]
+fn setup_keys_prover() {
+    let dir = std::env::temp_dir().join(format!("fibonacci_variant-setup-{}", std::process::id()));
+    let setup = Setup::load(&ParamsCache::new(&dir), K).unwrap();
+    let spec = FiboSpec { num: NUM, ..FiboSpec::default() };
+    let keys = setup.keys(spec).unwrap();
+    assert_eq!((keys.spec(), keys.params().k()), (spec, K));
+    let prover = keys.prover();
+    let proof = Proof::from(prover.prove([1, 2, 3].map(Fp::from)).unwrap());
+    let keys = prover.into_keys();
+    keys.verify(&proof).unwrap();
+
+    let fibonacci = setup.keys(FiboSpec { recurrence: Recurrence::Fibonacci, ..spec }).unwrap();
+    assert!(matches!(fibonacci.verify(&proof), Err(FibError::VkMismatch { .. })));
+    let larger = Setup::new(Params::new(K + 1)).keys(spec).unwrap();
+    assert!(matches!(
+        larger.verify(&proof),
+        Err(FibError::ParamsMismatch { params, proof }) if (params, proof) == (K + 1, K)
+    ));
+    assert!(matches!(setup.keys(FiboSpec { num: 1 << K, ..spec }), Err(FibError::KTooSmall(_))));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Artifacts are made once per shape and shared, by the service too, until
+// invalidated; the verifying key is the one a proof regenerates.
+#[test]
+fn circuit_artifacts() {
+    let dir = std::env::temp_dir().join(format!("fibonacci_variant-artifacts-{}", std::process::id()));
+    let artifacts = Arc::new(CircuitArtifacts::new(ParamsCache::new(&dir)));
+    let spec = FiboSpec { num: NUM, ..FiboSpec::default() };
+    let (k, made) = (spec.min_k(), artifacts.artifacts(spec.min_k(), spec).unwrap());
+    assert!(Arc::ptr_eq(&made, &artifacts.artifacts(k, spec).unwrap()));
+    assert_eq!(made.vk_hash, VkHash::of(&verifying_key(&artifacts.params(k).unwrap(), spec).unwrap()));
+    assert_eq!(made.cs.degree(), inspect(from_a23(1, NUM, OutputMode::Result), k).unwrap().degree);
+    assert!(Arc::ptr_eq(&artifacts.find(made.vk_hash).unwrap(), &made));
+    assert!(matches!(artifacts.artifacts(3, spec), Err(FibError::KTooSmall(_))));
+
+    #[cfg(feature = "server")]
+    {
+        let service = Service::with_artifacts(artifacts.clone(), 20);
+        let request = ProveRequest { a: Element::Number(1), b: Element::Number(2), c: Element::Number(3), num: NUM };
+        let proved = service.prove(&request).unwrap();
+        let prover = artifacts.prover(k, spec).unwrap();
+        assert_eq!(Proof::from_hex(&proved.proof).unwrap().vk_hash, prover.keys().vk_hash());
+        service.prove(&request).unwrap();
+        assert!(Arc::ptr_eq(&prover, &artifacts.prover(k, spec).unwrap()));
+    }
+
+    artifacts.invalidate(k, spec);
+    assert!(artifacts.find(made.vk_hash).is_none());
+    let remade = artifacts.artifacts(k, spec).unwrap();
+    assert!(!Arc::ptr_eq(&made, &remade));
+    assert_eq!(remade.vk_hash, made.vk_hash);
+    let params = artifacts.params(k).unwrap();
+    artifacts.invalidate_k(k);
+    assert!(!Arc::ptr_eq(&params, &artifacts.params(k).unwrap()));
+    artifacts.clear();
+    assert!(artifacts.find(made.vk_hash).is_none());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// A proof made and checked with nothing imported but the prelude.
+#[test]
+fn prelude() {
+    use fibonacci_variant::prelude::*;
+
+    let spec = FiboSpec { num: 10, ..FiboSpec::default() };
+    let (a, b, c) = (Fp::from(1), Fp::from(2), Fp::from(3));
+    let res = witness(a, b, c, spec.num)[spec.num - 1];
+    let circuit = FiboCircuit { spec, a: Value::known(a), b: Value::known(b), c: Value::known(c), witness: None };
+    let params: Params<EqAffine> = Params::new(min_k_for(spec.num));
+    let pk: ProvingKey<EqAffine> = keygen(&params, &circuit.without_witnesses()).unwrap();
+    let bytes = prove(&params, &pk, &circuit, &[res]).unwrap();
+    verify(&params, pk.get_vk(), &bytes, &[res]).unwrap();
+    let proof = Proof::new(params.k(), spec, vec![res], bytes, VkHash::of(pk.get_vk()));
+    Proof::from_bytes(&proof.to_bytes()).unwrap().verify(&params).unwrap();
+}
+
+// Generated once, then read back.
+#[test]
+fn params_cache() {
+    let dir = std::env::temp_dir().join(format!("fibonacci_variant-params-{}", std::process::id()));
+    let cache = ParamsCache::new(&dir);
+    let (mut generated, mut loaded) = (vec![], vec![]);
+    cache.get(K).unwrap().write(&mut generated).unwrap();
+    cache.get(K).unwrap().write(&mut loaded).unwrap();
+    assert_eq!(generated, loaded);
+    assert_eq!(cache.get(64).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // runs missing the same k at once generate it once, under the lock, and
+    // all read the one file; no temporary file is left behind
+    let all: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..4).map(|_| scope.spawn(|| cache.get(K).unwrap())).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    for params in all {
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+        assert_eq!(bytes, generated);
+    }
+    let mut names: Vec<_> =
+        std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+    names.sort();
+    assert_eq!(names, [format!("params-{}.bin", K), format!("params-{}.lock", K)]);
+
+    let file = dir.join("out.txt");
+    write_atomic(&file, |writer| writer.write_all(b"first")).unwrap();
+    write_atomic(&file, |writer| writer.write_all(b"second")).unwrap();
+    assert_eq!(std::fs::read(&file).unwrap(), b"second");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// The service refuses a request it cannot size before making parameters or
+// keys for it, so the cache directory is never even created.
+#[cfg(feature = "server")]
+#[test]
+fn service_limits() {
+    assert_eq!(FiboSpec { num: usize::MAX, ..FiboSpec::default() }.min_k(), usize::BITS);
+    let merkle = FiboSpec { num: usize::MAX, output: OutputMode::MerkleRoot, ..FiboSpec::default() };
+    assert_eq!(merkle.min_k(), usize::BITS);
+    let dir = std::env::temp_dir().join(format!("fibonacci_variant-service-{}", std::process::id()));
+    let service = Service::new(ParamsCache::new(&dir), 20);
+    for num in [0, 1 << 40, usize::MAX] {
+        let request = ProveRequest {
+            a: Element::Number(1),
+            b: Element::Number(2),
+            c: Element::Number(3),
+            num,
+        };
+        assert!(matches!(service.prove(&request), Err(ServiceError::BadRequest(_))));
+        assert!(matches!(service.submit(&request), Err(ServiceError::BadRequest(_))));
+    }
+    for (num, k) in [(1 << 40, K), (1 << 62, K), (NUM, 40)] {
+        let proof = Proof::new(k, FiboSpec { num, ..FiboSpec::default() }, vec![Fp::from(1)], vec![], VkHash([0; 32]));
+        let request = VerifyRequest { proof: proof.to_hex(), public_inputs: vec![] };
+        assert!(matches!(service.verify(&request), Err(ServiceError::BadRequest(_))));
+    }
+    // with no workers the job stays queued; cancelled, it is forgotten
+    let request = ProveRequest { a: Element::Number(1), b: Element::Number(2), c: Element::Number(3), num: NUM };
+    let id = service.submit(&request).unwrap().id;
+    assert!(matches!(service.job(id).unwrap().status, JobStatus::Queued));
+    assert!(matches!(service.cancel(id).unwrap().status, JobStatus::Cancelled));
+    assert!(matches!(service.job(id), Err(ServiceError::NotFound(_))));
+    assert!(matches!(service.cancel(id), Err(ServiceError::NotFound(_))));
+    assert!(!dir.exists());
+}
+
+// JSON-RPC answers each call with its id, errors with the reserved codes,
+// and notifications not at all.
+#[cfg(feature = "server")]
+#[test]
+fn json_rpc() {
+    use fibonacci_variant::rpc;
+    use serde_json::{json, Value};
+
+    let dir = std::env::temp_dir().join(format!("fibonacci_variant-rpc-{}", std::process::id()));
+    let service = Service::new(ParamsCache::new(&dir), 20);
+    let answer = |body: &str| -> Value { serde_json::from_str(&rpc::handle(&service, body).unwrap()).unwrap() };
+    let call = |body: Value| answer(&body.to_string());
+
+    let info = call(json!({ "jsonrpc": "2.0", "method": "info", "id": 1 }));
+    assert_eq!((info["id"].clone(), info["result"]["max_k"].clone()), (json!(1), json!(20)));
+    let report = call(json!({ "jsonrpc": "2.0", "method": "inspect", "params": { "num": NUM }, "id": "a" }));
+    assert_eq!(report["result"]["k"], json!(FiboSpec { num: NUM, ..FiboSpec::default() }.min_k()));
+    assert!(!dir.exists());
+
+    let params = json!({ "a": 1, "b": 2, "c": 3, "num": NUM });
+    let proof = call(json!({ "jsonrpc": "2.0", "method": "prove", "params": params, "id": 2 }))["result"]["proof"].clone();
+    let verify = |public: Value, id| {
+        let params = json!({ "proof": proof, "public_inputs": public });
+        json!({ "jsonrpc": "2.0", "method": "verify", "params": params, "id": id })
+    };
+    let answers = call(json!([verify(json!([]), 3), verify(json!([7]), 4), { "jsonrpc": "2.0", "method": "info" }]));
+    assert_eq!(answers, json!([
+        { "jsonrpc": "2.0", "result": { "valid": true }, "id": 3 },
+        { "jsonrpc": "2.0", "result": { "valid": false }, "id": 4 },
+    ]));
+
+    let code = |body: &str| answer(body)["error"]["code"].clone();
+    assert_eq!(code("{"), json!(rpc::PARSE_ERROR));
+    assert_eq!(code("[]"), json!(rpc::INVALID_REQUEST));
+    assert_eq!(code(r#"{"method": "info", "id": 5}"#), json!(rpc::INVALID_REQUEST));
+    assert_eq!(code(r#"{"jsonrpc": "2.0", "method": "mine", "id": 5}"#), json!(rpc::METHOD_NOT_FOUND));
+    let positional = r#"{"jsonrpc": "2.0", "method": "prove", "params": [1, 2, 3], "id": 5}"#;
+    assert_eq!(code(positional), json!(rpc::INVALID_PARAMS));
+    let past = r#"{"jsonrpc": "2.0", "method": "inspect", "params": {"num": 10, "k": 40}, "id": 5}"#;
+    assert_eq!(code(past), json!(rpc::INVALID_PARAMS));
+    assert_eq!(rpc::handle(&service, r#"{"jsonrpc": "2.0", "method": "info"}"#), None);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// The OpenAPI document has every route `Service::handle` answers, and the
+// Swagger UI page points at it.
+#[cfg(feature = "openapi")]
+#[test]
+fn openapi_document() {
+    use fibonacci_variant::openapi;
+    use serde_json::Value;
+
+    let document: Value = serde_json::from_str(&openapi::document().to_json().unwrap()).unwrap();
+    let paths = &document["paths"];
+    for (path, method) in [("/prove", "post"), ("/verify", "post"), ("/jobs", "post"), ("/rpc", "post")] {
+        assert!(paths[path][method].is_object(), "{} {}", method, path);
+    }
+    assert!(paths["/jobs/{id}"]["get"].is_object() && paths["/jobs/{id}"]["delete"].is_object());
+    let required = &document["components"]["schemas"]["ProveRequest"]["required"];
+    assert_eq!(required, &serde_json::json!(["a", "b", "c", "num"]));
+    let (page, content_type) = openapi::swagger_ui("").unwrap();
+    assert_eq!(content_type, "text/html");
+    assert!(std::str::from_utf8(&page).unwrap().contains("swagger-ui"));
+    assert!(openapi::swagger_ui("no-such-file.js").is_none());
+}
+
+// The client against a service on a local port: a proof straight away and
+// one through the queue both verify, and the service's errors come back
+// with their status.
+#[cfg(feature = "client")]
+#[test]
+fn client() {
+    use fibonacci_variant::client::{Client, ClientError};
+    use fibonacci_variant::server::serve;
+    use std::time::Duration;
+
+    let dir = std::env::temp_dir().join(format!("fibonacci_variant-client-{}", std::process::id()));
+    // A free port, given up for the service to take.
+    let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let service = Service::new(ParamsCache::new(&dir), 20);
+    let serving = addr.clone();
+    std::thread::spawn(move || serve(&serving, service, 2, 1));
+
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(async {
+        let client = Client::new(format!("http://{}/", addr));
+        let request = ProveRequest { a: Element::Number(1), b: Element::Number(2), c: Element::Number(3), num: NUM };
+        let proved = loop {
+            match client.prove(&request).await {
+                Err(ClientError::Http(err)) if err.is_connect() => tokio::time::sleep(Duration::from_millis(50)).await,
+                answer => break answer.unwrap(),
+            }
+        };
+        let proof = proved.to_proof().unwrap();
+        assert_eq!(proof.instance, vec![result(1)]);
+        assert!(client.verify_proof(&proof).await.unwrap());
+
+        let queued = client.prove_queued(&request, Duration::from_millis(50)).await.unwrap();
+        assert!(client.verify_proof(&queued.to_proof().unwrap()).await.unwrap());
+        let wrong = VerifyRequest { proof: proved.proof.clone(), public_inputs: vec![Element::Number(7)] };
+        assert!(!client.verify(&wrong).await.unwrap().valid);
+
+        let long = ProveRequest { num: 1 << 40, ..request };
+        assert!(matches!(client.prove(&long).await, Err(ClientError::Service { status: 400, .. })));
+        assert!(matches!(client.job(1 << 40).await, Err(ClientError::Service { status: 404, .. })));
+    });
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Several proofs under one key, checked together.
+#[test]
+fn batch_verification() {
+    let k = min_k_for(NUM);
+    let params: Params<EqAffine> = Params::new(k);
+    let vk_hash = VkHash::of(&keygen_vk(&params, &from_a23(0, NUM, OutputMode::Result)).unwrap());
+    let proofs: Vec<Proof> = (0..4)
+        .map(|a| {
+            let circuit = from_a23(a, NUM, OutputMode::Result);
+            let instance = vec![result(a)];
+            let bytes = prove_with(&params, &circuit, &instance).unwrap();
+            Proof::new(k, circuit.spec, instance, bytes, vk_hash)
+        })
+        .collect();
+    let vk = proofs[0].verifying_key(&params).unwrap();
+    verify_batch(&proofs, &vk, &params).unwrap();
+    let mut tampered = proofs.clone();
+    tampered[2].instance[0] += Fp::from(1);
+    assert!(verify_batch(&tampered, &vk, &params).is_err());
+    let mut stale = proofs.clone();
+    stale[1].vk_hash.0[31] ^= 1;
+    assert!(matches!(verify_batch(&stale, &vk, &params), Err(FibError::VkMismatch { .. })));
+}
+
+// A long sequence as a chain of chunk proofs.
+#[test]
+fn chunked_proving() {
+    let (long, chunk) = (23, 8);
+    assert_eq!(chunk_count(long, chunk), Some(4));
+    assert_eq!(chunk_count(long + 1, chunk), None);
+    let chunk_spec = FiboSpec { num: chunk, output: OutputMode::Chunk, ..FiboSpec::default() };
+    let k = chunk_spec.min_k();
+    let params: Params<EqAffine> = Params::new(k);
+    let start = [1, 2, 3].map(Fp::from);
+    let chunks = prove_chunks(&params, k, start, long, chunk).unwrap();
+    assert_eq!(chunks.len(), 4);
+    let statement = verify_chunks(&chunks, &params).unwrap();
+    let long_terms = witness(Fp::from(1), Fp::from(2), Fp::from(3), long);
+    assert_eq!(statement.initial, start);
+    assert_eq!(statement.last, [long_terms[long - 3], long_terms[long - 2], long_terms[long - 1]]);
+    assert_eq!(statement.num, long);
+    let mut swapped = chunks.clone();
+    swapped.swap(1, 2);
+    assert!(matches!(verify_chunks(&swapped, &params), Err(ChunkError::Link { index: 1 })));
+    let mut forged = chunks.clone();
+    forged[3].instance[5] += Fp::from(1);
+    assert!(matches!(verify_chunks(&forged, &params), Err(ChunkError::Proof(_))));
+    assert!(matches!(verify_chunks(&[], &params), Err(ChunkError::Empty)));
+    assert!(matches!(
+        prove_chunks(&params, k, start, long + 1, chunk),
+        Err(ChunkError::Length { .. })
+    ));
+}
+
+// The cost report against what the spec and a real proof say.
+#[test]
+fn cost_report() {
+    for output in [OutputMode::Result, OutputMode::Hashed] {
+        let circuit = from_a23(1, NUM, output);
+        let spec = circuit.spec;
+        let report: CircuitReport = inspect(circuit, spec.min_k()).unwrap();
+        assert_eq!(report.rows, spec.rows());
+        assert_eq!(report.min_k, spec.min_k());
+        assert_eq!(report.instance_columns, 1);
+    }
+    let k = min_k_for(NUM);
+    let params: Params<EqAffine> = Params::new(k);
+    let proof = prove_with(&params, &from_a23(1, NUM, OutputMode::Result), &[result(1)]).unwrap();
+    let report = inspect(from_a23(1, NUM, OutputMode::Result), k).unwrap();
+    assert_eq!((report.advice_columns, report.degree), (4, 3));
+    // the constants column; the step and chain selectors; the result and
+    // the instance cell it is copied to.
+    assert_eq!((report.fixed_columns, report.selectors, report.copied_columns), (1, 2, 2));
+    assert_eq!((report.quotient_degree, report.extended_domain), (2 * (1 << k) - 1, 2 << k));
+    assert_eq!(report.proof_size, proof.len());
+    let column = FiboCircuit {
+        spec: FiboSpec { num: NUM, layout: Layout::Column, ..FiboSpec::default() },
+        ..from_a23(1, NUM, OutputMode::Result)
+    };
+    let report = inspect(column, k).unwrap();
+    assert_eq!(report.advice_columns, 1);
+}
+
+// The same circuit over bn256 with KZG: both recurrences and every output
+// the backend lays out, short sequences included.
+#[cfg(feature = "kzg")]
+#[test]
+fn kzg_proofs() {
+    use fibonacci_variant::kzg::{self, Fr};
+    use halo2_proofs::circuit::Value;
+
+    let params = kzg::setup(K);
+    let cases = [
+        (Recurrence::Variant, OutputMode::Result, NUM),
+        (Recurrence::Variant, OutputMode::Continuation, NUM),
+        (Recurrence::Fibonacci, OutputMode::InitialAndResult, 2),
+        (Recurrence::Fibonacci, OutputMode::Chunk, NUM),
+    ];
+    for (recurrence, output, num) in cases {
+        let spec = FiboSpec { num, output, recurrence, ..FiboSpec::default() };
+        let [a, b, c] = [1, 2, 3].map(Fr::from);
+        let terms = recurrence.terms(a, b, c, num);
+        let last = terms[num - 1];
+        let instance = match output {
+            OutputMode::Result => vec![last],
+            OutputMode::Continuation => vec![terms[num - 3], terms[num - 2], last, Fr::from(num as u64)],
+            OutputMode::InitialAndResult => vec![a, b, c, last],
+            _ => [a, b, c, terms[num - 3], terms[num - 2], last].to_vec(),
+        };
+        let circuit = FiboCircuit { spec, a: Value::known(a), b: Value::known(b), c: Value::known(c), witness: None };
+        let pk = kzg::keygen(&params, &circuit).unwrap();
+        let proof = kzg::prove(&params, &pk, &circuit, &instance).unwrap();
+        kzg::verify(&params, pk.get_vk(), &proof, &instance).unwrap();
+        let mut wrong = instance.clone();
+        *wrong.last_mut().unwrap() += Fr::from(1);
+        assert!(matches!(kzg::verify(&params, pk.get_vk(), &proof, &wrong), Err(FibError::ProofVerificationFailed)));
+    }
+    let column = FiboSpec { num: NUM, layout: Layout::Column, ..FiboSpec::default() };
+    let column = FiboCircuit { spec: column, ..FiboCircuit::default() };
+    assert!(matches!(kzg::keygen(&params, &column), Err(FibError::Unsupported(_))));
+}
+
+// KZG keys written out and read back are the same keys: a proof under the
+// loaded proving key verifies under the loaded verifying key, and under the
+// original one.
+#[cfg(feature = "kzg")]
+#[test]
+fn kzg_keys() {
+    use fibonacci_variant::kzg::{self, Fr};
+    use halo2_proofs::circuit::Value;
+
+    let params = kzg::setup(K);
+    for recurrence in [Recurrence::Variant, Recurrence::Fibonacci] {
+        let spec = FiboSpec { num: NUM, recurrence, ..FiboSpec::default() };
+        let [a, b, c] = [1, 2, 3].map(Fr::from);
+        let instance = vec![recurrence.terms(a, b, c, NUM)[NUM - 1]];
+        let circuit = FiboCircuit { spec, a: Value::known(a), b: Value::known(b), c: Value::known(c), witness: None };
+        let pk = kzg::keygen(&params, &circuit).unwrap();
+        let (mut vk_bytes, mut pk_bytes) = (vec![], vec![]);
+        kzg::write_verifying_key(pk.get_vk(), &mut vk_bytes).unwrap();
+        kzg::write_proving_key(&pk, &mut pk_bytes).unwrap();
+        let vk = kzg::read_verifying_key(&mut vk_bytes.as_slice(), spec).unwrap();
+        let loaded = kzg::read_proving_key(&mut pk_bytes.as_slice(), spec).unwrap();
+        let mut rewritten = vec![];
+        kzg::write_verifying_key(&vk, &mut rewritten).unwrap();
+        assert_eq!(rewritten, vk_bytes);
+        let proof = kzg::prove(&params, &loaded, &circuit, &instance).unwrap();
+        kzg::verify(&params, &vk, &proof, &instance).unwrap();
+        kzg::verify(&params, pk.get_vk(), &proof, &instance).unwrap();
+        assert!(matches!(kzg::read_verifying_key(&mut &vk_bytes[..8], spec), Err(FibError::Io(_))));
+    }
+    let column = FiboSpec { num: NUM, layout: Layout::Column, ..FiboSpec::default() };
+    assert!(matches!(kzg::read_verifying_key(&mut &[][..], column), Err(FibError::Unsupported(_))));
+}
+
+// The Solidity verifier generates for each output, and its calldata is the
+// public inputs as big-endian words then the proof. A Keccak-transcript
+// proof passes the contract's checks run natively and fails them under a
+// wrong input; a Blake2b one from `kzg::prove` fails them outright.
+#[cfg(feature = "evm")]
+#[test]
+fn evm_verifier() {
+    use fibonacci_variant::evm;
+    use fibonacci_variant::kzg::{self, Fr};
+    use halo2_axiom::halo2curves::ff::PrimeField;
+    use halo2_proofs::circuit::Value;
+
+    let params = kzg::setup(K);
+    for output in [OutputMode::Result, OutputMode::InitialAndResult] {
+        let spec = FiboSpec { num: NUM, output, ..FiboSpec::default() };
+        let [a, b, c] = [1, 2, 3].map(Fr::from);
+        let instance = kzg::instance(&spec, [a, b, c]).unwrap();
+        let circuit = FiboCircuit { spec, a: Value::known(a), b: Value::known(b), c: Value::known(c), witness: None };
+        let pk = kzg::keygen(&params, &circuit).unwrap();
+        let code = evm::solidity_verifier(&params, pk.get_vk(), output.instance_rows());
+        assert!(code.contains("contract Halo2Verifier"));
+
+        let proof = evm::prove(&params, &pk, &circuit, &instance).unwrap();
+        evm::verify(&params, pk.get_vk(), &proof, &instance).unwrap();
+        let mut wrong = instance.clone();
+        *wrong.last_mut().unwrap() += Fr::from(1);
+        assert!(matches!(evm::verify(&params, pk.get_vk(), &proof, &wrong), Err(FibError::ProofVerificationFailed)));
+        let blake2b = kzg::prove(&params, &pk, &circuit, &instance).unwrap();
+        assert!(matches!(evm::verify(&params, pk.get_vk(), &blake2b, &instance), Err(FibError::ProofVerificationFailed)));
+
+        let calldata = evm::calldata(&instance, &proof);
+        assert_eq!(calldata.len(), 32 * instance.len() + proof.len());
+        let mut last = instance[instance.len() - 1].to_repr();
+        last.reverse();
+        assert_eq!(calldata[32 * (instance.len() - 1)..32 * instance.len()], last);
+        assert_eq!(calldata[32 * instance.len()..], proof);
+    }
+}
+
+// A KZG proof round-trips with its header, and `AnyProof` tells it from an
+// IPA one by the scheme byte and verifies each under its own parameters;
+// `Proof` refuses to read it.
+#[cfg(feature = "kzg")]
+#[test]
+fn backend_dispatch() {
+    use fibonacci_variant::backend::{AnyProof, Backend};
+    use fibonacci_variant::kzg::{self, Fr, KzgProof};
+    use halo2_proofs::circuit::Value;
+
+    let dir = std::env::temp_dir().join(format!("fibonacci_variant-backends-{}", std::process::id()));
+    let cache = ParamsCache::new(&dir);
+    let spec = FiboSpec { num: NUM, ..FiboSpec::default() };
+    let params = cache.get_kzg(K).unwrap();
+    let [a, b, c] = [1, 2, 3].map(Fr::from);
+    let instance = kzg::instance(&spec, [a, b, c]).unwrap();
+    let circuit = FiboCircuit { spec, a: Value::known(a), b: Value::known(b), c: Value::known(c), witness: None };
+    let pk = kzg::keygen(&params, &circuit).unwrap();
+    let bytes = kzg::prove(&params, &pk, &circuit, &instance).unwrap();
+    let proof = KzgProof { k: K, spec, instance, bytes, vk_hash: kzg::vk_hash(pk.get_vk()) };
+    assert_eq!(KzgProof::from_hex(&proof.to_hex()).unwrap(), proof);
+    proof.verify(&params).unwrap();
+    assert!(Proof::from_bytes(&proof.to_bytes()).is_err());
+
+    let kzg = AnyProof::from_bytes(&proof.to_bytes()).unwrap();
+    assert_eq!(kzg.backend(), Backend::Kzg);
+    kzg.verify(&cache).unwrap();
+    let ipa_params = cache.get(K).unwrap();
+    let ipa = from_a23(1, NUM, OutputMode::Result);
+    let ipa_pk = prover::keygen(&ipa_params, &ipa).unwrap();
+    let ipa_bytes = prover::prove(&ipa_params, &ipa_pk, &ipa, &[result(1)]).unwrap();
+    let ipa = Proof::new(K, ipa.spec, vec![result(1)], ipa_bytes, VkHash::of(ipa_pk.get_vk()));
+    let ipa = AnyProof::from_hex(&ipa.to_hex()).unwrap();
+    assert_eq!(ipa.backend(), Backend::Ipa);
+    ipa.verify(&cache).unwrap();
+
+    let wrong = KzgProof { instance: vec![Fr::from(1)], ..proof.clone() };
+    assert!(matches!(AnyProof::Kzg(wrong).verify(&cache), Err(FibError::ProofVerificationFailed)));
+    let large = KzgProof { k: kzg::MAX_K + 1, ..proof };
+    assert!(matches!(large.check_shape(), Err(FibError::KTooLarge { .. })));
+    assert_eq!(cache.get_kzg(kzg::MAX_K + 1).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!("kzg".parse::<Backend>().unwrap(), Backend::Kzg);
+    assert!("groth16".parse::<Backend>().is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// A proof made on a one-thread pool is as valid as any, and the pool is the
+// one parallel work inside sees.
+#[test]
+fn thread_pool() {
+    let (circuit, res) = (from_a23(1, NUM, OutputMode::Result), result(1));
+    let params: Params<EqAffine> = Params::new(K);
+    let proof = threads::with_threads(1, || {
+        assert_eq!(threads::current_threads(), 1);
+        prover::prove_spec(&params, &circuit, &[res], TranscriptHash::Blake2b, None).unwrap()
+    })
+    .unwrap();
+    Proof::from(proof).verify(&params).unwrap();
+    let sequences = [([1, 2, 3].map(Fp::from), NUM), ([4, 5, 6].map(Fp::from), NUM)];
+    let parallel = threads::with_threads(2, || fibonacci_variant::sequence::witnesses(&sequences)).unwrap();
+    assert_eq!(parallel[0], witness(Fp::from(1), Fp::from(2), Fp::from(3), NUM));
+}
+
+// Runs appended to one log from several threads all come back whole, and
+// summarize per shape; a line that is not a run names itself.
+#[cfg(feature = "session")]
+#[test]
+fn session_log() {
+    use fibonacci_variant::backend::Backend;
+    use fibonacci_variant::session::{self, ProveStats, Run};
+
+    let dir = std::env::temp_dir().join(format!("fibonacci_variant-session-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let log = dir.join("session.log");
+    let stats = |k, prove_secs| ProveStats {
+        k,
+        num: NUM,
+        proof_bytes: 2048,
+        params_secs: 0.0,
+        witness_secs: 0.0,
+        keygen_secs: 0.5,
+        prove_secs,
+        peak_rss: Some(u64::from(k) << 20),
+    };
+    let spec = FiboSpec { num: NUM, ..FiboSpec::default() };
+    std::thread::scope(|scope| {
+        for i in 0..4 {
+            let (log, spec) = (&log, &spec);
+            scope.spawn(move || session::append(log, &Run::now(Backend::Ipa, spec, stats(K, 1.0 + i as f64))).unwrap());
+        }
+    });
+    session::append(&log, &Run::now(Backend::Ipa, &spec, stats(K + 1, 3.0))).unwrap();
+    let runs = session::read(&log).unwrap();
+    assert_eq!(runs.len(), 5);
+    assert_eq!((runs[0].layout.as_str(), runs[0].output.as_str()), ("rows", "result"));
+
+    let summaries = session::summarize(&runs);
+    assert_eq!(summaries.len(), 2);
+    let (first, second) = (&summaries[0], &summaries[1]);
+    assert_eq!((first.shape.k, first.runs, second.shape.k, second.runs), (K, 4, K + 1, 1));
+    assert_eq!((first.prove_secs.min, first.prove_secs.mean, first.prove_secs.max), (1.0, 2.5, 4.0));
+    assert_eq!((second.last_prove_secs, second.peak_rss), (3.0, Some(u64::from(K + 1) << 20)));
+
+    std::fs::OpenOptions::new().append(true).open(&log).unwrap().write_all(b"\nnot a run\n").unwrap();
+    let err = session::read(&log).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("line 7"), "{}", err);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,73 @@
+//! Property tests: for random initial values and lengths, MockProver accepts
+//! each layout and recurrence against the natively computed result, and
+//! rejects it against any other value.
+
+// Build against halo2_proofs 0.4 under the usual crate name; see `compat`.
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_proofs_0_4 as halo2_proofs;
+
+mod common;
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::pasta::Fp;
+use proptest::prelude::*;
+
+use fibonacci_variant::dispatch::with_circuit;
+use fibonacci_variant::recurrence::Recurrence;
+use fibonacci_variant::{FiboSpec, Layout};
+
+use common::{circuit, Mock};
+
+/// Any field element, with zero and the largest one, `-1`, weighted up.
+fn field() -> impl Strategy<Value = Fp> {
+    let limbs = any::<[u64; 4]>().prop_map(|limbs| {
+        let shift = Fp::from(1 << 32).square();
+        limbs.iter().fold(Fp::ZERO, |acc, &limb| acc * shift + Fp::from(limb))
+    });
+    prop_oneof![
+        1 => Just(Fp::ZERO),
+        1 => Just(-Fp::ONE),
+        8 => limbs,
+    ]
+}
+
+/// The shapes `with_circuit` supports with a plain result.
+fn shape() -> impl Strategy<Value = (Layout, Recurrence)> {
+    prop_oneof![
+        Just((Layout::Rows, Recurrence::Variant)),
+        Just((Layout::Column, Recurrence::Variant)),
+        Just((Layout::Rows, Recurrence::Fibonacci)),
+    ]
+}
+
+/// Whether MockProver accepts the circuit for `a, b, c, num` exposing `claim`.
+fn accepts((layout, recurrence): (Layout, Recurrence), [a, b, c]: [Fp; 3], num: usize, claim: Fp) -> bool {
+    let spec = FiboSpec { num, layout, recurrence, ..FiboSpec::default() };
+    let prover = with_circuit(circuit(spec, [a, b, c]), Mock(spec.min_k(), vec![claim]))
+        .expect("the shape is supported")
+        .expect("the circuit fits its smallest k");
+    prover.verify().is_ok()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn accepts_the_native_result(shape in shape(), a in field(), b in field(), c in field(), num in 1usize..64) {
+        let result = shape.1.terms(a, b, c, num)[num - 1];
+        prop_assert!(accepts(shape, [a, b, c], num, result));
+    }
+
+    #[test]
+    fn rejects_any_other_result(
+        shape in shape(),
+        a in field(),
+        b in field(),
+        c in field(),
+        num in 1usize..64,
+        delta in field().prop_filter("a perturbation", |delta| *delta != Fp::ZERO),
+    ) {
+        let result = shape.1.terms(a, b, c, num)[num - 1];
+        prop_assert!(!accepts(shape, [a, b, c], num, result + delta));
+    }
+}
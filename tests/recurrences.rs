@@ -0,0 +1,120 @@
+//! The same chip over other recurrences: one defined here through the
+//! trait, plain Fibonacci, and coefficients in fixed columns.
+
+// Build against halo2_proofs 0.4 under the usual crate name; see `compat`.
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_proofs_0_4 as halo2_proofs;
+
+mod common;
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{keygen_vk, Circuit, Expression};
+use halo2_proofs::poly::commitment::Params;
+
+use fibonacci_variant::fingerprint::VkHash;
+use fibonacci_variant::prover;
+use fibonacci_variant::recurrence::{
+    recurrence_witness, Fibonacci, Recurrence, RecurrenceCircuit, RecurrenceGate, Variant,
+};
+use fibonacci_variant::weighted::{Coefficients, WeightedCircuit};
+use fibonacci_variant::{witness, FiboCircuit, FiboSpec, Proof};
+
+use common::{from_123, prove_with, K, NUM};
+
+fn result() -> Fp {
+    witness(Fp::from(1), Fp::from(2), Fp::from(3), NUM)[NUM - 1]
+}
+
+struct MulAdd;
+
+impl RecurrenceGate<Fp> for MulAdd {
+    const NAME: &'static str = "a * b + c";
+
+    fn expression(a: Expression<Fp>, b: Expression<Fp>, c: Expression<Fp>) -> Expression<Fp> {
+        a * b + c
+    }
+
+    fn next(a: Fp, b: Fp, c: Fp) -> Fp {
+        a * b + c
+    }
+}
+
+#[test]
+fn different_recurrence() {
+    let mul_add = RecurrenceCircuit::<_, MulAdd>::new(from_123(FiboSpec { num: NUM, ..FiboSpec::default() }));
+    let mut expected = vec![1, 2, 3].into_iter().map(Fp::from).collect::<Vec<_>>();
+    for i in 3..NUM {
+        expected.push(expected[i - 3] * expected[i - 2] + expected[i - 1]);
+    }
+    let terms_mul_add = recurrence_witness::<_, MulAdd>(mul_add.0.a, mul_add.0.b, mul_add.0.c, NUM);
+    for (term, expected) in terms_mul_add.iter().zip(&expected) {
+        term.assert_if_known(|term| term == expected);
+    }
+    let prover = MockProver::run(K, &mul_add, vec![vec![expected[NUM - 1]]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(K, &mul_add, vec![vec![result()]]).unwrap();
+    assert!(prover.verify().is_err());
+    // the variant through the trait is the same circuit as `FiboCircuit`.
+    let params: Params<EqAffine> = Params::new(K);
+    let variant = RecurrenceCircuit::<_, Variant>::new(mul_add.0.clone());
+    let vk_variant = keygen_vk(&params, &variant).unwrap();
+    let vk_fibo = keygen_vk(&params, &variant.0).unwrap();
+    assert_eq!(format!("{:?}", vk_variant.pinned()), format!("{:?}", vk_fibo.pinned()));
+}
+
+// The classic sequence on the same chip.
+#[test]
+fn plain_fibonacci() {
+    let fib = Recurrence::Fibonacci.terms(Fp::from(0), Fp::from(1), Fp::from(1), 20);
+    assert_eq!(fib[19], Fp::from(4181));
+    let circuit = RecurrenceCircuit::<_, Fibonacci>::new(FiboCircuit {
+        spec: FiboSpec { num: 20, recurrence: Recurrence::Fibonacci, ..FiboSpec::default() },
+        a: Value::known(Fp::from(0)),
+        b: Value::known(Fp::from(1)),
+        c: Value::known(Fp::from(1)),
+        witness: None,
+    });
+    let prover = MockProver::run(K, &circuit, vec![vec![fib[19]]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(K, &circuit, vec![vec![fib[18]]]).unwrap();
+    assert!(prover.verify().is_err());
+    assert!(MockProver::run(K, &circuit.0, vec![vec![fib[19]]]).is_err());
+    let params: Params<EqAffine> = Params::new(K);
+    let bytes = prove_with(&params, &circuit, &[fib[19]]).unwrap();
+    let vk_hash = VkHash::of(&keygen_vk(&params, &circuit).unwrap());
+    let proof = Proof::new(K, circuit.0.spec, vec![fib[19]], bytes, vk_hash);
+    Proof::from_hex(&proof.to_hex()).unwrap().verify(&params).unwrap();
+    let as_variant = Proof { spec: FiboSpec { recurrence: Recurrence::Variant, ..proof.spec }, ..proof };
+    assert!(as_variant.verify(&params).is_err());
+}
+
+// Coefficients in fixed columns, one key per choice.
+#[test]
+fn weighted_recurrence() {
+    let weighted = |alpha: u64, beta: u64, gamma: u64| WeightedCircuit {
+        coefficients: Coefficients { alpha: Fp::from(alpha), beta: Fp::from(beta), gamma: Fp::from(gamma) },
+        circuit: from_123(FiboSpec { num: NUM, ..FiboSpec::default() }),
+    };
+    let ones = weighted(1, 1, 1);
+    let prover = MockProver::run(K, &ones, vec![vec![result()]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let circuit = weighted(2, 3, 5);
+    let mut expected = [1, 2, 3].map(Fp::from).to_vec();
+    for i in 3..NUM {
+        let (a, b, c) = (expected[i - 3], expected[i - 2], expected[i - 1]);
+        expected.push((Fp::from(2) * a + Fp::from(5) * c) * (Fp::from(3) * b));
+    }
+    let weighted_result = expected[NUM - 1];
+    let prover = MockProver::run(K, &circuit, vec![vec![weighted_result]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(K, &circuit, vec![vec![result()]]).unwrap();
+    assert!(prover.verify().is_err());
+    let params: Params<EqAffine> = Params::new(K);
+    let proof = prove_with(&params, &circuit, &[weighted_result]).unwrap();
+    let vk = keygen_vk(&params, &circuit.without_witnesses()).unwrap();
+    prover::verify(&params, &vk, &proof, &[weighted_result]).unwrap();
+    let vk_ones = keygen_vk(&params, &ones.without_witnesses()).unwrap();
+    assert!(prover::verify(&params, &vk_ones, &proof, &[weighted_result]).is_err());
+}
@@ -0,0 +1,81 @@
+//! Soundness against a malicious prover: corrupting any single term of the
+//! sequence, kept consistent in every cell holding it, must fail the step
+//! gate first at the row reading that term, for every term and layout.
+
+// Build against halo2_proofs 0.4 under the usual crate name; see `compat`.
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_proofs_0_4 as halo2_proofs;
+
+mod common;
+
+use halo2_proofs::dev::{FailureLocation, VerifyFailure};
+use halo2_proofs::pasta::Fp;
+
+use fibonacci_variant::dispatch::with_circuit;
+use fibonacci_variant::malicious::{step_gate, WitnessOverride};
+use fibonacci_variant::{witness, FiboCircuit, FiboSpec, Layout};
+
+use common::{from_123, Mock};
+
+const NUM: usize = 12;
+
+
+fn honest(layout: Layout) -> FiboCircuit<Fp> {
+    from_123(FiboSpec { num: NUM, layout, ..FiboSpec::default() })
+}
+
+/// The rows at which `layout`'s step gate fails for `circuit`, in order.
+fn failing_steps(layout: Layout, circuit: FiboCircuit<Fp>, result: Fp) -> Vec<usize> {
+    let k = circuit.spec.min_k();
+    let prover = with_circuit(circuit, Mock(k, vec![result])).unwrap().unwrap();
+    let gate = format!("('{}')", step_gate(layout));
+    let mut rows: Vec<usize> = prover
+        .verify()
+        .err()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|failure| match failure {
+            VerifyFailure::ConstraintNotSatisfied {
+                constraint,
+                location: FailureLocation::InRegion { offset, .. },
+                ..
+            } if constraint.to_string().ends_with(&gate) => Some(offset),
+            _ => None,
+        })
+        .collect();
+    rows.sort_unstable();
+    rows
+}
+
+#[test]
+fn the_honest_witness_passes() {
+    let result = witness(Fp::from(1), Fp::from(2), Fp::from(3), NUM)[NUM - 1];
+    for layout in [Layout::Rows, Layout::Column] {
+        assert_eq!(failing_steps(layout, honest(layout), result), Vec::<usize>::new());
+    }
+}
+
+#[test]
+fn every_corrupted_term_fails_at_its_step() {
+    let terms = witness(Fp::from(1), Fp::from(2), Fp::from(3), NUM);
+    for layout in [Layout::Rows, Layout::Column] {
+        for term in 0..NUM {
+            for value in [terms[term] + Fp::from(1), Fp::from(0)] {
+                if value == terms[term] {
+                    continue;
+                }
+                let tamper = WitnessOverride { term, value };
+                let rows = failing_steps(layout, tamper.apply(&honest(layout)), terms[NUM - 1]);
+                assert_eq!(
+                    rows.first(),
+                    Some(&tamper.first_row(layout)),
+                    "{:?} layout, term {} set to {:?}: step gate failures at {:?}",
+                    layout,
+                    term,
+                    value,
+                    rows
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! Other statements about the sequence: a term at a public index, some
+//! term without saying which, the result or the initial values behind a
+//! hash, a Merkle root of the terms, several sequences in one proof, and
+//! initial values fixed by the key.
+
+// Build against halo2_proofs 0.4 under the usual crate name; see `compat`.
+#[cfg(feature = "halo2-0-4")]
+extern crate halo2_proofs_0_4 as halo2_proofs;
+
+mod common;
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::dev::{FailureLocation, MockProver, VerifyFailure};
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{keygen_vk, Any, Circuit};
+use halo2_proofs::poly::commitment::Params;
+
+use fibonacci_variant::batch::BatchFiboCircuit;
+use fibonacci_variant::constant::FiboCircuitConstant;
+use fibonacci_variant::fingerprint::VkHash;
+use fibonacci_variant::hashed::{commit_initial, hash_result, FiboCircuitCommitted, FiboCircuitHashed};
+use fibonacci_variant::indexed::FiboCircuitIndexed;
+use fibonacci_variant::membership::FiboCircuitMembership;
+use fibonacci_variant::merkle::{merkle_path, merkle_root, FiboCircuitMerkle};
+use fibonacci_variant::prover;
+use fibonacci_variant::row_usage::RowUsage;
+use fibonacci_variant::sequence::witnesses;
+use fibonacci_variant::{witness, FiboSpec, OutputMode, Proof};
+
+use common::{from_a23, prove_with, terms, K, NUM};
+
+// Any term, with its index public.
+#[test]
+fn term_at_a_public_index() {
+    let terms = terms();
+    let indexed = |index| FiboCircuitIndexed {
+        num: NUM,
+        index: Value::known(index),
+        a: Value::known(Fp::from(1)),
+        b: Value::known(Fp::from(2)),
+        c: Value::known(Fp::from(3)),
+    };
+    let public = |term, index: usize| vec![term, Fp::from(index as u64), Fp::from(1), Fp::from(2), Fp::from(3)];
+    let params: Params<EqAffine> = Params::new(K);
+    let vk = keygen_vk(&params, &indexed(0).without_witnesses()).unwrap();
+    for index in [0, 2, 3, 7, NUM - 1] {
+        let prover = MockProver::run(K, &indexed(index), vec![public(terms[index], index)]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(K, &indexed(index), vec![public(terms[index], index + 1)]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+    // past the end there is no term, not a zero one.
+    let prover = MockProver::run(K, &indexed(NUM), vec![public(Fp::from(0), NUM)]).unwrap();
+    assert!(prover.verify().is_err());
+    // a start chosen so the term at the index is a value of the prover's
+    // liking is not the start the instance names.
+    let forged = FiboCircuitIndexed { a: Value::known(Fp::from(9)), ..indexed(0) };
+    let prover = MockProver::run(K, &forged, vec![public(Fp::from(9), 0)]).unwrap();
+    let failures = prover.verify().unwrap_err();
+    assert!(failures.contains(&VerifyFailure::Permutation {
+        column: (Any::Advice, 0).into(),
+        location: FailureLocation::InRegion { region: (0, "indexed rows").into(), offset: 0 },
+    }));
+    let pk = prover::keygen(&params, &indexed(0)).unwrap();
+    let proof = prover::prove(&params, &pk, &indexed(7), &public(terms[7], 7)).unwrap();
+    prover::verify(&params, &vk, &proof, &public(terms[7], 7)).unwrap();
+    assert!(prover::verify(&params, &vk, &proof, &public(terms[7], 8)).is_err());
+    let mut restarted = public(terms[7], 7);
+    restarted[2] = Fp::from(9);
+    assert!(prover::verify(&params, &vk, &proof, &restarted).is_err());
+}
+
+// Some term is the value, without saying which.
+#[test]
+fn sequence_membership() {
+    let terms = terms();
+    let res = terms[NUM - 1];
+    let member = |value| FiboCircuitMembership {
+        num: NUM,
+        value: Value::known(value),
+        a: Value::known(Fp::from(1)),
+        b: Value::known(Fp::from(2)),
+        c: Value::known(Fp::from(3)),
+    };
+    let public = |value| vec![value, Fp::from(1), Fp::from(2), Fp::from(3)];
+    for value in [terms[0], terms[5], res] {
+        let prover = MockProver::run(K, &member(value), vec![public(value)]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+    let prover = MockProver::run(K, &member(Fp::from(4)), vec![public(Fp::from(4))]).unwrap();
+    assert!(prover.verify().is_err());
+    let prover = MockProver::run(K, &member(terms[5]), vec![public(terms[6])]).unwrap();
+    assert!(prover.verify().is_err());
+    // a sequence started at the value contains it, but is not the one the
+    // instance names.
+    let forged = FiboCircuitMembership { a: Value::known(Fp::from(4)), ..member(Fp::from(4)) };
+    let honest = vec![4, 4, 2, 3].into_iter().map(Fp::from).collect();
+    assert_eq!(MockProver::run(K, &forged, vec![honest]).unwrap().verify(), Ok(()));
+    let prover = MockProver::run(K, &forged, vec![public(Fp::from(4))]).unwrap();
+    let failures = prover.verify().unwrap_err();
+    assert!(failures.contains(&VerifyFailure::Permutation {
+        column: (Any::Advice, 0).into(),
+        location: FailureLocation::InRegion { region: (0, "membership rows").into(), offset: 0 },
+    }));
+    let params: Params<EqAffine> = Params::new(K);
+    let pk = prover::keygen(&params, &member(res)).unwrap();
+    let proof = prover::prove(&params, &pk, &member(terms[5]), &public(terms[5])).unwrap();
+    prover::verify(&params, pk.get_vk(), &proof, &public(terms[5])).unwrap();
+    assert!(prover::verify(&params, pk.get_vk(), &proof, &[terms[5], Fp::from(4), Fp::from(2), Fp::from(3)]).is_err());
+}
+
+// The result hidden behind its hash.
+#[test]
+fn hashed_result() {
+    let res = terms()[NUM - 1];
+    let hashed = FiboCircuitHashed(from_a23(1, NUM, OutputMode::Hashed));
+    let spec = hashed.0.spec;
+    let usage = RowUsage::measure(&hashed).unwrap();
+    assert_eq!(usage.total_rows, spec.rows());
+    let k = spec.min_k();
+    let prover = MockProver::run(k, &hashed, vec![vec![hash_result(res)]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(k, &hashed, vec![vec![res]]).unwrap();
+    assert!(prover.verify().is_err());
+    assert!(MockProver::run(k, &hashed.0, vec![vec![hash_result(res)]]).is_err());
+    let params: Params<EqAffine> = Params::new(k);
+    let bytes = prove_with(&params, &hashed, &[hash_result(res)]).unwrap();
+    let vk_hash = VkHash::of(&keygen_vk(&params, &hashed).unwrap());
+    let proof = Proof::new(k, spec, vec![hash_result(res)], bytes, vk_hash);
+    Proof::from_bytes(&proof.to_bytes()).unwrap().verify(&params).unwrap();
+}
+
+// The initial values hidden behind a commitment.
+#[test]
+fn committed_initial_values() {
+    let res = terms()[NUM - 1];
+    let blinding = Fp::from(0x5eed);
+    let committed = FiboCircuitCommitted {
+        circuit: from_a23(1, NUM, OutputMode::Committed),
+        blinding: Value::known(blinding),
+    };
+    let spec = committed.circuit.spec;
+    let usage = RowUsage::measure(&committed).unwrap();
+    assert_eq!(usage.total_rows, spec.rows());
+    let commitment = commit_initial(Fp::from(1), Fp::from(2), Fp::from(3), blinding);
+    let k = spec.min_k();
+    let prover = MockProver::run(k, &committed, vec![vec![commitment, res]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let wrong = commit_initial(Fp::from(1), Fp::from(2), Fp::from(4), blinding);
+    let prover = MockProver::run(k, &committed, vec![vec![wrong, res]]).unwrap();
+    assert!(prover.verify().is_err());
+    let prover = MockProver::run(k, &committed, vec![vec![commitment, res + Fp::from(1)]]).unwrap();
+    assert!(prover.verify().is_err());
+    let params: Params<EqAffine> = Params::new(k);
+    let instance = vec![commitment, res];
+    let bytes = prove_with(&params, &committed, &instance).unwrap();
+    let vk_hash = VkHash::of(&keygen_vk(&params, &committed).unwrap());
+    let proof = Proof::new(k, spec, instance, bytes, vk_hash);
+    Proof::from_bytes(&proof.to_bytes()).unwrap().verify(&params).unwrap();
+}
+
+// A Merkle root over every term, opened on the host.
+#[test]
+fn merkle_root_of_the_terms() {
+    let terms = terms();
+    let merkle = FiboCircuitMerkle(from_a23(1, NUM, OutputMode::MerkleRoot));
+    let spec = merkle.0.spec;
+    let usage = RowUsage::measure(&merkle).unwrap();
+    assert_eq!(usage.total_rows, spec.rows());
+    let root = merkle_root(&terms);
+    for (index, &term) in terms.iter().enumerate() {
+        let path = merkle_path(&terms, index).unwrap();
+        assert!(path.verify(term, root));
+        assert!(!path.verify(term + Fp::from(1), root));
+    }
+    assert!(merkle_path(&terms, NUM).is_none());
+    let k = spec.min_k();
+    let prover = MockProver::run(k, &merkle, vec![vec![root]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(k, &merkle, vec![vec![merkle_root(&terms[1..])]]).unwrap();
+    assert!(prover.verify().is_err());
+    let params: Params<EqAffine> = Params::new(k);
+    let bytes = prove_with(&params, &merkle, &[root]).unwrap();
+    let vk_hash = VkHash::of(&keygen_vk(&params, &merkle).unwrap());
+    let proof = Proof::new(k, spec, vec![root], bytes, vk_hash);
+    Proof::from_bytes(&proof.to_bytes()).unwrap().verify(&params).unwrap();
+}
+
+// Independent sequences side by side in one proof.
+#[test]
+fn batch_of_sequences() {
+    let batch = BatchFiboCircuit {
+        circuits: vec![
+            from_a23(1, NUM, OutputMode::Result),
+            from_a23(4, 7, OutputMode::Continuation),
+            from_a23(5, 12, OutputMode::InitialAndResult),
+        ],
+    };
+    assert_eq!(batch.instance_offsets(), [0, 1, 5]);
+    let usage = RowUsage::measure(&batch).unwrap();
+    assert_eq!(usage.total_rows, batch.rows());
+    let seq7 = witness(Fp::from(4), Fp::from(2), Fp::from(3), 7);
+    let seq12 = witness(Fp::from(5), Fp::from(2), Fp::from(3), 12);
+    let mut instance = vec![terms()[NUM - 1]];
+    instance.extend([seq7[4], seq7[5], seq7[6], Fp::from(7)]);
+    instance.extend([Fp::from(5), Fp::from(2), Fp::from(3), seq12[11]]);
+    let prover = MockProver::run(batch.min_k(), &batch, vec![instance.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let mut wrong = instance.clone();
+    wrong[5] += Fp::from(1);
+    let prover = MockProver::run(batch.min_k(), &batch, vec![wrong]).unwrap();
+    assert!(prover.verify().is_err());
+    let params: Params<EqAffine> = Params::new(batch.min_k());
+    let pk = prover::keygen(&params, &batch.without_witnesses()).unwrap();
+    let proof = prover::prove(&params, &pk, &batch, &instance).unwrap();
+    prover::verify(&params, pk.get_vk(), &proof, &instance).unwrap();
+    let batch = BatchFiboCircuit {
+        circuits: (0..100).map(|a| from_a23(a, NUM, OutputMode::Result)).collect(),
+    };
+    let instance: Vec<_> = (0..100).map(|a| witness(Fp::from(a), Fp::from(2), Fp::from(3), NUM)[NUM - 1]).collect();
+    let prover = MockProver::run(batch.min_k(), &batch, vec![instance.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    // the members' witnesses computed up front, in parallel with `multicore`.
+    let sequences: Vec<_> = (0..100).map(|a| ([Fp::from(a), Fp::from(2), Fp::from(3)], NUM + a as usize)).collect();
+    for (terms, &([a, b, c], num)) in witnesses(&sequences).iter().zip(&sequences) {
+        assert_eq!(*terms, witness(a, b, c, num));
+    }
+    let precomputed = batch.with_witnesses();
+    assert!(precomputed.circuits.iter().all(|circuit| circuit.witness.is_some()));
+    let prover = MockProver::run(precomputed.min_k(), &precomputed, vec![instance]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    // one-term members expose four values each from a single row, so the
+    // instance column sets `k`, not the layout.
+    let batch = BatchFiboCircuit {
+        circuits: (0..20).map(|a| from_a23(a, 1, OutputMode::InitialAndResult)).collect(),
+    };
+    assert_eq!((batch.rows(), batch.instance_rows()), (20, 80));
+    let instance: Vec<_> = (0..20).flat_map(|a| [a, 2, 3, a].map(Fp::from)).collect();
+    let prover = MockProver::run(batch.min_k(), &batch, vec![instance]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+// Initial values as constants in the key, whatever the prover claims.
+#[test]
+fn constant_initial_values() {
+    let initial = [1, 2, 3].map(Fp::from);
+    for num in [1, 4, NUM] {
+        let circuit = FiboCircuitConstant::new(FiboSpec { num, ..FiboSpec::default() }, initial);
+        let result = witness(initial[0], initial[1], initial[2], num)[num - 1];
+        let prover = MockProver::run(circuit.min_k(), &circuit, vec![vec![result]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+    // a prover starting from 5 instead of 1, consistently.
+    let honest = FiboCircuitConstant::new(FiboSpec { num: NUM, ..FiboSpec::default() }, initial);
+    let forged = FiboCircuitConstant { circuit: from_a23(5, NUM, OutputMode::Result), ..honest.clone() };
+    let forged_result = witness(Fp::from(5), Fp::from(2), Fp::from(3), NUM)[NUM - 1];
+    let prover = MockProver::run(K, &forged, vec![vec![forged_result]]).unwrap();
+    let failures = prover.verify().unwrap_err();
+    assert!(failures.contains(&VerifyFailure::Permutation {
+        column: (Any::Advice, 0).into(),
+        location: FailureLocation::InRegion { region: (0, "sequence").into(), offset: 0 },
+    }));
+    // keys are per choice of constants.
+    let params: Params<EqAffine> = Params::new(K);
+    let vk = keygen_vk(&params, &honest.without_witnesses()).unwrap();
+    let other = FiboCircuitConstant::new(honest.circuit.spec, [1, 2, 4].map(Fp::from));
+    let vk_other = keygen_vk(&params, &other.without_witnesses()).unwrap();
+    assert_ne!(format!("{:?}", vk.pinned()), format!("{:?}", vk_other.pinned()));
+    let res = terms()[NUM - 1];
+    let proof = prove_with(&params, &honest, &[res]).unwrap();
+    prover::verify(&params, &vk, &proof, &[res]).unwrap();
+    assert!(prover::verify(&params, &vk_other, &proof, &[res]).is_err());
+}